@@ -1,7 +1,122 @@
+pub(crate) mod append;
+pub(crate) mod bf_add;
+pub(crate) mod bf_exists;
+pub(crate) mod bf_reserve;
+pub(crate) mod bitcount;
+pub(crate) mod bitfield;
+pub(crate) mod bitop;
+pub(crate) mod bitpos;
+pub(crate) mod blmove;
+pub(crate) mod bpop;
+pub(crate) mod cf_add;
+pub(crate) mod cf_del;
+pub(crate) mod cf_exists;
+pub(crate) mod cf_reserve;
+pub(crate) mod cms_incrby;
+pub(crate) mod cms_initbydim;
+pub(crate) mod cms_query;
+pub(crate) mod copy;
+pub(crate) mod dbsize;
 pub(crate) mod del;
+pub(crate) mod exists;
+pub(crate) mod expire;
+pub(crate) mod expiretime;
+pub(crate) mod flush;
+pub(crate) mod ft_create;
+pub(crate) mod ft_dropindex;
+pub(crate) mod ft_search;
+pub(crate) mod geoadd;
+pub(crate) mod geodist;
+pub(crate) mod geohash;
+pub(crate) mod geopos;
+pub(crate) mod geosearch;
 pub(crate) mod get;
+pub(crate) mod getbit;
+pub(crate) mod getdel;
+pub(crate) mod getex;
+pub(crate) mod getrange;
+pub(crate) mod getset;
+pub(crate) mod hdel;
+pub(crate) mod hexpire;
+pub(crate) mod hget;
+pub(crate) mod hgetall;
+pub(crate) mod hincrby;
+pub(crate) mod hmget;
+pub(crate) mod hpersist;
+pub(crate) mod hscan;
+pub(crate) mod hset;
+pub(crate) mod httl;
+pub(crate) mod incr;
+pub(crate) mod incrbyfloat;
+pub(crate) mod json_arrappend;
+pub(crate) mod json_del;
+pub(crate) mod json_get;
+pub(crate) mod json_numincrby;
+pub(crate) mod json_set;
+pub(crate) mod key_type;
+pub(crate) mod keys;
+pub(crate) mod lindex;
+pub(crate) mod llen;
+pub(crate) mod lrange;
+pub(crate) mod lrem;
+pub(crate) mod lset;
+pub(crate) mod ltrim;
+pub(crate) mod mget;
+pub(crate) mod move_key;
+pub(crate) mod mset;
+pub(crate) mod persist;
+pub(crate) mod pfadd;
+pub(crate) mod pfcount;
+pub(crate) mod pfmerge;
 pub(crate) mod ping;
+pub(crate) mod pop;
 pub(crate) mod publish;
+pub(crate) mod push;
+pub(crate) mod randomkey;
+pub(crate) mod rename;
+pub(crate) mod sadd;
+pub(crate) mod scan;
+pub(crate) mod scard;
+pub(crate) mod select;
 pub(crate) mod set;
+pub(crate) mod setbit;
+pub(crate) mod setop;
+pub(crate) mod setrange;
+pub(crate) mod sismember;
+pub(crate) mod smembers;
+pub(crate) mod srem;
+pub(crate) mod sscan;
+pub(crate) mod strlen;
 pub(crate) mod subscribe;
+pub(crate) mod swapdb;
+pub(crate) mod topk_add;
+pub(crate) mod topk_list;
+pub(crate) mod topk_reserve;
+pub(crate) mod ts_add;
+pub(crate) mod ts_create;
+pub(crate) mod ts_createrule;
+pub(crate) mod ts_mrange;
+pub(crate) mod ts_range;
+pub(crate) mod ttl;
 pub(crate) mod unknown;
+pub(crate) mod vindex_add;
+pub(crate) mod vindex_create;
+pub(crate) mod vindex_del;
+pub(crate) mod vindex_knn;
+pub(crate) mod xack;
+pub(crate) mod xadd;
+pub(crate) mod xautoclaim;
+pub(crate) mod xclaim;
+pub(crate) mod xgroup;
+pub(crate) mod xlen;
+pub(crate) mod xpending;
+pub(crate) mod xrange;
+pub(crate) mod xreadgroup;
+pub(crate) mod xtrim;
+pub(crate) mod zadd;
+pub(crate) mod zincrby;
+pub(crate) mod zrange;
+pub(crate) mod zrangebyscore;
+pub(crate) mod zrank;
+pub(crate) mod zrem;
+pub(crate) mod zscan;
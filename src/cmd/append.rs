@@ -0,0 +1,33 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Append {
+    key: Entity,
+    value: Bytes,
+}
+
+impl Append {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Append, CacheError> {
+        let key = parse.next()?;
+        let value = parse.next_bytes()?;
+        Ok(Append { key, value })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.append(self.key, self.value).await {
+            Ok(len) => Entity::Integer(len as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct BfAdd {
+    key: Entity,
+    item: Bytes,
+}
+
+impl BfAdd {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<BfAdd, CacheError> {
+        let key = parse.next()?;
+        let item = parse.next_bytes()?;
+        Ok(BfAdd { key, item })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.bf_add(self.key, &self.item).await {
+            Ok(added) => Entity::Integer(added as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use tracing::debug;
+
+use crate::{
+    cmd::zadd::next_score,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, bloom::BloomParams, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct BfReserve {
+    key: Entity,
+    params: BloomParams,
+}
+
+impl BfReserve {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<BfReserve, CacheError> {
+        let key = parse.next()?;
+        let error_rate = next_score(&parse.next_bytes()?)?;
+        if !(error_rate > 0.0 && error_rate < 1.0) {
+            return Err("ERR (0 < error rate range < 1)".into());
+        }
+        let capacity = match parse.next_int()? {
+            capacity if capacity > 0 => capacity as u64,
+            _ => return Err("ERR (capacity should be larger than 0)".into()),
+        };
+
+        let mut params = BloomParams {
+            error_rate,
+            capacity,
+            ..BloomParams::default()
+        };
+        let mut non_scaling = false;
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "EXPANSION" => {
+                    params.expansion = Some(parse_expansion(parse)?);
+                }
+                Ok(s) if s.to_uppercase() == "NONSCALING" => non_scaling = true,
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        if non_scaling {
+            params.expansion = None;
+        }
+
+        Ok(BfReserve { key, params })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.bf_reserve(self.key, self.params).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Reads a strictly positive `EXPANSION` factor.
+fn parse_expansion(parse: &mut Parse) -> Result<u32, CacheError> {
+    match parse.next_int()? {
+        expansion if expansion > 0 && expansion <= u32::MAX as i64 => Ok(expansion as u32),
+        _ => Err("ERR expansion should be greater or equal to 1".into()),
+    }
+}
@@ -0,0 +1,65 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        bitmap::{BitRange, BitUnit},
+        entity::Entity,
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct BitCount {
+    key: Entity,
+    range: Option<BitRange>,
+}
+
+impl BitCount {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<BitCount, CacheError> {
+        let key = parse.next()?;
+        let range = parse_bit_range(parse)?;
+        if range.is_some_and(|range| range.end.is_none()) {
+            return Err("ERR syntax error".into());
+        }
+        Ok(BitCount { key, range })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.bitcount(&self.key, self.range).await {
+            Ok(count) => Entity::Integer(count as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Parses the optional `[start [end [BYTE | BIT]]]` tail of `BITCOUNT` and
+/// `BITPOS`.
+pub(crate) fn parse_bit_range(parse: &mut Parse) -> Result<Option<BitRange>, CacheError> {
+    let start = match parse.next_int() {
+        Ok(start) => start,
+        Err(CacheError::EndOfStream) => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let end = match parse.next_int() {
+        Ok(end) => Some(end),
+        Err(CacheError::EndOfStream) => None,
+        Err(err) => return Err(err),
+    };
+    let unit = match parse.next_string() {
+        Ok(unit) if end.is_some() => match &unit.to_uppercase()[..] {
+            "BYTE" => BitUnit::Byte,
+            "BIT" => BitUnit::Bit,
+            _ => return Err("ERR syntax error".into()),
+        },
+        Ok(_) => return Err("ERR syntax error".into()),
+        Err(CacheError::EndOfStream) => BitUnit::Byte,
+        Err(err) => return Err(err),
+    };
+    Ok(Some(BitRange { start, end, unit }))
+}
@@ -0,0 +1,104 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        bitmap::{FieldOp, FieldType, MAX_BIT_OFFSET, Overflow},
+        entity::Entity,
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct BitField {
+    key: Entity,
+    ops: Vec<FieldOp>,
+}
+
+impl BitField {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<BitField, CacheError> {
+        let key = parse.next()?;
+
+        let mut ops = vec![];
+        let mut overflow = Overflow::Wrap;
+        loop {
+            let subcommand = match parse.next_string() {
+                Ok(subcommand) => subcommand.to_uppercase(),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            };
+            match &subcommand[..] {
+                "GET" => {
+                    let (ty, offset) = parse_field(parse)?;
+                    ops.push(FieldOp::Get(ty, offset));
+                }
+                "SET" => {
+                    let (ty, offset) = parse_field(parse)?;
+                    ops.push(FieldOp::Set(ty, offset, parse.next_int()?, overflow));
+                }
+                "INCRBY" => {
+                    let (ty, offset) = parse_field(parse)?;
+                    ops.push(FieldOp::IncrBy(ty, offset, parse.next_int()?, overflow));
+                }
+                "OVERFLOW" => {
+                    overflow = match &parse.next_string()?.to_uppercase()[..] {
+                        "WRAP" => Overflow::Wrap,
+                        "SAT" => Overflow::Sat,
+                        "FAIL" => Overflow::Fail,
+                        _ => return Err("ERR Invalid OVERFLOW type specified".into()),
+                    };
+                }
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+        Ok(BitField { key, ops })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.bitfield(self.key, &self.ops).await {
+            Ok(results) => Entity::Array(
+                results
+                    .into_iter()
+                    .map(|result| result.map(Entity::Integer).unwrap_or(Entity::Null))
+                    .collect(),
+            ),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Parses `type offset`, where an offset prefixed with `#` counts in units of
+/// the type's width.
+fn parse_field(parse: &mut Parse) -> Result<(FieldType, u64), CacheError> {
+    let ty = FieldType::parse(&parse.next_bytes()?).ok_or(
+        "ERR Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.",
+    )?;
+    let raw: Bytes = parse.next_bytes()?;
+    let (scaled, digits) = match raw.split_first() {
+        Some((b'#', digits)) => (true, digits),
+        _ => (false, &raw[..]),
+    };
+    let offset = str::from_utf8(digits)
+        .ok()
+        .and_then(|digits| digits.parse::<u64>().ok())
+        .and_then(|offset| {
+            if scaled {
+                offset.checked_mul(ty.bits as u64)
+            } else {
+                Some(offset)
+            }
+        })
+        .filter(|offset| {
+            offset
+                .checked_add(ty.bits as u64 - 1)
+                .is_some_and(|last| last <= MAX_BIT_OFFSET)
+        })
+        .ok_or("ERR bit offset is not an integer or out of range")?;
+    Ok((ty, offset))
+}
@@ -0,0 +1,48 @@
+use tracing::debug;
+
+use crate::{
+    cmd::setop::parse_keys,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, bitmap::BitOperation, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct BitOp {
+    operation: BitOperation,
+    destination: Entity,
+    keys: Vec<Entity>,
+}
+
+impl BitOp {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<BitOp, CacheError> {
+        let operation = match &parse.next_string()?.to_uppercase()[..] {
+            "AND" => BitOperation::And,
+            "OR" => BitOperation::Or,
+            "XOR" => BitOperation::Xor,
+            "NOT" => BitOperation::Not,
+            _ => return Err("ERR syntax error".into()),
+        };
+        let destination = parse.next()?;
+        let keys = parse_keys(parse)?;
+        if operation == BitOperation::Not && keys.len() != 1 {
+            return Err("ERR BITOP NOT must be called with a single source key.".into());
+        }
+        Ok(BitOp {
+            operation,
+            destination,
+            keys,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.bitop(self.operation, self.destination, &self.keys).await {
+            Ok(len) => Entity::Integer(len as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use tracing::debug;
+
+use crate::{
+    cmd::bitcount::parse_bit_range,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, bitmap::BitRange, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct BitPos {
+    key: Entity,
+    bit: bool,
+    range: Option<BitRange>,
+}
+
+impl BitPos {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<BitPos, CacheError> {
+        let key = parse.next()?;
+        let bit = match parse.next_int()? {
+            0 => false,
+            1 => true,
+            _ => return Err("ERR The bit argument must be 1 or 0.".into()),
+        };
+        let range = parse_bit_range(parse)?;
+        Ok(BitPos { key, bit, range })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.bitpos(&self.key, self.bit, self.range).await {
+            Ok(pos) => Entity::Integer(pos),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::{
+    cmd::bpop::{block, parse_timeout},
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    shutdown::Shutdown,
+    storage::{
+        Db,
+        entity::Entity,
+        list::{End, blocking::BlockedPop},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct BLMove {
+    source: Entity,
+    destination: Entity,
+    from: End,
+    to: End,
+    timeout: Option<Duration>,
+}
+
+impl BLMove {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<BLMove, CacheError> {
+        let source = parse.next()?;
+        let destination = parse.next()?;
+        let from = parse_end(parse)?;
+        let to = parse_end(parse)?;
+        let timeout = parse_timeout(&parse.next_bytes()?)?;
+        Ok(BLMove {
+            source,
+            destination,
+            from,
+            to,
+            timeout,
+        })
+    }
+
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> Result<(), CacheError> {
+        let pop = BlockedPop::Move(self.from, self.destination, self.to);
+        let served = block(db, &[self.source], pop, self.timeout, shutdown).await;
+        let response = match served {
+            Some(Ok((_, value))) => Entity::Bulk(value),
+            Some(Err(err)) => Entity::Error(err.to_string()),
+            None if shutdown.is_shutdown() => return Ok(()),
+            None => Entity::Null,
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+fn parse_end(parse: &mut Parse) -> Result<End, CacheError> {
+    match &parse.next_string()?.to_uppercase()[..] {
+        "LEFT" => Ok(End::Left),
+        "RIGHT" => Ok(End::Right),
+        _ => Err("ERR syntax error".into()),
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::{
+    cmd::xreadgroup::sleep_until,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    shutdown::Shutdown,
+    storage::{
+        Db,
+        entity::Entity,
+        list::{
+            End,
+            blocking::{Blocked, BlockedPop, Served},
+        },
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct BPop {
+    keys: Vec<Entity>,
+    end: End,
+    timeout: Option<Duration>,
+}
+
+impl BPop {
+    pub(crate) fn parse_frames(parse: &mut Parse, end: End) -> Result<BPop, CacheError> {
+        let mut args = parse.rest_bytes()?;
+        let timeout = args.pop().ok_or(CacheError::EndOfStream)?;
+        if args.is_empty() {
+            return Err(CacheError::EndOfStream);
+        }
+        Ok(BPop {
+            keys: args.into_iter().map(Entity::Bulk).collect(),
+            end,
+            timeout: parse_timeout(&timeout)?,
+        })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        match self.end {
+            End::Left => "blpop",
+            End::Right => "brpop",
+        }
+    }
+
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> Result<(), CacheError> {
+        let served = block(
+            db,
+            &self.keys,
+            BlockedPop::Pop(self.end),
+            self.timeout,
+            shutdown,
+        )
+        .await;
+        let response = match served {
+            Some(Ok((key, value))) => Entity::Array(vec![key, Entity::Bulk(value)]),
+            Some(Err(err)) => Entity::Error(err.to_string()),
+            None if shutdown.is_shutdown() => return Ok(()),
+            None => Entity::Null,
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Parses a timeout in (possibly fractional) seconds, where `0` means forever.
+pub(crate) fn parse_timeout(raw: &[u8]) -> Result<Option<Duration>, CacheError> {
+    let seconds = str::from_utf8(raw)
+        .ok()
+        .and_then(|raw| raw.parse::<f64>().ok())
+        .filter(|seconds| seconds.is_finite())
+        .ok_or("ERR timeout is not a float or out of range")?;
+    if seconds < 0.0 {
+        return Err("ERR timeout is negative".into());
+    }
+    if seconds == 0.0 {
+        return Ok(None);
+    }
+    Duration::try_from_secs_f64(seconds)
+        .map(Some)
+        .map_err(|_| "ERR timeout is out of range".into())
+}
+
+/// Pops right away when possible, otherwise waits until a push serves this
+/// client, the timeout elapses or the server shuts down. Returns `None` on
+/// timeout and shutdown. A timeout too far away for an `Instant` blocks
+/// forever.
+pub(crate) async fn block(
+    db: &Db,
+    keys: &[Entity],
+    pop: BlockedPop,
+    timeout: Option<Duration>,
+    shutdown: &mut Shutdown,
+) -> Option<Served> {
+    let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+    let mut ticket = match db.block_pop(keys, pop).await {
+        Ok(Blocked::Ready(key, value)) => return Some(Ok((key, value))),
+        Ok(Blocked::Waiting(ticket)) => ticket,
+        Err(err) => return Some(Err(err)),
+    };
+    let served = tokio::select! {
+        served = &mut ticket.receiver => served.ok(),
+        _ = sleep_until(deadline) => None,
+        _ = shutdown.recv() => None,
+    };
+    match served {
+        Some(served) => Some(served),
+        // A push may have served us just as we gave up.
+        None => db.unblock(ticket).await,
+    }
+}
@@ -0,0 +1,33 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct CfAdd {
+    key: Entity,
+    item: Bytes,
+}
+
+impl CfAdd {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<CfAdd, CacheError> {
+        let key = parse.next()?;
+        let item = parse.next_bytes()?;
+        Ok(CfAdd { key, item })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.cf_add(self.key, &self.item).await {
+            Ok(()) => Entity::Integer(1),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
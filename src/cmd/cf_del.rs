@@ -0,0 +1,33 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct CfDel {
+    key: Entity,
+    item: Bytes,
+}
+
+impl CfDel {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<CfDel, CacheError> {
+        let key = parse.next()?;
+        let item = parse.next_bytes()?;
+        Ok(CfDel { key, item })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.cf_del(&self.key, &self.item).await {
+            Ok(deleted) => Entity::Integer(deleted as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, cuckoo::CuckooParams, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct CfReserve {
+    key: Entity,
+    params: CuckooParams,
+}
+
+impl CfReserve {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<CfReserve, CacheError> {
+        let key = parse.next()?;
+        let capacity = match parse.next_int()? {
+            capacity if capacity > 0 => capacity as u64,
+            _ => return Err("ERR (capacity should be larger than 0)".into()),
+        };
+
+        let mut params = CuckooParams {
+            capacity,
+            ..CuckooParams::default()
+        };
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            };
+            let value = parse.next_int()?;
+            match &option[..] {
+                "BUCKETSIZE" if (1..=255).contains(&value) => params.bucket_size = value as usize,
+                "BUCKETSIZE" => return Err("ERR bucket size must be between 1 and 255".into()),
+                "MAXITERATIONS" if (1..=65535).contains(&value) => {
+                    params.max_iterations = value as u32
+                }
+                "MAXITERATIONS" => {
+                    return Err("ERR max iterations must be between 1 and 65535".into());
+                }
+                "EXPANSION" if (0..=32768).contains(&value) => params.expansion = value as u32,
+                "EXPANSION" => return Err("ERR expansion must be between 0 and 32768".into()),
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        Ok(CfReserve { key, params })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.cf_reserve(self.key, self.params).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
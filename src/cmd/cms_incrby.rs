@@ -0,0 +1,54 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct CmsIncrBy {
+    key: Entity,
+    increments: Vec<(Bytes, u64)>,
+}
+
+impl CmsIncrBy {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<CmsIncrBy, CacheError> {
+        let key = parse.next()?;
+        let mut increments = vec![];
+        loop {
+            let item = match parse.next_bytes() {
+                Ok(item) => item,
+                Err(CacheError::EndOfStream) if !increments.is_empty() => break,
+                Err(err) => return Err(err),
+            };
+            let by = match parse.next_int()? {
+                by if by >= 0 => by as u64,
+                _ => return Err("ERR CMS: Cannot parse number".into()),
+            };
+            increments.push((item, by));
+        }
+        Ok(CmsIncrBy { key, increments })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.cms_incrby(&self.key, &self.increments).await {
+            Ok(counts) => counts_response(counts),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+pub(crate) fn counts_response(counts: Vec<u64>) -> Entity {
+    Entity::Array(
+        counts
+            .into_iter()
+            .map(|count| Entity::Integer(count.min(i64::MAX as u64) as i64))
+            .collect(),
+    )
+}
@@ -0,0 +1,42 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct CmsInitByDim {
+    key: Entity,
+    width: u64,
+    depth: u64,
+}
+
+impl CmsInitByDim {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<CmsInitByDim, CacheError> {
+        let key = parse.next()?;
+        let width = parse_dimension(parse)?;
+        let depth = parse_dimension(parse)?;
+        Ok(CmsInitByDim { key, width, depth })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.cms_init(self.key, self.width, self.depth).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Reads a strictly positive sketch dimension.
+pub(crate) fn parse_dimension(parse: &mut Parse) -> Result<u64, CacheError> {
+    match parse.next_int()? {
+        n if n > 0 => Ok(n as u64),
+        _ => Err("ERR invalid width/depth, must be a positive integer".into()),
+    }
+}
@@ -0,0 +1,35 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::cms_incrby::counts_response,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct CmsQuery {
+    key: Entity,
+    items: Vec<Bytes>,
+}
+
+impl CmsQuery {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<CmsQuery, CacheError> {
+        let key = parse.next()?;
+        let mut items = vec![parse.next_bytes()?];
+        items.extend(parse.rest_bytes()?);
+        Ok(CmsQuery { key, items })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.cms_query(&self.key, &self.items).await {
+            Ok(counts) => counts_response(counts),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
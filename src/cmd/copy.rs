@@ -0,0 +1,47 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct CopyKey {
+    from: Entity,
+    to: Entity,
+    replace: bool,
+}
+
+impl CopyKey {
+    /// `COPY source destination [REPLACE]`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<CopyKey, CacheError> {
+        let from = parse.next()?;
+        let to = parse.next()?;
+        let mut replace = false;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "REPLACE" => replace = true,
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(CopyKey { from, to, replace })
+    }
+
+    /// Replies `1` if the value was copied, `0` if `source` does not exist
+    /// or `destination` does and `REPLACE` was not given.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.copy(&self.from, &self.to, self.replace).await {
+            Ok(copied) => Entity::Integer(copied as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct DbSize;
+
+impl DbSize {
+    /// `DBSIZE`.
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> Result<DbSize, CacheError> {
+        Ok(DbSize)
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = Entity::Integer(db.dbsize().await as i64);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
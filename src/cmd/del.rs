@@ -9,18 +9,24 @@ use crate::{
 
 #[derive(Debug)]
 pub(crate) struct Del {
-    key: Entity,
+    keys: Vec<Entity>,
+    unlink: bool,
 }
 
 impl Del {
-    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Self, CacheError> {
-        let key = parse.next()?;
-        Ok(Self { key })
+    /// `DEL key [key ...]`, or `UNLINK`, which does the same.
+    pub(crate) fn parse_frames(parse: &mut Parse, unlink: bool) -> Result<Self, CacheError> {
+        let keys = parse.rest_keys()?;
+        Ok(Self { keys, unlink })
     }
 
+    pub(crate) fn get_name(&self) -> &str {
+        if self.unlink { "unlink" } else { "del" }
+    }
+
+    /// Replies with the number of keys that were removed.
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
-        db.del(&self.key).await;
-        let response = Entity::Simple("OK".to_string());
+        let response = Entity::Integer(db.del(&self.keys).await as i64);
         debug!(?response);
         dst.write_frame(&response).await?;
         Ok(())
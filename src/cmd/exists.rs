@@ -0,0 +1,36 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Exists {
+    keys: Vec<Entity>,
+    touch: bool,
+}
+
+impl Exists {
+    /// `EXISTS key [key ...]`, or `TOUCH`, which also counts the keys that
+    /// exist as there is no access time to update.
+    pub(crate) fn parse_frames(parse: &mut Parse, touch: bool) -> Result<Exists, CacheError> {
+        let keys = parse.rest_keys()?;
+        Ok(Exists { keys, touch })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        if self.touch { "touch" } else { "exists" }
+    }
+
+    /// Replies with the number of keys that exist, counting a key given
+    /// more than once each time.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = Entity::Integer(db.exists(&self.keys).await as i64);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use tracing::debug;
+
+use crate::{
+    cmd::getex::Deadline,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, ExpireCondition, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Expire {
+    key: Entity,
+    deadline: Deadline,
+    condition: Option<ExpireCondition>,
+    millis: bool,
+    absolute: bool,
+}
+
+impl Expire {
+    /// `EXPIRE key seconds [NX|XX|GT|LT]`, or `PEXPIRE` with milliseconds,
+    /// or `EXPIREAT`/`PEXPIREAT` with a Unix time.
+    pub(crate) fn parse_frames(
+        parse: &mut Parse,
+        millis: bool,
+        absolute: bool,
+    ) -> Result<Expire, CacheError> {
+        let key = parse.next()?;
+        let deadline = Deadline::new(parse.next_int()?, millis, absolute);
+        let condition = match parse.next_string() {
+            Ok(option) => Some(ExpireCondition::parse(&option).ok_or("ERR syntax error")?),
+            Err(CacheError::EndOfStream) => None,
+            Err(err) => return Err(err),
+        };
+        Ok(Expire {
+            key,
+            deadline,
+            condition,
+            millis,
+            absolute,
+        })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        match (self.millis, self.absolute) {
+            (false, false) => "expire",
+            (true, false) => "pexpire",
+            (false, true) => "expireat",
+            (true, true) => "pexpireat",
+        }
+    }
+
+    /// Replies `1` if the deadline was set, `0` if the key does not exist or
+    /// the condition did not hold.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match self.deadline.instant(self.get_name()) {
+            Ok(when) => Entity::Integer(db.expire(&self.key, when, self.condition).await as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
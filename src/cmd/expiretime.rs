@@ -0,0 +1,50 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, Ttl, entity::Entity, unix_millis_from_instant},
+};
+
+#[derive(Debug)]
+pub(crate) struct ExpireTime {
+    key: Entity,
+    millis: bool,
+}
+
+impl ExpireTime {
+    /// `EXPIRETIME key`, or `PEXPIRETIME` in milliseconds.
+    pub(crate) fn parse_frames(parse: &mut Parse, millis: bool) -> Result<ExpireTime, CacheError> {
+        let key = parse.next()?;
+        Ok(ExpireTime { key, millis })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        if self.millis {
+            "pexpiretime"
+        } else {
+            "expiretime"
+        }
+    }
+
+    /// Replies with the Unix time the key expires at, `-1` without a TTL or
+    /// `-2` if the key does not exist.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = Entity::Integer(match db.ttl(&self.key).await {
+            Ttl::Missing => -2,
+            Ttl::Persistent => -1,
+            Ttl::ExpiresAt(when) => {
+                let millis = unix_millis_from_instant(when);
+                if self.millis {
+                    millis as i64
+                } else {
+                    (millis / 1000) as i64
+                }
+            }
+        });
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{databases::Databases, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Flush {
+    all: bool,
+    lazy: bool,
+}
+
+impl Flush {
+    /// `FLUSHDB [ASYNC | SYNC]`, or `FLUSHALL`.
+    pub(crate) fn parse_frames(parse: &mut Parse, all: bool) -> Result<Flush, CacheError> {
+        let lazy = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "ASYNC" => true,
+            Ok(s) if s.to_uppercase() == "SYNC" => false,
+            Ok(_) => return Err("ERR syntax error".into()),
+            Err(CacheError::EndOfStream) => false,
+            Err(err) => return Err(err),
+        };
+        Ok(Flush { all, lazy })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        if self.all { "flushall" } else { "flushdb" }
+    }
+
+    /// Empties the selected database, or with `FLUSHALL` every one.
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        selected: usize,
+        dst: &mut Connection,
+    ) -> Result<(), CacheError> {
+        if self.all {
+            databases.flush_all(self.lazy).await;
+        } else {
+            databases.get(selected).flush(self.lazy).await;
+        }
+        let response = Entity::Simple("OK".to_string());
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        search::{FieldKind, IndexDefinition},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct FtCreate {
+    name: String,
+    definition: IndexDefinition,
+}
+
+impl FtCreate {
+    /// `FT.CREATE index [ON HASH] [PREFIX count prefix ...] SCHEMA field
+    /// TAG|NUMERIC|TEXT [field type ...]`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<FtCreate, CacheError> {
+        let name = parse.next_string()?;
+        let mut prefixes = vec![];
+        loop {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "ON" if parse.next_string()?.to_uppercase() == "HASH" => {}
+                "ON" => return Err("ERR only hashes can be indexed".into()),
+                "PREFIX" => {
+                    let count = parse.next_count()?;
+                    for _ in 0..count {
+                        prefixes.push(parse.next_bytes()?);
+                    }
+                }
+                "SCHEMA" => break,
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        let mut schema = vec![];
+        loop {
+            let field = match parse.next_bytes() {
+                Ok(field) => field,
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            };
+            let kind = FieldKind::parse(&parse.next_string()?).ok_or("ERR unknown field type")?;
+            if schema.iter().any(|(name, _)| *name == field) {
+                return Err("ERR duplicate field in schema".into());
+            }
+            schema.push((field, kind));
+        }
+        if schema.is_empty() {
+            return Err("ERR empty schema".into());
+        }
+
+        Ok(FtCreate {
+            name,
+            definition: IndexDefinition { prefixes, schema },
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.ft_create(self.name, self.definition).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
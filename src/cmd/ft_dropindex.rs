@@ -0,0 +1,31 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct FtDropIndex {
+    name: String,
+}
+
+impl FtDropIndex {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<FtDropIndex, CacheError> {
+        let name = parse.next_string()?;
+        Ok(FtDropIndex { name })
+    }
+
+    /// Drops the index only; the indexed hashes stay.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.ft_dropindex(&self.name).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        search::{SearchQuery, query::Query},
+    },
+};
+
+const DEFAULT_LIMIT: usize = 10;
+
+#[derive(Debug)]
+pub(crate) struct FtSearch {
+    name: String,
+    query: SearchQuery,
+    no_content: bool,
+}
+
+impl FtSearch {
+    /// `FT.SEARCH index query [NOCONTENT] [SORTBY field [ASC|DESC]]
+    /// [LIMIT offset num]`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<FtSearch, CacheError> {
+        let name = parse.next_string()?;
+        let mut query = SearchQuery {
+            query: Query::parse(&parse.next_string()?)?,
+            sort_by: None,
+            offset: 0,
+            limit: DEFAULT_LIMIT,
+        };
+        let mut no_content = false;
+        let mut pending = None;
+        loop {
+            let option = match pending
+                .take()
+                .map(Ok)
+                .unwrap_or_else(|| parse.next_string())
+            {
+                Ok(option) => option.to_uppercase(),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            };
+            match &option[..] {
+                "NOCONTENT" => no_content = true,
+                "SORTBY" => {
+                    let field = parse.next_bytes()?;
+                    let ascending = match parse.next_string() {
+                        Ok(order) if order.to_uppercase() == "ASC" => true,
+                        Ok(order) if order.to_uppercase() == "DESC" => false,
+                        // Not an order, so the next option.
+                        Ok(option) => {
+                            pending = Some(option);
+                            true
+                        }
+                        Err(CacheError::EndOfStream) => true,
+                        Err(err) => return Err(err),
+                    };
+                    query.sort_by = Some((field, ascending));
+                }
+                "LIMIT" => {
+                    let offset = parse.next_int()?;
+                    let limit = parse.next_int()?;
+                    if offset < 0 || limit < 0 {
+                        return Err("ERR LIMIT arguments must not be negative".into());
+                    }
+                    query.offset = offset as usize;
+                    query.limit = limit as usize;
+                }
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        Ok(FtSearch {
+            name,
+            query,
+            no_content,
+        })
+    }
+
+    /// Replies with the number of matches, then each key of the page
+    /// followed, unless `NOCONTENT`, by its fields and values.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.ft_search(&self.name, &self.query).await {
+            Ok((total, documents)) => {
+                let mut response = Entity::array();
+                response.push_int(total as i64);
+                for (key, fields) in documents {
+                    response.push(key);
+                    if !self.no_content {
+                        let mut content = Entity::array();
+                        for (field, value) in fields {
+                            content.push_bulk(field);
+                            content.push_bulk(value);
+                        }
+                        response.push(content);
+                    }
+                }
+                response
+            }
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
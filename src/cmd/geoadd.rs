@@ -0,0 +1,100 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::zadd::next_score,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        geo::Coord,
+        sorted_set::{ZAddFlags, ZAddOutcome},
+    },
+};
+
+/// `GEOADD` stores members in a sorted set scored by their geohash, so every
+/// sorted set command works on geo keys too.
+#[derive(Debug)]
+pub(crate) struct GeoAdd {
+    key: Entity,
+    members: Vec<(Coord, Bytes)>,
+    flags: ZAddFlags,
+    ch: bool,
+}
+
+impl GeoAdd {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<GeoAdd, CacheError> {
+        let key = parse.next()?;
+        let mut flags = ZAddFlags::default();
+        let mut ch = false;
+
+        let lon = loop {
+            let arg = parse.next_bytes()?;
+            match &arg.to_ascii_uppercase()[..] {
+                b"NX" => flags.nx = true,
+                b"XX" => flags.xx = true,
+                b"CH" => ch = true,
+                _ => break arg,
+            }
+        };
+        if flags.nx && flags.xx {
+            return Err("ERR XX and NX options at the same time are not compatible".into());
+        }
+
+        let mut members = vec![(
+            parse_coord(&lon, &parse.next_bytes()?)?,
+            parse.next_bytes()?,
+        )];
+        loop {
+            match parse.next_bytes() {
+                Ok(lon) => {
+                    let coord = parse_coord(&lon, &parse.next_bytes()?)?;
+                    members.push((coord, parse.next_bytes()?));
+                }
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(GeoAdd {
+            key,
+            members,
+            flags,
+            ch,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let pairs = self
+            .members
+            .into_iter()
+            .map(|(coord, member)| (coord.score(), member))
+            .collect();
+        let response = match db.zadd(self.key, pairs, self.flags).await {
+            Ok(outcomes) => {
+                let counted = outcomes
+                    .into_iter()
+                    .filter(|outcome| {
+                        *outcome == ZAddOutcome::Added
+                            || (self.ch && *outcome == ZAddOutcome::Updated)
+                    })
+                    .count();
+                Entity::Integer(counted as i64)
+            }
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Reads a `longitude latitude` pair, rejecting positions outside the
+/// indexable area.
+pub(crate) fn parse_coord(lon: &[u8], lat: &[u8]) -> Result<Coord, CacheError> {
+    let (lon, lat) = (next_score(lon)?, next_score(lat)?);
+    Coord::new(lon, lat)
+        .ok_or_else(|| format!("ERR invalid longitude,latitude pair {:.6},{:.6}", lon, lat).into())
+}
@@ -0,0 +1,57 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, geo::unit_factor},
+};
+
+#[derive(Debug)]
+pub(crate) struct GeoDist {
+    key: Entity,
+    from: Bytes,
+    to: Bytes,
+    unit: f64,
+}
+
+impl GeoDist {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<GeoDist, CacheError> {
+        let key = parse.next()?;
+        let from = parse.next_bytes()?;
+        let to = parse.next_bytes()?;
+        let unit = match parse.next_string() {
+            Ok(unit) => parse_unit(&unit)?,
+            Err(CacheError::EndOfStream) => 1.0,
+            Err(err) => return Err(err),
+        };
+        Ok(GeoDist {
+            key,
+            from,
+            to,
+            unit,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.geodist(&self.key, &self.from, &self.to).await {
+            Ok(Some(distance)) => format_distance(distance / self.unit),
+            Ok(None) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Reads a distance unit as its length in meters.
+pub(crate) fn parse_unit(unit: &str) -> Result<f64, CacheError> {
+    unit_factor(unit)
+        .ok_or_else(|| "ERR unsupported unit provided. please use M, KM, FT, MI".into())
+}
+
+pub(crate) fn format_distance(distance: f64) -> Entity {
+    Entity::Bulk(Bytes::from(format!("{:.4}", distance)))
+}
@@ -0,0 +1,41 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct GeoHash {
+    key: Entity,
+    members: Vec<Bytes>,
+}
+
+impl GeoHash {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<GeoHash, CacheError> {
+        let key = parse.next()?;
+        let members = parse.rest_bytes()?;
+        Ok(GeoHash { key, members })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.geohash(&self.key, &self.members).await {
+            Ok(hashes) => Entity::Array(
+                hashes
+                    .into_iter()
+                    .map(|hash| {
+                        hash.map(|hash| Entity::Bulk(Bytes::from(hash)))
+                            .unwrap_or(Entity::Null)
+                    })
+                    .collect(),
+            ),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, geo::Coord, sorted_set::format_score},
+};
+
+#[derive(Debug)]
+pub(crate) struct GeoPos {
+    key: Entity,
+    members: Vec<Bytes>,
+}
+
+impl GeoPos {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<GeoPos, CacheError> {
+        let key = parse.next()?;
+        let members = parse.rest_bytes()?;
+        Ok(GeoPos { key, members })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.geopos(&self.key, &self.members).await {
+            Ok(positions) => Entity::Array(
+                positions
+                    .into_iter()
+                    .map(|coord| coord.map(coord_response).unwrap_or(Entity::Null))
+                    .collect(),
+            ),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// A `[longitude, latitude]` pair.
+pub(crate) fn coord_response(coord: Coord) -> Entity {
+    Entity::Array(vec![
+        Entity::Bulk(format_score(coord.lon)),
+        Entity::Bulk(format_score(coord.lat)),
+    ])
+}
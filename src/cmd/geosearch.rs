@@ -0,0 +1,146 @@
+use tracing::debug;
+
+use crate::{
+    cmd::{
+        geoadd::parse_coord,
+        geodist::{format_distance, parse_unit},
+        geopos::coord_response,
+        zadd::next_score,
+    },
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        geo::{GeoFrom, GeoMatch, GeoQuery, GeoShape},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct GeoSearch {
+    key: Entity,
+    query: GeoQuery,
+    /// Meters per unit of the shape, used for `WITHDIST`.
+    unit: f64,
+    with_dist: bool,
+    with_hash: bool,
+    with_coord: bool,
+}
+
+impl GeoSearch {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<GeoSearch, CacheError> {
+        let key = parse.next()?;
+        let mut from = None;
+        let mut shape = None;
+        let mut unit = 1.0;
+        let mut ascending = None;
+        let mut count = None;
+        let (mut with_dist, mut with_hash, mut with_coord) = (false, false, false);
+
+        loop {
+            let arg = match parse.next_string() {
+                Ok(arg) => arg.to_uppercase(),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            };
+            match &arg[..] {
+                "FROMMEMBER" if from.is_none() => {
+                    from = Some(GeoFrom::Member(parse.next_bytes()?));
+                }
+                "FROMLONLAT" if from.is_none() => {
+                    let lon = parse.next_bytes()?;
+                    from = Some(GeoFrom::Coord(parse_coord(&lon, &parse.next_bytes()?)?));
+                }
+                "BYRADIUS" if shape.is_none() => {
+                    let radius = parse_distance(&parse.next_bytes()?)?;
+                    unit = parse_unit(&parse.next_string()?)?;
+                    shape = Some(GeoShape::Radius(radius * unit));
+                }
+                "BYBOX" if shape.is_none() => {
+                    let width = parse_distance(&parse.next_bytes()?)?;
+                    let height = parse_distance(&parse.next_bytes()?)?;
+                    unit = parse_unit(&parse.next_string()?)?;
+                    shape = Some(GeoShape::Box {
+                        width: width * unit,
+                        height: height * unit,
+                    });
+                }
+                "ASC" => ascending = Some(true),
+                "DESC" => ascending = Some(false),
+                "COUNT" => {
+                    let n = match parse.next_int()? {
+                        n if n > 0 => n as usize,
+                        _ => return Err("ERR COUNT must be > 0".into()),
+                    };
+                    count = Some((n, false));
+                }
+                "ANY" => match &mut count {
+                    Some((_, any)) => *any = true,
+                    None => return Err("ERR the ANY argument requires COUNT argument".into()),
+                },
+                "WITHDIST" => with_dist = true,
+                "WITHHASH" => with_hash = true,
+                "WITHCOORD" => with_coord = true,
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        let from = from.ok_or("ERR exactly one of FROMMEMBER or FROMLONLAT can be specified")?;
+        let shape = shape.ok_or("ERR exactly one of BYRADIUS and BYBOX can be specified")?;
+        Ok(GeoSearch {
+            key,
+            query: GeoQuery {
+                from,
+                shape,
+                ascending,
+                count,
+            },
+            unit,
+            with_dist,
+            with_hash,
+            with_coord,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.geosearch(&self.key, &self.query).await {
+            Ok(matches) => Entity::Array(
+                matches
+                    .into_iter()
+                    .map(|found| self.match_response(found))
+                    .collect(),
+            ),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// The bare member, or `[member, dist?, hash?, [lon, lat]?]` when any
+    /// `WITH*` option was given.
+    fn match_response(&self, found: GeoMatch) -> Entity {
+        if !(self.with_dist || self.with_hash || self.with_coord) {
+            return Entity::Bulk(found.member);
+        }
+        let mut item = vec![Entity::Bulk(found.member)];
+        if self.with_dist {
+            item.push(format_distance(found.distance / self.unit));
+        }
+        if self.with_hash {
+            item.push(Entity::Integer(found.hash as i64));
+        }
+        if self.with_coord {
+            item.push(coord_response(found.coord));
+        }
+        Entity::Array(item)
+    }
+}
+
+fn parse_distance(raw: &[u8]) -> Result<f64, CacheError> {
+    match next_score(raw)? {
+        distance if distance >= 0.0 => Ok(distance),
+        _ => Err("ERR radius cannot be negative".into()),
+    }
+}
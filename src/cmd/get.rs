@@ -20,10 +20,10 @@ impl Get {
 
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
-        let response = if let Some(value) = db.get(&self.key).await {
-            value
-        } else {
-            Entity::Null
+        let response = match db.get(&self.key).await {
+            Ok(Some(value)) => value,
+            Ok(None) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
         };
 
         debug!(?response);
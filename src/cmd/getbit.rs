@@ -0,0 +1,33 @@
+use tracing::debug;
+
+use crate::{
+    cmd::setbit::parse_bit_offset,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct GetBit {
+    key: Entity,
+    offset: u64,
+}
+
+impl GetBit {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<GetBit, CacheError> {
+        let key = parse.next()?;
+        let offset = parse_bit_offset(parse)?;
+        Ok(GetBit { key, offset })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.getbit(&self.key, self.offset).await {
+            Ok(on) => Entity::Integer(on as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct GetDel {
+    key: Entity,
+}
+
+impl GetDel {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<GetDel, CacheError> {
+        let key = parse.next()?;
+        Ok(GetDel { key })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.getdel(&self.key).await {
+            Ok(Some(value)) => value,
+            Ok(None) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
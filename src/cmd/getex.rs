@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, instant_from_unix_millis, stream::unix_millis, string::Expiry},
+};
+
+#[derive(Debug)]
+pub(crate) struct GetEx {
+    key: Entity,
+    expiry: Expiry,
+    deadline: Option<Deadline>,
+}
+
+impl GetEx {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<GetEx, CacheError> {
+        let key = parse.next()?;
+
+        let option = match parse.next_string() {
+            Ok(option) => option.to_uppercase(),
+            Err(CacheError::EndOfStream) => {
+                return Ok(GetEx {
+                    key,
+                    expiry: Expiry::Keep,
+                    deadline: None,
+                });
+            }
+            Err(err) => return Err(err),
+        };
+        let (expiry, deadline) = match &option[..] {
+            "PERSIST" => (Expiry::Persist, None),
+            "EX" | "PX" | "EXAT" | "PXAT" => (
+                Expiry::Keep,
+                Some(Deadline::parse(&option, parse, "getex")?),
+            ),
+            _ => return Err("ERR syntax error".into()),
+        };
+        Ok(GetEx {
+            key,
+            expiry,
+            deadline,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let expiry = match self.deadline.map(|deadline| deadline.instant("getex")) {
+            None => Ok(self.expiry),
+            Some(when) => when.map(Expiry::At),
+        };
+        let response = match expiry {
+            Ok(expiry) => match db.getex(&self.key, expiry).await {
+                Ok(Some(value)) => value,
+                Ok(None) => Entity::Null,
+                Err(err) => Entity::Error(err.to_string()),
+            },
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// The time given with `EX`, `PX`, `EXAT` or `PXAT`, or to the `EXPIRE`
+/// family of commands. It becomes a deadline when the command runs.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Deadline {
+    Ex(i64),
+    Px(i64),
+    ExAt(i64),
+    PxAt(i64),
+}
+
+impl Deadline {
+    /// Reads the time following `option`, which must be positive.
+    pub(crate) fn parse(
+        option: &str,
+        parse: &mut Parse,
+        command: &str,
+    ) -> Result<Deadline, CacheError> {
+        let time = parse.next_int()?;
+        if time <= 0 {
+            return Err(invalid_expire_time(command));
+        }
+        Ok(match option {
+            "EX" => Deadline::Ex(time),
+            "PX" => Deadline::Px(time),
+            "EXAT" => Deadline::ExAt(time),
+            _ => Deadline::PxAt(time),
+        })
+    }
+
+    /// A time in seconds or milliseconds, from now or since the epoch.
+    pub(crate) fn new(time: i64, millis: bool, absolute: bool) -> Deadline {
+        match (millis, absolute) {
+            (false, false) => Deadline::Ex(time),
+            (true, false) => Deadline::Px(time),
+            (false, true) => Deadline::ExAt(time),
+            (true, true) => Deadline::PxAt(time),
+        }
+    }
+
+    /// The deadline named, already due if the time is not positive, or an
+    /// error for `command` if, in milliseconds since the epoch, it does not
+    /// fit an `i64`, as in Redis.
+    pub(crate) fn instant(self, command: &str) -> Result<Instant, CacheError> {
+        let (millis, relative) = match self {
+            Deadline::Ex(time) => (time.checked_mul(1000), true),
+            Deadline::Px(time) => (Some(time), true),
+            Deadline::ExAt(time) => (time.checked_mul(1000), false),
+            Deadline::PxAt(time) => (Some(time), false),
+        };
+        let when = millis.and_then(|millis| {
+            if relative {
+                millis.checked_add(unix_millis() as i64)?;
+                Instant::now().checked_add(Duration::from_millis(millis.max(0) as u64))
+            } else {
+                Some(instant_from_unix_millis(millis.max(0) as u64))
+            }
+        });
+        when.ok_or_else(|| invalid_expire_time(command))
+    }
+}
+
+fn invalid_expire_time(command: &str) -> CacheError {
+    format!("ERR invalid expire time in '{}' command", command).into()
+}
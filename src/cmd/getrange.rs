@@ -0,0 +1,34 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct GetRange {
+    key: Entity,
+    start: i64,
+    end: i64,
+}
+
+impl GetRange {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<GetRange, CacheError> {
+        let key = parse.next()?;
+        let start = parse.next_int()?;
+        let end = parse.next_int()?;
+        Ok(GetRange { key, start, end })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.getrange(&self.key, self.start, self.end).await {
+            Ok(value) => Entity::Bulk(value),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct GetSet {
+    key: Entity,
+    value: Entity,
+}
+
+impl GetSet {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<GetSet, CacheError> {
+        let key = parse.next()?;
+        let value = parse.next()?;
+        Ok(GetSet { key, value })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.getset(self.key, self.value).await {
+            Ok(Some(value)) => value,
+            Ok(None) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
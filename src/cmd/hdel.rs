@@ -0,0 +1,34 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct HDel {
+    key: Entity,
+    fields: Vec<Bytes>,
+}
+
+impl HDel {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<HDel, CacheError> {
+        let key = parse.next()?;
+        let mut fields = vec![parse.next_bytes()?];
+        fields.extend(parse.rest_bytes()?);
+        Ok(HDel { key, fields })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.hdel(&self.key, &self.fields).await {
+            Ok(removed) => Entity::Integer(removed as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::getex::Deadline,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, ExpireCondition, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct HExpire {
+    key: Entity,
+    deadline: Deadline,
+    condition: Option<ExpireCondition>,
+    fields: Vec<Bytes>,
+    millis: bool,
+}
+
+impl HExpire {
+    /// `HEXPIRE key seconds [NX|XX|GT|LT] FIELDS numfields field [field ...]`,
+    /// or `HPEXPIRE` with milliseconds.
+    pub(crate) fn parse_frames(parse: &mut Parse, millis: bool) -> Result<HExpire, CacheError> {
+        let key = parse.next()?;
+        let deadline = Deadline::new(parse.next_int()?, millis, false);
+        let option = parse.next_string()?;
+        let (condition, option) = match ExpireCondition::parse(&option) {
+            Some(condition) => (Some(condition), parse.next_string()?),
+            None => (None, option),
+        };
+        let fields = parse_fields(&option, parse)?;
+        Ok(HExpire {
+            key,
+            deadline,
+            condition,
+            fields,
+            millis,
+        })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        if self.millis { "hpexpire" } else { "hexpire" }
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let replies = match self.deadline.instant(self.get_name()) {
+            Ok(when) => {
+                db.hexpire(&self.key, &self.fields, when, self.condition)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        let response = match replies {
+            Ok(replies) => Entity::Array(replies.into_iter().map(Entity::Integer).collect()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Parses `FIELDS numfields field [field ...]`, which ends every hash field
+/// TTL command, once `option` has been read where `FIELDS` belongs.
+pub(crate) fn parse_fields(option: &str, parse: &mut Parse) -> Result<Vec<Bytes>, CacheError> {
+    const MISMATCH: &str = "ERR The `numfields` parameter must match the number of arguments";
+
+    if option.to_uppercase() != "FIELDS" {
+        return Err("ERR Mandatory argument FIELDS is missing or not at the right position".into());
+    }
+    let count = parse.next_count()?;
+    let mut fields = Vec::with_capacity(count);
+    for _ in 0..count {
+        match parse.next_bytes() {
+            Ok(field) => fields.push(field),
+            Err(CacheError::EndOfStream) => return Err(MISMATCH.into()),
+            Err(err) => return Err(err),
+        }
+    }
+    match parse.next_bytes() {
+        Err(CacheError::EndOfStream) => Ok(fields),
+        Ok(_) => Err(MISMATCH.into()),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,34 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct HGet {
+    key: Entity,
+    field: Bytes,
+}
+
+impl HGet {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<HGet, CacheError> {
+        let key = parse.next()?;
+        let field = parse.next_bytes()?;
+        Ok(HGet { key, field })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.hget(&self.key, &self.field).await {
+            Ok(Some(value)) => Entity::Bulk(value),
+            Ok(None) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
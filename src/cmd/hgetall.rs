@@ -0,0 +1,37 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct HGetAll {
+    key: Entity,
+}
+
+impl HGetAll {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<HGetAll, CacheError> {
+        let key = parse.next()?;
+        Ok(HGetAll { key })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.hgetall(&self.key).await {
+            Ok(pairs) => {
+                let mut response = Entity::array();
+                for (field, value) in pairs {
+                    response.push_bulk(field);
+                    response.push_bulk(value);
+                }
+                response
+            }
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct HIncrBy {
+    key: Entity,
+    field: Bytes,
+    increment: i64,
+}
+
+impl HIncrBy {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<HIncrBy, CacheError> {
+        let key = parse.next()?;
+        let field = parse.next_bytes()?;
+        let increment = parse.next_int()?;
+        Ok(HIncrBy {
+            key,
+            field,
+            increment,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.hincrby(self.key, self.field, self.increment).await {
+            Ok(value) => Entity::Integer(value),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
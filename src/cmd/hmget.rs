@@ -0,0 +1,39 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct HMGet {
+    key: Entity,
+    fields: Vec<Bytes>,
+}
+
+impl HMGet {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<HMGet, CacheError> {
+        let key = parse.next()?;
+        let mut fields = vec![parse.next_bytes()?];
+        fields.extend(parse.rest_bytes()?);
+        Ok(HMGet { key, fields })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.hmget(&self.key, &self.fields).await {
+            Ok(values) => Entity::Array(
+                values
+                    .into_iter()
+                    .map(|value| value.map(Entity::Bulk).unwrap_or(Entity::Null))
+                    .collect(),
+            ),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::hexpire::parse_fields,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct HPersist {
+    key: Entity,
+    fields: Vec<Bytes>,
+}
+
+impl HPersist {
+    /// `HPERSIST key FIELDS numfields field [field ...]`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<HPersist, CacheError> {
+        let key = parse.next()?;
+        let fields = parse_fields(&parse.next_string()?, parse)?;
+        Ok(HPersist { key, fields })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.hpersist(&self.key, &self.fields).await {
+            Ok(replies) => Entity::Array(replies.into_iter().map(Entity::Integer).collect()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, scan::DEFAULT_COUNT},
+};
+
+#[derive(Debug)]
+pub(crate) struct HScan {
+    key: Entity,
+    cursor: u64,
+    pattern: Option<Bytes>,
+    count: usize,
+    no_values: bool,
+}
+
+impl HScan {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<HScan, CacheError> {
+        let key = parse.next()?;
+        let cursor = parse.next_cursor()?;
+        let mut pattern = None;
+        let mut count = DEFAULT_COUNT;
+        let mut no_values = false;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "MATCH" => pattern = Some(parse.next_bytes()?),
+                Ok(s) if s.to_uppercase() == "COUNT" => count = parse.next_count()?,
+                Ok(s) if s.to_uppercase() == "NOVALUES" => no_values = true,
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(HScan {
+            key,
+            cursor,
+            pattern,
+            count,
+            no_values,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let page = db
+            .hscan(&self.key, self.cursor, self.pattern.as_deref(), self.count)
+            .await;
+        let response = match page {
+            Ok((next, pairs)) => {
+                let mut items = Entity::array();
+                for (field, value) in pairs {
+                    items.push_bulk(field);
+                    if !self.no_values {
+                        items.push_bulk(value);
+                    }
+                }
+                Entity::Array(vec![Entity::Bulk(Bytes::from(next.to_string())), items])
+            }
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
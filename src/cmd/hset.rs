@@ -0,0 +1,40 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct HSet {
+    key: Entity,
+    pairs: Vec<(Bytes, Bytes)>,
+}
+
+impl HSet {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<HSet, CacheError> {
+        let key = parse.next()?;
+        let mut pairs = vec![(parse.next_bytes()?, parse.next_bytes()?)];
+        loop {
+            match parse.next_bytes() {
+                Ok(field) => pairs.push((field, parse.next_bytes()?)),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(HSet { key, pairs })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.hset(self.key, self.pairs).await {
+            Ok(added) => Entity::Integer(added as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use bytes::Bytes;
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::{
+    cmd::hexpire::parse_fields,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, Ttl, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct HTtl {
+    key: Entity,
+    fields: Vec<Bytes>,
+}
+
+impl HTtl {
+    /// `HTTL key FIELDS numfields field [field ...]`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<HTtl, CacheError> {
+        let key = parse.next()?;
+        let fields = parse_fields(&parse.next_string()?, parse)?;
+        Ok(HTtl { key, fields })
+    }
+
+    /// Replies per field with its remaining seconds, rounded, `-1` without
+    /// a TTL or `-2` if it does not exist.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.httl(&self.key, &self.fields).await {
+            Ok(ttls) => {
+                let now = Instant::now();
+                Entity::Array(
+                    ttls.into_iter()
+                        .map(|ttl| match ttl {
+                            Ttl::Missing => Entity::Integer(-2),
+                            Ttl::Persistent => Entity::Integer(-1),
+                            Ttl::ExpiresAt(when) => {
+                                let millis = when.saturating_duration_since(now).as_millis();
+                                Entity::Integer(((millis + 500) / 1000) as i64)
+                            }
+                        })
+                        .collect(),
+                )
+            }
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
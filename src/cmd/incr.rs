@@ -0,0 +1,58 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+/// `INCR`, `DECR`, `INCRBY` and `DECRBY`.
+#[derive(Debug)]
+pub(crate) struct Incr {
+    key: Entity,
+    /// The explicit amount of `INCRBY`/`DECRBY`.
+    by: Option<i64>,
+    decr: bool,
+}
+
+impl Incr {
+    pub(crate) fn parse_frames(
+        parse: &mut Parse,
+        decr: bool,
+        by: bool,
+    ) -> Result<Incr, CacheError> {
+        let key = parse.next()?;
+        let by = if by { Some(parse.next_int()?) } else { None };
+        Ok(Incr { key, by, decr })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        match (self.decr, self.by.is_some()) {
+            (false, false) => "incr",
+            (true, false) => "decr",
+            (false, true) => "incrby",
+            (true, true) => "decrby",
+        }
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let amount = self.by.unwrap_or(1);
+        let increment = if self.decr {
+            amount.checked_neg()
+        } else {
+            Some(amount)
+        };
+        let result = match increment {
+            Some(increment) => db.incr_by(self.key, increment).await,
+            None => Err("ERR decrement would overflow".into()),
+        };
+        let response = match result {
+            Ok(value) => Entity::Integer(value),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, sorted_set::parse_score},
+};
+
+#[derive(Debug)]
+pub(crate) struct IncrByFloat {
+    key: Entity,
+    increment: f64,
+}
+
+impl IncrByFloat {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<IncrByFloat, CacheError> {
+        let key = parse.next()?;
+        let increment = parse_score(&parse.next_bytes()?)
+            .filter(|increment| increment.is_finite())
+            .ok_or("ERR value is not a valid float")?;
+        Ok(IncrByFloat { key, increment })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.incr_by_float(self.key, self.increment).await {
+            Ok(value) => Entity::Bulk(value),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
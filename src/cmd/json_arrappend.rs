@@ -0,0 +1,64 @@
+use serde_json::Value as Json;
+use tracing::debug;
+
+use crate::{
+    cmd::json_set::parse_json,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, json::path::JsonPath},
+};
+
+#[derive(Debug)]
+pub(crate) struct JsonArrAppend {
+    key: Entity,
+    path: JsonPath,
+    values: Vec<Json>,
+}
+
+impl JsonArrAppend {
+    /// `JSON.ARRAPPEND key [path] value [value ...]`: with a single argument
+    /// after the key, that argument is the value and the path is the root.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<JsonArrAppend, CacheError> {
+        let key = parse.next()?;
+        let mut args = vec![parse.next_bytes()?];
+        args.extend(parse.rest_bytes()?);
+
+        let path = if args.len() > 1 {
+            let path = args.remove(0);
+            JsonPath::parse(str::from_utf8(&path).map_err(|_| "ERR invalid JSON path")?)?
+        } else {
+            JsonPath::parse(".")?
+        };
+        let values = args
+            .iter()
+            .map(|value| parse_json(value))
+            .collect::<Result<_, _>>()?;
+        Ok(JsonArrAppend { key, path, values })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.json_arrappend(&self.key, &self.path, &self.values).await {
+            Ok(lengths) if self.path.is_legacy() => match lengths.into_iter().next() {
+                Some(Some(len)) => Entity::Integer(len as i64),
+                _ => Entity::Error(format!(
+                    "ERR Path '{}' does not exist or does not hold an array",
+                    self.path.raw()
+                )),
+            },
+            Ok(lengths) => Entity::Array(
+                lengths
+                    .into_iter()
+                    .map(|len| {
+                        len.map(|len| Entity::Integer(len as i64))
+                            .unwrap_or(Entity::Null)
+                    })
+                    .collect(),
+            ),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, json::path::JsonPath},
+};
+
+#[derive(Debug)]
+pub(crate) struct JsonDel {
+    key: Entity,
+    path: JsonPath,
+}
+
+impl JsonDel {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<JsonDel, CacheError> {
+        let key = parse.next()?;
+        let path = match parse.next_string() {
+            Ok(path) => JsonPath::parse(&path)?,
+            Err(CacheError::EndOfStream) => JsonPath::root(),
+            Err(err) => return Err(err),
+        };
+        Ok(JsonDel { key, path })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.json_del(&self.key, &self.path).await {
+            Ok(deleted) => Entity::Integer(deleted as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use serde_json::{Map, Value as Json};
+use tracing::debug;
+
+use crate::{
+    cmd::json_set::json_response,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, json::path::JsonPath},
+};
+
+#[derive(Debug)]
+pub(crate) struct JsonGet {
+    key: Entity,
+    paths: Vec<JsonPath>,
+}
+
+impl JsonGet {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<JsonGet, CacheError> {
+        let key = parse.next()?;
+        let mut paths = vec![];
+        loop {
+            match parse.next_string() {
+                Ok(path) => paths.push(JsonPath::parse(&path)?),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        if paths.is_empty() {
+            paths.push(JsonPath::parse(".")?);
+        }
+        Ok(JsonGet { key, paths })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.json_get(&self.key, &self.paths).await {
+            Ok(Some(values)) => self.reply(values),
+            Ok(None) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    /// A single path replies with its value, several with an object keyed
+    /// by path.
+    fn reply(&self, values: Vec<Vec<Json>>) -> Entity {
+        let mut replies = Map::new();
+        for (path, matches) in self.paths.iter().zip(values) {
+            match path_reply(path, matches) {
+                Ok(reply) => replies.insert(path.raw().to_string(), reply),
+                Err(err) => return Entity::Error(err.to_string()),
+            };
+        }
+        match replies.len() {
+            1 => json_response(&replies.into_values().next().expect("one reply")),
+            _ => json_response(&Json::Object(replies)),
+        }
+    }
+}
+
+/// A legacy path replies with its first match, a JSONPath with all of them.
+fn path_reply(path: &JsonPath, matches: Vec<Json>) -> Result<Json, CacheError> {
+    if !path.is_legacy() {
+        return Ok(Json::Array(matches));
+    }
+    matches
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("ERR Path '{}' does not exist", path.raw()).into())
+}
@@ -0,0 +1,50 @@
+use serde_json::{Number, Value as Json};
+use tracing::debug;
+
+use crate::{
+    cmd::json_set::{json_response, parse_json},
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, json::path::JsonPath},
+};
+
+#[derive(Debug)]
+pub(crate) struct JsonNumIncrBy {
+    key: Entity,
+    path: JsonPath,
+    by: Number,
+}
+
+impl JsonNumIncrBy {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<JsonNumIncrBy, CacheError> {
+        let key = parse.next()?;
+        let path = JsonPath::parse(&parse.next_string()?)?;
+        let Json::Number(by) = parse_json(&parse.next_bytes()?)? else {
+            return Err("ERR increment is not a number".into());
+        };
+        Ok(JsonNumIncrBy { key, path, by })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.json_numincrby(&self.key, &self.path, &self.by).await {
+            Ok(results) if self.path.is_legacy() => match results.into_iter().next() {
+                Some(Some(n)) => json_response(&Json::Number(n)),
+                _ => Entity::Error(format!(
+                    "ERR Path '{}' does not exist or does not hold a number",
+                    self.path.raw()
+                )),
+            },
+            Ok(results) => json_response(&Json::Array(
+                results
+                    .into_iter()
+                    .map(|n| n.map(Json::Number).unwrap_or(Json::Null))
+                    .collect(),
+            )),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
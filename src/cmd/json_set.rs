@@ -0,0 +1,73 @@
+use serde_json::Value as Json;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        json::{JsonSetFlags, path::JsonPath},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct JsonSet {
+    key: Entity,
+    path: JsonPath,
+    value: Json,
+    flags: JsonSetFlags,
+}
+
+impl JsonSet {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<JsonSet, CacheError> {
+        let key = parse.next()?;
+        let path = JsonPath::parse(&parse.next_string()?)?;
+        let value = parse_json(&parse.next_bytes()?)?;
+
+        let mut flags = JsonSetFlags::default();
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "NX" => flags.nx = true,
+                Ok(s) if s.to_uppercase() == "XX" => flags.xx = true,
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        if flags.nx && flags.xx {
+            return Err("ERR XX and NX options at the same time are not compatible".into());
+        }
+
+        Ok(JsonSet {
+            key,
+            path,
+            value,
+            flags,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db
+            .json_set(self.key, &self.path, self.value, self.flags)
+            .await
+        {
+            Ok(true) => Entity::Simple("OK".to_string()),
+            Ok(false) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+pub(crate) fn parse_json(raw: &[u8]) -> Result<Json, CacheError> {
+    serde_json::from_slice(raw).map_err(|err| format!("ERR invalid JSON: {}", err).into())
+}
+
+/// Serializes a reply the way `JSON.GET` and `JSON.NUMINCRBY` return it.
+pub(crate) fn json_response(value: &Json) -> Entity {
+    Entity::Bulk(value.to_string().into())
+}
@@ -0,0 +1,31 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Type {
+    key: Entity,
+}
+
+impl Type {
+    /// `TYPE key`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Type, CacheError> {
+        let key = parse.next()?;
+        Ok(Type { key })
+    }
+
+    /// Replies with the type of the value, or `none` if the key does not
+    /// exist.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let name = db.type_of(&self.key).await.unwrap_or("none");
+        let response = Entity::Simple(name.to_string());
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
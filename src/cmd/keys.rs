@@ -0,0 +1,31 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Keys {
+    pattern: Bytes,
+}
+
+impl Keys {
+    /// `KEYS pattern`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Keys, CacheError> {
+        let pattern = parse.next_bytes()?;
+        Ok(Keys { pattern })
+    }
+
+    /// Replies with every matching key at once, holding the lock for the
+    /// whole keyspace; `SCAN` is the way to walk a large one.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = Entity::Array(db.keys(&self.pattern).await);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
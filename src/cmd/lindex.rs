@@ -0,0 +1,33 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct LIndex {
+    key: Entity,
+    index: i64,
+}
+
+impl LIndex {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<LIndex, CacheError> {
+        let key = parse.next()?;
+        let index = parse.next_int()?;
+        Ok(LIndex { key, index })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.lindex(&self.key, self.index).await {
+            Ok(Some(value)) => Entity::Bulk(value),
+            Ok(None) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct LRange {
+    key: Entity,
+    start: i64,
+    stop: i64,
+}
+
+impl LRange {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<LRange, CacheError> {
+        let key = parse.next()?;
+        let start = parse.next_int()?;
+        let stop = parse.next_int()?;
+        Ok(LRange { key, start, stop })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.lrange(&self.key, self.start, self.stop).await {
+            Ok(values) => Entity::Array(values.into_iter().map(Entity::Bulk).collect()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
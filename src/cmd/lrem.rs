@@ -0,0 +1,35 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct LRem {
+    key: Entity,
+    count: i64,
+    value: Bytes,
+}
+
+impl LRem {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<LRem, CacheError> {
+        let key = parse.next()?;
+        let count = parse.next_int()?;
+        let value = parse.next_bytes()?;
+        Ok(LRem { key, count, value })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.lrem(&self.key, self.count, &self.value).await {
+            Ok(removed) => Entity::Integer(removed as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
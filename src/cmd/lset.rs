@@ -0,0 +1,35 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct LSet {
+    key: Entity,
+    index: i64,
+    value: Bytes,
+}
+
+impl LSet {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<LSet, CacheError> {
+        let key = parse.next()?;
+        let index = parse.next_int()?;
+        let value = parse.next_bytes()?;
+        Ok(LSet { key, index, value })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.lset(&self.key, self.index, self.value).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct LTrim {
+    key: Entity,
+    start: i64,
+    stop: i64,
+}
+
+impl LTrim {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<LTrim, CacheError> {
+        let key = parse.next()?;
+        let start = parse.next_int()?;
+        let stop = parse.next_int()?;
+        Ok(LTrim { key, start, stop })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.ltrim(&self.key, self.start, self.stop).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct MGet {
+    keys: Vec<Entity>,
+}
+
+impl MGet {
+    /// `MGET key [key ...]`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<MGet, CacheError> {
+        let keys = parse.rest_keys()?;
+        Ok(MGet { keys })
+    }
+
+    /// Replies with the value of every key, or nil where it is missing or
+    /// not a string.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = Entity::Array(
+            db.mget(&self.keys)
+                .await
+                .into_iter()
+                .map(|value| value.unwrap_or(Entity::Null))
+                .collect(),
+        );
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{databases::Databases, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Move {
+    key: Entity,
+    index: i64,
+}
+
+impl Move {
+    /// `MOVE key db`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Move, CacheError> {
+        let key = parse.next()?;
+        let index = parse.next_int()?;
+        Ok(Move { key, index })
+    }
+
+    /// Replies `1` if the key was moved out of the selected database, `0`
+    /// if it does not exist there or already exists in the other one.
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        selected: usize,
+        dst: &mut Connection,
+    ) -> Result<(), CacheError> {
+        let moved = match databases.index(self.index) {
+            Ok(index) => databases.move_key(&self.key, selected, index).await,
+            Err(err) => Err(err),
+        };
+        let response = match moved {
+            Ok(moved) => Entity::Integer(moved as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
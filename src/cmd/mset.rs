@@ -0,0 +1,54 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct MSet {
+    pairs: Vec<(Entity, Entity)>,
+    nx: bool,
+}
+
+impl MSet {
+    /// `MSET key value [key value ...]`, or `MSETNX`, which writes nothing
+    /// if any of the keys exists.
+    pub(crate) fn parse_frames(parse: &mut Parse, nx: bool) -> Result<MSet, CacheError> {
+        let mut pairs = vec![];
+        let mut rest = parse.rest_bytes()?.into_iter().map(Entity::Bulk);
+        while let Some(key) = rest.next() {
+            let value = rest.next().ok_or_else(|| {
+                format!(
+                    "ERR wrong number of arguments for '{}' command",
+                    if nx { "msetnx" } else { "mset" }
+                )
+            })?;
+            pairs.push((key, value));
+        }
+        if pairs.is_empty() {
+            return Err(CacheError::EndOfStream);
+        }
+        Ok(MSet { pairs, nx })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        if self.nx { "msetnx" } else { "mset" }
+    }
+
+    /// Replies `+OK` to `MSET`, and `1` or `0` to `MSETNX` as the pairs were
+    /// written or not.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let written = db.mset(self.pairs, self.nx).await;
+        let response = if self.nx {
+            Entity::Integer(written as i64)
+        } else {
+            Entity::Simple("OK".to_string())
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Persist {
+    key: Entity,
+}
+
+impl Persist {
+    /// `PERSIST key`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Persist, CacheError> {
+        let key = parse.next()?;
+        Ok(Persist { key })
+    }
+
+    /// Replies `1` if a TTL was removed, `0` otherwise.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = Entity::Integer(db.persist(&self.key).await as i64);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct PfAdd {
+    key: Entity,
+    elements: Vec<Bytes>,
+}
+
+impl PfAdd {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<PfAdd, CacheError> {
+        let key = parse.next()?;
+        let elements = parse.rest_bytes()?;
+        Ok(PfAdd { key, elements })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.pfadd(self.key, &self.elements).await {
+            Ok(changed) => Entity::Integer(changed as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use tracing::debug;
+
+use crate::{
+    cmd::setop::parse_keys,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct PfCount {
+    keys: Vec<Entity>,
+}
+
+impl PfCount {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<PfCount, CacheError> {
+        let keys = parse_keys(parse)?;
+        Ok(PfCount { keys })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.pfcount(&self.keys).await {
+            Ok(count) => Entity::Integer(count as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use tracing::debug;
+
+use crate::{
+    cmd::setop::parse_keys,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct PfMerge {
+    destination: Entity,
+    keys: Vec<Entity>,
+}
+
+impl PfMerge {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<PfMerge, CacheError> {
+        let destination = parse.next()?;
+        let keys = match parse_keys(parse) {
+            Ok(keys) => keys,
+            Err(CacheError::EndOfStream) => vec![],
+            Err(err) => return Err(err),
+        };
+        Ok(PfMerge { destination, keys })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.pfmerge(self.destination, &self.keys).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, list::End},
+};
+
+#[derive(Debug)]
+pub(crate) struct Pop {
+    key: Entity,
+    count: Option<usize>,
+    end: End,
+}
+
+impl Pop {
+    pub(crate) fn parse_frames(parse: &mut Parse, end: End) -> Result<Pop, CacheError> {
+        let key = parse.next()?;
+        let count = match parse.next_int() {
+            Ok(count) if count < 0 => {
+                return Err("ERR value is out of range, must be positive".into());
+            }
+            Ok(count) => Some(count as usize),
+            Err(CacheError::EndOfStream) => None,
+            Err(err) => return Err(err),
+        };
+        Ok(Pop { key, count, end })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        match self.end {
+            End::Left => "lpop",
+            End::Right => "rpop",
+        }
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let popped = db.pop(&self.key, self.end, self.count.unwrap_or(1)).await;
+        let response = match (popped, self.count) {
+            (Ok(None), _) => Entity::Null,
+            (Ok(Some(mut values)), None) => values.pop().map(Entity::Bulk).unwrap_or(Entity::Null),
+            (Ok(Some(values)), Some(_)) => {
+                Entity::Array(values.into_iter().map(Entity::Bulk).collect())
+            }
+            (Err(err), _) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
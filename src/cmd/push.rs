@@ -0,0 +1,42 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, list::End},
+};
+
+#[derive(Debug)]
+pub(crate) struct Push {
+    key: Entity,
+    values: Vec<Bytes>,
+    end: End,
+}
+
+impl Push {
+    pub(crate) fn parse_frames(parse: &mut Parse, end: End) -> Result<Push, CacheError> {
+        let key = parse.next()?;
+        let mut values = vec![parse.next_bytes()?];
+        values.extend(parse.rest_bytes()?);
+        Ok(Push { key, values, end })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        match self.end {
+            End::Left => "lpush",
+            End::Right => "rpush",
+        }
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.push(self.key, self.values, self.end).await {
+            Ok(len) => Entity::Integer(len as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct RandomKey;
+
+impl RandomKey {
+    /// `RANDOMKEY`.
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> Result<RandomKey, CacheError> {
+        Ok(RandomKey)
+    }
+
+    /// Replies with a random key, or nil if there are none.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = db.random_key().await.unwrap_or(Entity::Null);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Rename {
+    from: Entity,
+    to: Entity,
+    nx: bool,
+}
+
+impl Rename {
+    /// `RENAME key newkey`, or `RENAMENX`, which leaves an existing `newkey`
+    /// alone.
+    pub(crate) fn parse_frames(parse: &mut Parse, nx: bool) -> Result<Rename, CacheError> {
+        let from = parse.next()?;
+        let to = parse.next()?;
+        Ok(Rename { from, to, nx })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        if self.nx { "renamenx" } else { "rename" }
+    }
+
+    /// Replies `+OK` to `RENAME`, and `1` or `0` to `RENAMENX` as the key was
+    /// renamed or not.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.rename(&self.from, &self.to, self.nx).await {
+            Ok(renamed) if self.nx => Entity::Integer(renamed as i64),
+            Ok(_) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct SAdd {
+    key: Entity,
+    members: Vec<Bytes>,
+}
+
+impl SAdd {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<SAdd, CacheError> {
+        let key = parse.next()?;
+        let mut members = vec![parse.next_bytes()?];
+        members.extend(parse.rest_bytes()?);
+        Ok(SAdd { key, members })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.sadd(self.key, self.members).await {
+            Ok(added) => Entity::Integer(added as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
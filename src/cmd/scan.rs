@@ -0,0 +1,63 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, scan::DEFAULT_COUNT},
+};
+
+#[derive(Debug)]
+pub(crate) struct Scan {
+    cursor: u64,
+    pattern: Option<Bytes>,
+    count: usize,
+    type_name: Option<String>,
+}
+
+impl Scan {
+    /// `SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Scan, CacheError> {
+        let cursor = parse.next_cursor()?;
+        let mut pattern = None;
+        let mut count = DEFAULT_COUNT;
+        let mut type_name = None;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "MATCH" => pattern = Some(parse.next_bytes()?),
+                Ok(s) if s.to_uppercase() == "COUNT" => count = parse.next_count()?,
+                Ok(s) if s.to_uppercase() == "TYPE" => type_name = Some(parse.next_string()?),
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Scan {
+            cursor,
+            pattern,
+            count,
+            type_name,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let (next, keys) = db
+            .scan(
+                self.cursor,
+                self.pattern.as_deref(),
+                self.type_name.as_deref(),
+                self.count,
+            )
+            .await;
+        let response = Entity::Array(vec![
+            Entity::Bulk(Bytes::from(next.to_string())),
+            Entity::Array(keys),
+        ]);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
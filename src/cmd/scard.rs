@@ -0,0 +1,30 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct SCard {
+    key: Entity,
+}
+
+impl SCard {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<SCard, CacheError> {
+        let key = parse.next()?;
+        Ok(SCard { key })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.scard(&self.key).await {
+            Ok(len) => Entity::Integer(len as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{databases::Databases, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Select {
+    index: i64,
+}
+
+impl Select {
+    /// `SELECT index`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Select, CacheError> {
+        let index = parse.next_int()?;
+        Ok(Select { index })
+    }
+
+    /// Switches the connection to the database at `index`, for every later
+    /// command until the next `SELECT`.
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        selected: &mut usize,
+        dst: &mut Connection,
+    ) -> Result<(), CacheError> {
+        let response = match databases.index(self.index) {
+            Ok(index) => {
+                *selected = index;
+                Entity::Simple("OK".to_string())
+            }
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -1,52 +1,89 @@
-use std::time::Duration;
-
 use tracing::debug;
 
 use crate::{
+    cmd::getex::Deadline,
     connection::Connection,
     error::CacheError,
     parse::Parse,
-    storage::{Db, entity::Entity},
+    storage::{
+        Db,
+        entity::Entity,
+        string::{Expiry, SetCondition, SetOptions},
+    },
 };
 
 #[derive(Debug)]
 pub(crate) struct Set {
     key: Entity,
     value: Entity,
-    expire: Option<Duration>,
+    options: SetOptions,
+    deadline: Option<Deadline>,
 }
 
 impl Set {
+    /// `SET key value [NX|XX] [GET] [EX seconds|PX ms|EXAT unix-seconds|PXAT
+    /// unix-ms|KEEPTTL]`, options in any order.
     pub(crate) fn parse_frames(parse: &mut Parse) -> Result<Set, CacheError> {
         let key = parse.next()?;
         let value = parse.next()?;
 
-        let mut expire = None;
-
-        match parse.next_string() {
-            Ok(s) if s.to_uppercase() == "EX" => {
-                let secs = parse.next_int()?;
-                expire = Some(Duration::from_secs(secs as u64));
-            }
-            Ok(s) if s.to_uppercase() == "PX" => {
-                let ms = parse.next_int()?;
-                expire = Some(Duration::from_millis(ms as u64));
+        let mut options = SetOptions::default();
+        let mut deadline = None;
+        let mut has_expiry = false;
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            };
+            match &option[..] {
+                "NX" if options.condition != Some(SetCondition::Xx) => {
+                    options.condition = Some(SetCondition::Nx)
+                }
+                "XX" if options.condition != Some(SetCondition::Nx) => {
+                    options.condition = Some(SetCondition::Xx)
+                }
+                "GET" => options.get = true,
+                "KEEPTTL" if !has_expiry => {
+                    options.expiry = Expiry::Keep;
+                    has_expiry = true;
+                }
+                "EX" | "PX" | "EXAT" | "PXAT" if !has_expiry => {
+                    deadline = Some(Deadline::parse(&option, parse, "set")?);
+                    has_expiry = true;
+                }
+                _ => return Err("ERR syntax error".into()),
             }
-            Ok(_) => return Err("currently `SET` only supports the expiration option".into()),
-            Err(CacheError::EndOfStream) => {}
-            Err(err) => return Err(err),
         }
 
-        Ok(Set { key, value, expire })
+        Ok(Set {
+            key,
+            value,
+            options,
+            deadline,
+        })
     }
 
+    /// Replies `OK`, or nil when the condition fails; with `GET`, the
+    /// previous value or nil instead.
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
-        db.set(self.key, self.value, self.expire).await;
-
-        let response = Entity::Simple("OK".to_string());
+        let mut options = self.options;
+        let written = match self.deadline.map(|deadline| deadline.instant("set")) {
+            Some(Err(err)) => Err(err),
+            Some(Ok(when)) => {
+                options.expiry = Expiry::At(when);
+                db.set(self.key, self.value, options).await
+            }
+            None => db.set(self.key, self.value, options).await,
+        };
+        let response = match written {
+            Ok((_, previous)) if options.get => previous.unwrap_or(Entity::Null),
+            Ok((true, _)) => Entity::Simple("OK".to_string()),
+            Ok((false, _)) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
         debug!(?response);
         dst.write_frame(&response).await?;
-
         Ok(())
     }
 }
@@ -0,0 +1,45 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, bitmap::MAX_BIT_OFFSET, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct SetBit {
+    key: Entity,
+    offset: u64,
+    on: bool,
+}
+
+impl SetBit {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<SetBit, CacheError> {
+        let key = parse.next()?;
+        let offset = parse_bit_offset(parse)?;
+        let on = match parse.next_int()? {
+            0 => false,
+            1 => true,
+            _ => return Err("ERR bit is not an integer or out of range".into()),
+        };
+        Ok(SetBit { key, offset, on })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.setbit(self.key, self.offset, self.on).await {
+            Ok(previous) => Entity::Integer(previous as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+pub(crate) fn parse_bit_offset(parse: &mut Parse) -> Result<u64, CacheError> {
+    match parse.next_int()? {
+        offset if (0..=MAX_BIT_OFFSET as i64).contains(&offset) => Ok(offset as u64),
+        _ => Err("ERR bit offset is not an integer or out of range".into()),
+    }
+}
@@ -0,0 +1,98 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, set::SetOperation},
+};
+
+/// `SINTER`, `SUNION` and `SDIFF`.
+#[derive(Debug)]
+pub(crate) struct SetOp {
+    operation: SetOperation,
+    keys: Vec<Entity>,
+}
+
+/// `SINTERSTORE`, `SUNIONSTORE` and `SDIFFSTORE`.
+#[derive(Debug)]
+pub(crate) struct SetOpStore {
+    operation: SetOperation,
+    destination: Entity,
+    keys: Vec<Entity>,
+}
+
+impl SetOp {
+    pub(crate) fn parse_frames(
+        parse: &mut Parse,
+        operation: SetOperation,
+    ) -> Result<SetOp, CacheError> {
+        let keys = parse_keys(parse)?;
+        Ok(SetOp { operation, keys })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        match self.operation {
+            SetOperation::Inter => "sinter",
+            SetOperation::Union => "sunion",
+            SetOperation::Diff => "sdiff",
+        }
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.set_operation(self.operation, &self.keys).await {
+            Ok(members) => Entity::Array(members.into_iter().map(Entity::Bulk).collect()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+impl SetOpStore {
+    pub(crate) fn parse_frames(
+        parse: &mut Parse,
+        operation: SetOperation,
+    ) -> Result<SetOpStore, CacheError> {
+        let destination = parse.next()?;
+        let keys = parse_keys(parse)?;
+        Ok(SetOpStore {
+            operation,
+            destination,
+            keys,
+        })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        match self.operation {
+            SetOperation::Inter => "sinterstore",
+            SetOperation::Union => "sunionstore",
+            SetOperation::Diff => "sdiffstore",
+        }
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let stored = db
+            .set_operation_store(self.operation, self.destination, &self.keys)
+            .await;
+        let response = match stored {
+            Ok(len) => Entity::Integer(len as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+pub(crate) fn parse_keys(parse: &mut Parse) -> Result<Vec<Entity>, CacheError> {
+    let mut keys = vec![parse.next()?];
+    loop {
+        match parse.next() {
+            Ok(key) => keys.push(key),
+            Err(CacheError::EndOfStream) => return Ok(keys),
+            Err(err) => return Err(err),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct SetRange {
+    key: Entity,
+    offset: usize,
+    value: Bytes,
+}
+
+impl SetRange {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<SetRange, CacheError> {
+        let key = parse.next()?;
+        let offset = parse.next_int()?;
+        if offset < 0 {
+            return Err("ERR offset is out of range".into());
+        }
+        let value = parse.next_bytes()?;
+        Ok(SetRange {
+            key,
+            offset: offset as usize,
+            value,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.setrange(self.key, self.offset, self.value).await {
+            Ok(len) => Entity::Integer(len as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct SMembers {
+    key: Entity,
+}
+
+impl SMembers {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<SMembers, CacheError> {
+        let key = parse.next()?;
+        Ok(SMembers { key })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.smembers(&self.key).await {
+            Ok(members) => Entity::Array(members.into_iter().map(Entity::Bulk).collect()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
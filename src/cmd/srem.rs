@@ -0,0 +1,34 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct SRem {
+    key: Entity,
+    members: Vec<Bytes>,
+}
+
+impl SRem {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<SRem, CacheError> {
+        let key = parse.next()?;
+        let mut members = vec![parse.next_bytes()?];
+        members.extend(parse.rest_bytes()?);
+        Ok(SRem { key, members })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.srem(&self.key, &self.members).await {
+            Ok(removed) => Entity::Integer(removed as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{databases::Databases, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct SwapDb {
+    first: i64,
+    second: i64,
+}
+
+impl SwapDb {
+    /// `SWAPDB index1 index2`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<SwapDb, CacheError> {
+        let first = parse.next_int()?;
+        let second = parse.next_int()?;
+        Ok(SwapDb { first, second })
+    }
+
+    /// Swaps the contents of the two databases, so connections that had
+    /// either selected see the other's keys from then on.
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        dst: &mut Connection,
+    ) -> Result<(), CacheError> {
+        let response = match (databases.index(self.first), databases.index(self.second)) {
+            (Ok(first), Ok(second)) => {
+                databases.swap(first, second).await;
+                Entity::Simple("OK".to_string())
+            }
+            (Err(err), _) | (_, Err(err)) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct TopKAdd {
+    key: Entity,
+    items: Vec<Bytes>,
+}
+
+impl TopKAdd {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<TopKAdd, CacheError> {
+        let key = parse.next()?;
+        let mut items = vec![parse.next_bytes()?];
+        items.extend(parse.rest_bytes()?);
+        Ok(TopKAdd { key, items })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.topk_add(&self.key, &self.items).await {
+            Ok(expelled) => Entity::Array(
+                expelled
+                    .into_iter()
+                    .map(|item| item.map(Entity::Bulk).unwrap_or(Entity::Null))
+                    .collect(),
+            ),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct TopKList {
+    key: Entity,
+    with_count: bool,
+}
+
+impl TopKList {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<TopKList, CacheError> {
+        let key = parse.next()?;
+        let with_count = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "WITHCOUNT" => true,
+            Ok(_) => return Err("ERR syntax error".into()),
+            Err(CacheError::EndOfStream) => false,
+            Err(err) => return Err(err),
+        };
+        Ok(TopKList { key, with_count })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.topk_list(&self.key).await {
+            Ok(top) => {
+                let mut items = vec![];
+                for (item, count) in top {
+                    items.push(Entity::Bulk(item));
+                    if self.with_count {
+                        items.push(Entity::Integer(count as i64));
+                    }
+                }
+                Entity::Array(items)
+            }
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use tracing::debug;
+
+use crate::{
+    cmd::{cms_initbydim::parse_dimension, zadd::next_score},
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, top_k::TopKParams},
+};
+
+#[derive(Debug)]
+pub(crate) struct TopKReserve {
+    key: Entity,
+    k: usize,
+    params: TopKParams,
+}
+
+impl TopKReserve {
+    /// `TOPK.RESERVE key topk [width depth decay]`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<TopKReserve, CacheError> {
+        let key = parse.next()?;
+        let k = match parse.next_int()? {
+            k if k > 0 => k as usize,
+            _ => return Err("ERR TopK: invalid k".into()),
+        };
+
+        let mut params = TopKParams::default();
+        match parse_dimension(parse) {
+            Ok(width) => {
+                params.width = width;
+                params.depth = parse_dimension(parse)?;
+                params.decay = next_score(&parse.next_bytes()?)?;
+                if !(params.decay > 0.0 && params.decay <= 1.0) {
+                    return Err("ERR TopK: decay must be between 0 and 1".into());
+                }
+            }
+            Err(CacheError::EndOfStream) => {}
+            Err(err) => return Err(err),
+        }
+        Ok(TopKReserve { key, k, params })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.topk_reserve(self.key, self.k, self.params).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
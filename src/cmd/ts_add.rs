@@ -0,0 +1,59 @@
+use tracing::debug;
+
+use crate::{
+    cmd::{ts_create::parse_series_option, ts_range::parse_timestamp, zadd::next_score},
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, time_series::SeriesOptions},
+};
+
+#[derive(Debug)]
+pub(crate) struct TsAdd {
+    key: Entity,
+    /// `None` for `*`, the current time.
+    timestamp: Option<u64>,
+    value: f64,
+    /// Used only if the series does not exist yet.
+    options: SeriesOptions,
+}
+
+impl TsAdd {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<TsAdd, CacheError> {
+        let key = parse.next()?;
+        let timestamp = match &parse.next_string()?[..] {
+            "*" => None,
+            timestamp => Some(parse_timestamp(timestamp)?),
+        };
+        let value = next_score(&parse.next_bytes()?)?;
+
+        let mut options = SeriesOptions::default();
+        loop {
+            match parse.next_string() {
+                Ok(option) => parse_series_option(&option, parse, &mut options)?,
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(TsAdd {
+            key,
+            timestamp,
+            value,
+            options,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db
+            .ts_add(self.key, self.timestamp, self.value, self.options)
+            .await
+        {
+            Ok(timestamp) => Entity::Integer(timestamp as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, time_series::SeriesOptions},
+};
+
+#[derive(Debug)]
+pub(crate) struct TsCreate {
+    key: Entity,
+    options: SeriesOptions,
+}
+
+impl TsCreate {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<TsCreate, CacheError> {
+        let key = parse.next()?;
+        let mut options = SeriesOptions::default();
+        loop {
+            match parse.next_string() {
+                Ok(option) => parse_series_option(&option, parse, &mut options)?,
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(TsCreate { key, options })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.ts_create(self.key, self.options).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Parses `RETENTION ms` or `LABELS label value ...`, which takes the rest of
+/// the arguments.
+pub(crate) fn parse_series_option(
+    option: &str,
+    parse: &mut Parse,
+    options: &mut SeriesOptions,
+) -> Result<(), CacheError> {
+    match &option.to_uppercase()[..] {
+        "RETENTION" => {
+            options.retention = match parse.next_int()? {
+                0 => None,
+                retention if retention > 0 => Some(retention as u64),
+                _ => return Err("ERR TSDB: invalid retention value".into()),
+            };
+        }
+        "LABELS" => loop {
+            let label = match parse.next_string() {
+                Ok(label) => label,
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            };
+            options.labels.retain(|(existing, _)| *existing != label);
+            options.labels.push((label, parse.next_string()?));
+        },
+        _ => return Err("ERR syntax error".into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use tracing::debug;
+
+use crate::{
+    cmd::ts_range::parse_range_option,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        time_series::{Aggregation, RangeQuery},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct TsCreateRule {
+    source: Entity,
+    destination: Entity,
+    aggregation: Aggregation,
+}
+
+impl TsCreateRule {
+    /// `TS.CREATERULE source destination AGGREGATION aggregator bucketDuration`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<TsCreateRule, CacheError> {
+        let source = parse.next()?;
+        let destination = parse.next()?;
+        let mut query = RangeQuery {
+            from: 0,
+            to: u64::MAX,
+            aggregation: None,
+            count: None,
+        };
+        let option = parse.next_string()?;
+        if option.to_uppercase() != "AGGREGATION" {
+            return Err("ERR syntax error".into());
+        }
+        parse_range_option(&option, parse, &mut query)?;
+        let aggregation = query.aggregation.expect("AGGREGATION parsed");
+        Ok(TsCreateRule {
+            source,
+            destination,
+            aggregation,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db
+            .ts_createrule(&self.source, &self.destination, self.aggregation)
+            .await
+        {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::ts_range::{parse_range, parse_range_option, samples_response},
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        time_series::{LabelFilter, RangeQuery},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct TsMRange {
+    query: RangeQuery,
+    filters: Vec<LabelFilter>,
+    with_labels: bool,
+}
+
+impl TsMRange {
+    /// `TS.MRANGE from to [WITHLABELS] [COUNT n] [AGGREGATION aggregator
+    /// bucketDuration] FILTER label=value ...`, with `FILTER` last.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<TsMRange, CacheError> {
+        let mut query = parse_range(parse)?;
+        let mut with_labels = false;
+        loop {
+            let option = parse.next_string()?;
+            match &option.to_uppercase()[..] {
+                "WITHLABELS" => with_labels = true,
+                "FILTER" => break,
+                _ if parse_range_option(&option, parse, &mut query)? => {}
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        let mut filters = vec![];
+        loop {
+            match parse.next_string() {
+                Ok(filter) => filters.push(parse_filter(&filter)?),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        if !filters
+            .iter()
+            .any(|filter| !filter.negate && !filter.value.is_empty())
+        {
+            return Err("ERR TSDB: please provide at least one matcher".into());
+        }
+
+        Ok(TsMRange {
+            query,
+            filters,
+            with_labels,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let series = db.ts_mrange(&self.filters, &self.query).await;
+        let response = Entity::Array(
+            series
+                .into_iter()
+                .map(|(key, labels, samples)| {
+                    let labels = if self.with_labels {
+                        labels
+                            .into_iter()
+                            .map(|(label, value)| {
+                                Entity::Array(vec![
+                                    Entity::Bulk(Bytes::from(label)),
+                                    Entity::Bulk(Bytes::from(value)),
+                                ])
+                            })
+                            .collect()
+                    } else {
+                        vec![]
+                    };
+                    Entity::Array(vec![key, Entity::Array(labels), samples_response(samples)])
+                })
+                .collect(),
+        );
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+fn parse_filter(filter: &str) -> Result<LabelFilter, CacheError> {
+    let (label, value, negate) = if let Some((label, value)) = filter.split_once("!=") {
+        (label, value, true)
+    } else if let Some((label, value)) = filter.split_once('=') {
+        (label, value, false)
+    } else {
+        return Err("ERR TSDB: failed parsing labels".into());
+    };
+    if label.is_empty() {
+        return Err("ERR TSDB: failed parsing labels".into());
+    }
+    Ok(LabelFilter {
+        label: label.to_string(),
+        value: value.to_string(),
+        negate,
+    })
+}
@@ -0,0 +1,113 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        sorted_set::format_score,
+        time_series::{Aggregation, Aggregator, RangeQuery},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct TsRange {
+    key: Entity,
+    query: RangeQuery,
+}
+
+impl TsRange {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<TsRange, CacheError> {
+        let key = parse.next()?;
+        let mut query = parse_range(parse)?;
+        loop {
+            match parse.next_string() {
+                Ok(option) if parse_range_option(&option, parse, &mut query)? => {}
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(TsRange { key, query })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.ts_range(&self.key, &self.query).await {
+            Ok(samples) => samples_response(samples),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Reads `fromTimestamp toTimestamp`, where `-` and `+` are the earliest and
+/// latest possible ones.
+pub(crate) fn parse_range(parse: &mut Parse) -> Result<RangeQuery, CacheError> {
+    let mut bound = |unbounded| -> Result<u64, CacheError> {
+        match &parse.next_string()?[..] {
+            "-" => Ok(0),
+            "+" => Ok(unbounded),
+            timestamp => parse_timestamp(timestamp),
+        }
+    };
+    let from = bound(0)?;
+    let to = bound(u64::MAX)?;
+    Ok(RangeQuery {
+        from,
+        to,
+        aggregation: None,
+        count: None,
+    })
+}
+
+/// Parses a Unix time in milliseconds. Timestamps are replied as integers,
+/// so they must fit in one.
+pub(crate) fn parse_timestamp(timestamp: &str) -> Result<u64, CacheError> {
+    timestamp
+        .parse::<i64>()
+        .ok()
+        .and_then(|timestamp| u64::try_from(timestamp).ok())
+        .ok_or_else(|| "ERR TSDB: invalid timestamp".into())
+}
+
+/// Parses `COUNT n` or `AGGREGATION aggregator bucketDuration`. Returns
+/// false for any other option.
+pub(crate) fn parse_range_option(
+    option: &str,
+    parse: &mut Parse,
+    query: &mut RangeQuery,
+) -> Result<bool, CacheError> {
+    match &option.to_uppercase()[..] {
+        "COUNT" => query.count = Some(parse.next_count()?),
+        "AGGREGATION" => {
+            let aggregator = Aggregator::parse(&parse.next_string()?)
+                .ok_or("ERR TSDB: unknown aggregation type")?;
+            let bucket = match parse.next_int()? {
+                bucket if bucket > 0 => bucket as u64,
+                _ => return Err("ERR TSDB: bucketDuration must be greater than zero".into()),
+            };
+            query.aggregation = Some(Aggregation { aggregator, bucket });
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// An array of `[timestamp, value]` pairs.
+pub(crate) fn samples_response(samples: Vec<(u64, f64)>) -> Entity {
+    Entity::Array(
+        samples
+            .into_iter()
+            .map(|(timestamp, value)| {
+                Entity::Array(vec![
+                    Entity::Integer(timestamp as i64),
+                    Entity::Bulk(format_score(value)),
+                ])
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,47 @@
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, Ttl as KeyTtl, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct Ttl {
+    key: Entity,
+    millis: bool,
+}
+
+impl Ttl {
+    /// `TTL key`, or `PTTL` in milliseconds.
+    pub(crate) fn parse_frames(parse: &mut Parse, millis: bool) -> Result<Ttl, CacheError> {
+        let key = parse.next()?;
+        Ok(Ttl { key, millis })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        if self.millis { "pttl" } else { "ttl" }
+    }
+
+    /// Replies with the time left, `-1` without a TTL or `-2` if the key
+    /// does not exist. Seconds are rounded to the nearest.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = Entity::Integer(match db.ttl(&self.key).await {
+            KeyTtl::Missing => -2,
+            KeyTtl::Persistent => -1,
+            KeyTtl::ExpiresAt(when) => {
+                let millis = when.saturating_duration_since(Instant::now()).as_millis();
+                if self.millis {
+                    millis as i64
+                } else {
+                    ((millis + 500) / 1000) as i64
+                }
+            }
+        });
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::zadd::next_score,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct VIndexAdd {
+    key: Entity,
+    member: Bytes,
+    vector: Vec<f32>,
+}
+
+impl VIndexAdd {
+    /// `VINDEX.ADD key member value [value ...]`, one value per dimension.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<VIndexAdd, CacheError> {
+        let key = parse.next()?;
+        let member = parse.next_bytes()?;
+        let mut vector = vec![next_score(&parse.next_bytes()?)? as f32];
+        loop {
+            match parse.next_bytes() {
+                Ok(value) => vector.push(next_score(&value)? as f32),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(VIndexAdd {
+            key,
+            member,
+            vector,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.vindex_add(&self.key, self.member, self.vector).await {
+            Ok(added) => Entity::Integer(added as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
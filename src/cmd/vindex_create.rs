@@ -0,0 +1,88 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        vector::{Algorithm, HnswParams, Metric, VectorParams},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct VIndexCreate {
+    key: Entity,
+    params: VectorParams,
+}
+
+impl VIndexCreate {
+    /// `VINDEX.CREATE key DIM n METRIC COSINE|L2|IP [ALGORITHM FLAT|HNSW]
+    /// [M m] [EF_CONSTRUCTION n] [EF_RUNTIME n]`, options in any order.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<VIndexCreate, CacheError> {
+        let key = parse.next()?;
+        let mut dim = None;
+        let mut metric = None;
+        let mut hnsw = false;
+        let mut hnsw_params = None;
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            };
+            match &option[..] {
+                "DIM" => dim = Some(parse.next_count()?),
+                "METRIC" => {
+                    metric = Some(
+                        Metric::parse(&parse.next_string()?).ok_or("ERR unknown vector metric")?,
+                    )
+                }
+                "ALGORITHM" => match &parse.next_string()?.to_uppercase()[..] {
+                    "FLAT" => hnsw = false,
+                    "HNSW" => hnsw = true,
+                    _ => return Err("ERR unknown vector algorithm".into()),
+                },
+                "M" | "EF_CONSTRUCTION" | "EF_RUNTIME" => {
+                    let params = hnsw_params.get_or_insert_with(HnswParams::default);
+                    let value = parse.next_count()?;
+                    match &option[..] {
+                        "M" if value < 2 => return Err("ERR M must be at least 2".into()),
+                        "M" => params.m = value,
+                        "EF_CONSTRUCTION" => params.ef_construction = value,
+                        _ => params.ef_runtime = value,
+                    }
+                }
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        let (Some(dim), Some(metric)) = (dim, metric) else {
+            return Err("ERR DIM and METRIC are required".into());
+        };
+        let algorithm = match (hnsw, hnsw_params) {
+            (true, params) => Algorithm::Hnsw(params.unwrap_or_default()),
+            (false, None) => Algorithm::Flat,
+            (false, Some(_)) => return Err("ERR M and EF options need ALGORITHM HNSW".into()),
+        };
+        Ok(VIndexCreate {
+            key,
+            params: VectorParams {
+                dim,
+                metric,
+                algorithm,
+            },
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.vindex_create(self.key, self.params).await {
+            Ok(()) => Entity::Simple("OK".to_string()),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
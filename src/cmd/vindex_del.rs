@@ -0,0 +1,33 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct VIndexDel {
+    key: Entity,
+    member: Bytes,
+}
+
+impl VIndexDel {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<VIndexDel, CacheError> {
+        let key = parse.next()?;
+        let member = parse.next_bytes()?;
+        Ok(VIndexDel { key, member })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.vindex_del(&self.key, &self.member).await {
+            Ok(removed) => Entity::Integer(removed as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
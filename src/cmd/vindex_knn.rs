@@ -0,0 +1,55 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::zadd::next_score,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct VIndexKnn {
+    key: Entity,
+    k: usize,
+    vector: Vec<f32>,
+    ef: Option<usize>,
+}
+
+impl VIndexKnn {
+    /// `VINDEX.KNN key k value [value ...] [EF ef]`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<VIndexKnn, CacheError> {
+        let key = parse.next()?;
+        let k = parse.next_count()?;
+        let mut vector = vec![];
+        let mut ef = None;
+        loop {
+            match parse.next_bytes() {
+                Ok(arg) if arg.eq_ignore_ascii_case(b"EF") => ef = Some(parse.next_count()?),
+                Ok(value) => vector.push(next_score(&value)? as f32),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(VIndexKnn { key, k, vector, ef })
+    }
+
+    /// Replies with each member followed by its distance, nearest first.
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.vindex_knn(&self.key, self.vector, self.k, self.ef).await {
+            Ok(found) => {
+                let mut items = vec![];
+                for (member, distance) in found {
+                    items.push(Entity::Bulk(member));
+                    items.push(Entity::Bulk(Bytes::from(distance.to_string())));
+                }
+                Entity::Array(items)
+            }
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::xrange::parse_id,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, stream::StreamId},
+};
+
+#[derive(Debug)]
+pub(crate) struct XAck {
+    key: Entity,
+    group: Bytes,
+    ids: Vec<StreamId>,
+}
+
+impl XAck {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<XAck, CacheError> {
+        let key = parse.next()?;
+        let group = parse.next_bytes()?;
+        let ids = parse
+            .rest_bytes()?
+            .iter()
+            .map(|id| parse_id(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        if ids.is_empty() {
+            return Err("ERR wrong number of arguments for 'xack' command".into());
+        }
+        Ok(XAck { key, group, ids })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.xack(&self.key, &self.group, &self.ids).await {
+            Ok(acked) => Entity::Integer(acked as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::xtrim::TrimArgs,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        stream::{StreamFields, Trim, XAddId},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct XAdd {
+    key: Entity,
+    id: XAddId,
+    fields: StreamFields,
+    no_mkstream: bool,
+    trim: Option<Trim>,
+}
+
+impl XAdd {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<XAdd, CacheError> {
+        let key = parse.next()?;
+        let mut no_mkstream = false;
+        let mut trim = TrimArgs::default();
+
+        let id = loop {
+            let arg = parse.next_bytes()?;
+            if arg.eq_ignore_ascii_case(b"NOMKSTREAM") {
+                no_mkstream = true;
+            } else if !trim.parse_option(&arg, parse)? {
+                break XAddId::parse(&arg)
+                    .ok_or("ERR Invalid stream ID specified as stream command argument")?;
+            }
+        };
+
+        let mut fields = vec![(parse.next_bytes()?, parse.next_bytes()?)];
+        loop {
+            match parse.next_bytes() {
+                Ok(field) => fields.push((field, parse.next_bytes()?)),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(XAdd {
+            key,
+            id,
+            fields,
+            no_mkstream,
+            trim: trim.finish()?,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let added = db
+            .xadd(self.key, self.id, self.fields, self.no_mkstream, self.trim)
+            .await;
+        let response = match added {
+            Ok(Some(id)) => Entity::Bulk(Bytes::from(id.to_string())),
+            Ok(None) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::{
+        xclaim::ids_response,
+        xrange::{entries_response, parse_id},
+    },
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, stream::StreamId},
+};
+
+#[derive(Debug)]
+pub(crate) struct XAutoClaim {
+    key: Entity,
+    group: Bytes,
+    consumer: Bytes,
+    min_idle: u64,
+    start: StreamId,
+    count: usize,
+    just_id: bool,
+}
+
+impl XAutoClaim {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<XAutoClaim, CacheError> {
+        let key = parse.next()?;
+        let group = parse.next_bytes()?;
+        let consumer = parse.next_bytes()?;
+        let min_idle = parse.next_int()?.max(0) as u64;
+        let start = parse_id(&parse.next_bytes()?)?;
+
+        let mut count = 100;
+        let mut just_id = false;
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "COUNT" => count = parse.next_count()?,
+                Ok(s) if s.to_uppercase() == "JUSTID" => just_id = true,
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(XAutoClaim {
+            key,
+            group,
+            consumer,
+            min_idle,
+            start,
+            count,
+            just_id,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let result = db
+            .xautoclaim(
+                &self.key,
+                &self.group,
+                &self.consumer,
+                self.min_idle,
+                self.start,
+                self.count,
+                self.just_id,
+            )
+            .await;
+        let response = match result {
+            Ok((cursor, claimed, deleted)) => {
+                let claimed = if self.just_id {
+                    ids_response(claimed.into_iter().map(|(id, _)| id))
+                } else {
+                    entries_response(claimed)
+                };
+                Entity::Array(vec![
+                    Entity::Bulk(Bytes::from(cursor.to_string())),
+                    claimed,
+                    ids_response(deleted.into_iter()),
+                ])
+            }
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::xrange::{entries_response, parse_id},
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        stream::{StreamId, group::ClaimOptions},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct XClaim {
+    key: Entity,
+    group: Bytes,
+    consumer: Bytes,
+    min_idle: u64,
+    ids: Vec<StreamId>,
+    options: ClaimOptions,
+}
+
+impl XClaim {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<XClaim, CacheError> {
+        let key = parse.next()?;
+        let group = parse.next_bytes()?;
+        let consumer = parse.next_bytes()?;
+        let min_idle = parse.next_int()?.max(0) as u64;
+
+        let mut args = parse.rest_bytes()?.into_iter().peekable();
+        let mut ids = vec![];
+        while let Some(id) = args.peek().and_then(|raw| StreamId::parse(raw, 0)) {
+            ids.push(id);
+            args.next();
+        }
+        if ids.is_empty() {
+            return Err("ERR Invalid stream ID specified as stream command argument".into());
+        }
+
+        let mut options = ClaimOptions::default();
+        while let Some(option) = args.next() {
+            let mut value = || -> Result<u64, CacheError> {
+                args.next()
+                    .as_deref()
+                    .and_then(|raw| str::from_utf8(raw).ok())
+                    .and_then(|raw| raw.parse::<i64>().ok())
+                    .map(|value| value.max(0) as u64)
+                    .ok_or_else(|| "ERR value is not an integer or out of range".into())
+            };
+            match &option.to_ascii_uppercase()[..] {
+                b"IDLE" => options.idle = Some(value()?),
+                b"TIME" => options.time = Some(value()?),
+                b"RETRYCOUNT" => options.retry_count = Some(value()?),
+                b"FORCE" => options.force = true,
+                b"JUSTID" => options.just_id = true,
+                b"LASTID" => {
+                    let raw = args.next().ok_or("ERR syntax error")?;
+                    options.last_id = Some(parse_id(&raw)?);
+                }
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        Ok(XClaim {
+            key,
+            group,
+            consumer,
+            min_idle,
+            ids,
+            options,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let just_id = self.options.just_id;
+        let result = db
+            .xclaim(
+                &self.key,
+                &self.group,
+                &self.consumer,
+                self.min_idle,
+                &self.ids,
+                self.options,
+            )
+            .await;
+        let response = match result {
+            Ok(claimed) if just_id => ids_response(claimed.into_iter().map(|(id, _)| id)),
+            Ok(claimed) => entries_response(claimed),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+pub(crate) fn ids_response(ids: impl Iterator<Item = StreamId>) -> Entity {
+    let mut response = Entity::array();
+    for id in ids {
+        response.push_bulk(Bytes::from(id.to_string()));
+    }
+    response
+}
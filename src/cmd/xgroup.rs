@@ -0,0 +1,147 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::xrange::parse_id,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, stream::group::GroupStart},
+};
+
+#[derive(Debug)]
+pub(crate) enum XGroup {
+    Create {
+        key: Entity,
+        group: Bytes,
+        start: GroupStart,
+        mkstream: bool,
+    },
+    SetId {
+        key: Entity,
+        group: Bytes,
+        start: GroupStart,
+    },
+    Destroy {
+        key: Entity,
+        group: Bytes,
+    },
+    CreateConsumer {
+        key: Entity,
+        group: Bytes,
+        consumer: Bytes,
+    },
+    DelConsumer {
+        key: Entity,
+        group: Bytes,
+        consumer: Bytes,
+    },
+}
+
+impl XGroup {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<XGroup, CacheError> {
+        let subcommand = parse.next_string()?.to_uppercase();
+        let key = parse.next()?;
+        let group = parse.next_bytes()?;
+
+        let command = match &subcommand[..] {
+            "CREATE" => {
+                let start = parse_start(&parse.next_bytes()?)?;
+                let mut mkstream = false;
+                loop {
+                    match parse.next_string() {
+                        Ok(s) if s.to_uppercase() == "MKSTREAM" => mkstream = true,
+                        Ok(s) if s.to_uppercase() == "ENTRIESREAD" => {
+                            parse.next_int()?;
+                        }
+                        Ok(_) => return Err("ERR syntax error".into()),
+                        Err(CacheError::EndOfStream) => break,
+                        Err(err) => return Err(err),
+                    }
+                }
+                XGroup::Create {
+                    key,
+                    group,
+                    start,
+                    mkstream,
+                }
+            }
+            "SETID" => {
+                let start = parse_start(&parse.next_bytes()?)?;
+                match parse.next_string() {
+                    Ok(s) if s.to_uppercase() == "ENTRIESREAD" => {
+                        parse.next_int()?;
+                    }
+                    Ok(_) => return Err("ERR syntax error".into()),
+                    Err(CacheError::EndOfStream) => {}
+                    Err(err) => return Err(err),
+                }
+                XGroup::SetId { key, group, start }
+            }
+            "DESTROY" => XGroup::Destroy { key, group },
+            "CREATECONSUMER" => XGroup::CreateConsumer {
+                key,
+                group,
+                consumer: parse.next_bytes()?,
+            },
+            "DELCONSUMER" => XGroup::DelConsumer {
+                key,
+                group,
+                consumer: parse.next_bytes()?,
+            },
+            _ => {
+                return Err(format!("ERR unknown subcommand '{}' for 'XGROUP'", subcommand).into());
+            }
+        };
+        Ok(command)
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let result = match self {
+            XGroup::Create {
+                key,
+                group,
+                start,
+                mkstream,
+            } => db
+                .xgroup_create(key, group, start, mkstream)
+                .await
+                .map(|()| Entity::Simple("OK".to_string())),
+            XGroup::SetId { key, group, start } => db
+                .xgroup_setid(&key, &group, start)
+                .await
+                .map(|()| Entity::Simple("OK".to_string())),
+            XGroup::Destroy { key, group } => db
+                .xgroup_destroy(&key, &group)
+                .await
+                .map(|destroyed| Entity::Integer(destroyed as i64)),
+            XGroup::CreateConsumer {
+                key,
+                group,
+                consumer,
+            } => db
+                .xgroup_create_consumer(&key, &group, &consumer)
+                .await
+                .map(|created| Entity::Integer(created as i64)),
+            XGroup::DelConsumer {
+                key,
+                group,
+                consumer,
+            } => db
+                .xgroup_del_consumer(&key, &group, &consumer)
+                .await
+                .map(|pending| Entity::Integer(pending as i64)),
+        };
+        let response = result.unwrap_or_else(|err| Entity::Error(err.to_string()));
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+fn parse_start(raw: &[u8]) -> Result<GroupStart, CacheError> {
+    if raw == b"$" {
+        return Ok(GroupStart::Last);
+    }
+    parse_id(raw).map(GroupStart::Id)
+}
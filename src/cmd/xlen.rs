@@ -0,0 +1,30 @@
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity},
+};
+
+#[derive(Debug)]
+pub(crate) struct XLen {
+    key: Entity,
+}
+
+impl XLen {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<XLen, CacheError> {
+        let key = parse.next()?;
+        Ok(XLen { key })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.xlen(&self.key).await {
+            Ok(len) => Entity::Integer(len as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::xrange::parse_bound,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, stream::group::PendingQuery},
+};
+
+#[derive(Debug)]
+pub(crate) struct XPending {
+    key: Entity,
+    group: Bytes,
+    /// `None` for the summary form.
+    query: Option<PendingQuery>,
+}
+
+impl XPending {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<XPending, CacheError> {
+        let key = parse.next()?;
+        let group = parse.next_bytes()?;
+
+        let mut args = parse.rest_bytes()?.into_iter();
+        let Some(mut first) = args.next() else {
+            return Ok(XPending {
+                key,
+                group,
+                query: None,
+            });
+        };
+        let mut min_idle = 0;
+        if first.eq_ignore_ascii_case(b"IDLE") {
+            min_idle = args
+                .next()
+                .as_deref()
+                .and_then(|raw| str::from_utf8(raw).ok())
+                .and_then(|raw| raw.parse::<u64>().ok())
+                .ok_or("ERR value is not an integer or out of range")?;
+            first = args.next().ok_or("ERR syntax error")?;
+        }
+        let (Some(end), Some(count)) = (args.next(), args.next()) else {
+            return Err("ERR syntax error".into());
+        };
+        let count = str::from_utf8(&count)
+            .ok()
+            .and_then(|count| count.parse::<i64>().ok())
+            .ok_or("ERR value is not an integer or out of range")?;
+        let consumer = args.next();
+        if args.next().is_some() {
+            return Err("ERR syntax error".into());
+        }
+
+        Ok(XPending {
+            key,
+            group,
+            query: Some(PendingQuery {
+                min_idle,
+                start: parse_bound(&first, b"-", 0)?,
+                end: parse_bound(&end, b"+", u64::MAX)?,
+                count: count.max(0) as usize,
+                consumer,
+            }),
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let result = match &self.query {
+            None => db
+                .xpending_summary(&self.key, &self.group)
+                .await
+                .map(|summary| {
+                    let Some((first, last)) = summary.bounds else {
+                        return Entity::Array(vec![
+                            Entity::Integer(0),
+                            Entity::Null,
+                            Entity::Null,
+                            Entity::Null,
+                        ]);
+                    };
+                    let mut consumers = Entity::array();
+                    for (consumer, count) in summary.consumers {
+                        consumers.push(Entity::Array(vec![
+                            Entity::Bulk(consumer),
+                            Entity::Bulk(Bytes::from(count.to_string())),
+                        ]));
+                    }
+                    Entity::Array(vec![
+                        Entity::Integer(summary.count as i64),
+                        Entity::Bulk(Bytes::from(first.to_string())),
+                        Entity::Bulk(Bytes::from(last.to_string())),
+                        consumers,
+                    ])
+                }),
+            Some(query) => db
+                .xpending(&self.key, &self.group, query)
+                .await
+                .map(|details| {
+                    let mut response = Entity::array();
+                    for detail in details {
+                        response.push(Entity::Array(vec![
+                            Entity::Bulk(Bytes::from(detail.id.to_string())),
+                            Entity::Bulk(detail.consumer),
+                            Entity::Integer(detail.idle as i64),
+                            Entity::Integer(detail.delivery_count as i64),
+                        ]));
+                    }
+                    response
+                }),
+        };
+        let response = result.unwrap_or_else(|err| Entity::Error(err.to_string()));
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
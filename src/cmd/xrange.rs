@@ -0,0 +1,122 @@
+use std::ops::Bound;
+
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        stream::{StreamFields, StreamId},
+    },
+};
+
+/// `XRANGE`, and `XREVRANGE` when `rev` is set.
+#[derive(Debug)]
+pub(crate) struct XRange {
+    key: Entity,
+    start: Bound<StreamId>,
+    end: Bound<StreamId>,
+    count: Option<usize>,
+    rev: bool,
+}
+
+impl XRange {
+    pub(crate) fn parse_frames(parse: &mut Parse, rev: bool) -> Result<XRange, CacheError> {
+        let key = parse.next()?;
+        let first = parse.next_bytes()?;
+        let second = parse.next_bytes()?;
+        // `XREVRANGE` takes the end of the range first.
+        let (start, end) = if rev {
+            (second, first)
+        } else {
+            (first, second)
+        };
+        let start = parse_bound(&start, b"-", 0)?;
+        let end = parse_bound(&end, b"+", u64::MAX)?;
+
+        let count = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "COUNT" => Some(parse.next_int()?.max(0) as usize),
+            Ok(_) => return Err("ERR syntax error".into()),
+            Err(CacheError::EndOfStream) => None,
+            Err(err) => return Err(err),
+        };
+
+        Ok(XRange {
+            key,
+            start,
+            end,
+            count,
+            rev,
+        })
+    }
+
+    pub(crate) fn get_name(&self) -> &str {
+        if self.rev { "xrevrange" } else { "xrange" }
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let entries = db
+            .xrange(&self.key, self.start, self.end, self.count, self.rev)
+            .await;
+        let response = match entries {
+            Ok(entries) => entries_response(entries),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Parses a range endpoint: `unbounded` (`-` or `+`), an ID, or an ID
+/// prefixed with `(` for an exclusive bound. A bare millisecond value gets
+/// `default_seq` as its sequence.
+pub(crate) fn parse_bound(
+    raw: &[u8],
+    unbounded: &[u8],
+    default_seq: u64,
+) -> Result<Bound<StreamId>, CacheError> {
+    if raw == unbounded {
+        return Ok(Bound::Unbounded);
+    }
+    match raw.split_first() {
+        Some((b'(', rest)) => StreamId::parse(rest, default_seq)
+            .map(Bound::Excluded)
+            .ok_or_else(|| INVALID.into()),
+        _ => StreamId::parse(raw, default_seq)
+            .map(Bound::Included)
+            .ok_or_else(|| INVALID.into()),
+    }
+}
+
+/// Parses an exact entry ID; a bare millisecond value gets sequence `0`.
+pub(crate) fn parse_id(raw: &[u8]) -> Result<StreamId, CacheError> {
+    StreamId::parse(raw, 0).ok_or_else(|| INVALID.into())
+}
+
+const INVALID: &str = "ERR Invalid stream ID specified as stream command argument";
+
+/// Renders entries as `[[id, [field, value, ...]], ...]`.
+pub(crate) fn entries_response(entries: Vec<(StreamId, StreamFields)>) -> Entity {
+    let mut response = Entity::array();
+    for (id, fields) in entries {
+        response.push(Entity::Array(vec![
+            Entity::Bulk(Bytes::from(id.to_string())),
+            fields_response(fields),
+        ]));
+    }
+    response
+}
+
+pub(crate) fn fields_response(fields: StreamFields) -> Entity {
+    let mut pairs = Entity::array();
+    for (field, value) in fields {
+        pairs.push_bulk(field);
+        pairs.push_bulk(value);
+    }
+    pairs
+}
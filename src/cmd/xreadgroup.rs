@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::{
+    cmd::xrange::{fields_response, parse_id},
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    shutdown::Shutdown,
+    storage::{
+        Db,
+        entity::Entity,
+        stream::group::{ReadId, ReadResult},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct XReadGroup {
+    group: Bytes,
+    consumer: Bytes,
+    count: Option<usize>,
+    block: Option<Duration>,
+    no_ack: bool,
+    streams: Vec<(Entity, ReadId)>,
+}
+
+impl XReadGroup {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<XReadGroup, CacheError> {
+        if parse.next_string()?.to_uppercase() != "GROUP" {
+            return Err("ERR syntax error".into());
+        }
+        let group = parse.next_bytes()?;
+        let consumer = parse.next_bytes()?;
+
+        let mut count = None;
+        let mut block = None;
+        let mut no_ack = false;
+        loop {
+            match &parse.next_string()?.to_uppercase()[..] {
+                // As in Redis, `COUNT 0` means no limit.
+                "COUNT" => count = usize::try_from(parse.next_int()?).ok().filter(|n| *n > 0),
+                "BLOCK" => {
+                    let ms = parse.next_int()?;
+                    if ms < 0 {
+                        return Err("ERR timeout is negative".into());
+                    }
+                    block = Some(Duration::from_millis(ms as u64));
+                }
+                "NOACK" => no_ack = true,
+                "STREAMS" => break,
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        let mut args = parse.rest_bytes()?;
+        if args.is_empty() || args.len() % 2 != 0 {
+            return Err("ERR Unbalanced 'xreadgroup' list of streams: for each stream key an ID or '>' must be specified.".into());
+        }
+        let ids = args.split_off(args.len() / 2);
+        let streams = args
+            .into_iter()
+            .zip(ids)
+            .map(|(key, id)| {
+                let id = if &id[..] == b">" {
+                    ReadId::New
+                } else {
+                    ReadId::Pending(parse_id(&id)?)
+                };
+                Ok((Entity::Bulk(key), id))
+            })
+            .collect::<Result<_, CacheError>>()?;
+
+        Ok(XReadGroup {
+            group,
+            consumer,
+            count,
+            block,
+            no_ack,
+            streams,
+        })
+    }
+
+    /// Reads once, and with `BLOCK` keeps the connection parked until an
+    /// `XADD` delivers something, the timeout (`0` = forever) elapses or the
+    /// server shuts down.
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> Result<(), CacheError> {
+        // Only reads of new entries can block; history reads answer at once.
+        let can_block = self.streams.iter().all(|(_, id)| *id == ReadId::New);
+        let deadline = match self.block {
+            // A timeout too far away for an `Instant` blocks forever.
+            Some(timeout) if can_block && !timeout.is_zero() => Instant::now().checked_add(timeout),
+            _ => None,
+        };
+
+        let response = loop {
+            let written = db.stream_written();
+            tokio::pin!(written);
+            written.as_mut().enable();
+
+            let read = db
+                .xreadgroup(
+                    &self.group,
+                    &self.consumer,
+                    &self.streams,
+                    self.count,
+                    self.no_ack,
+                )
+                .await;
+            match read {
+                Ok(result) if result.is_empty() && can_block && self.block.is_some() => {}
+                Ok(result) if result.is_empty() => break Entity::Null,
+                Ok(result) => break read_response(result),
+                Err(err) => break Entity::Error(err.to_string()),
+            }
+
+            tokio::select! {
+                _ = &mut written => {}
+                _ = sleep_until(deadline) => break Entity::Null,
+                _ = shutdown.recv() => return Ok(()),
+            }
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Sleeps until `deadline`, or forever when there is none.
+pub(crate) async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+fn read_response(result: ReadResult) -> Entity {
+    let mut response = Entity::array();
+    for (key, entries) in result {
+        let mut items = Entity::array();
+        for (id, fields) in entries {
+            let fields = fields.map(fields_response).unwrap_or(Entity::Null);
+            items.push(Entity::Array(vec![
+                Entity::Bulk(Bytes::from(id.to_string())),
+                fields,
+            ]));
+        }
+        response.push(Entity::Array(vec![key, items]));
+    }
+    response
+}
@@ -0,0 +1,109 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        stream::{StreamId, Trim, TrimStrategy},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct XTrim {
+    key: Entity,
+    trim: Trim,
+}
+
+/// Accumulates `MAXLEN`/`MINID [=|~] threshold` and `LIMIT count` arguments,
+/// which `XADD` and `XTRIM` share.
+#[derive(Debug, Default)]
+pub(crate) struct TrimArgs {
+    strategy: Option<TrimStrategy>,
+    approximate: bool,
+    limit: Option<usize>,
+}
+
+impl TrimArgs {
+    /// Consumes `arg` and its operands if it is a trimming option. Returns
+    /// `false` when `arg` is not one, leaving it for the caller.
+    pub(crate) fn parse_option(
+        &mut self,
+        arg: &[u8],
+        parse: &mut Parse,
+    ) -> Result<bool, CacheError> {
+        match &arg.to_ascii_uppercase()[..] {
+            b"MAXLEN" => {
+                let threshold = self.threshold(parse)?;
+                let max_len = str::from_utf8(&threshold)
+                    .ok()
+                    .and_then(|raw| raw.parse().ok())
+                    .ok_or("ERR value is not an integer or out of range")?;
+                self.strategy = Some(TrimStrategy::MaxLen(max_len));
+            }
+            b"MINID" => {
+                let threshold = self.threshold(parse)?;
+                let min_id = StreamId::parse(&threshold, 0)
+                    .ok_or("ERR Invalid stream ID specified as stream command argument")?;
+                self.strategy = Some(TrimStrategy::MinId(min_id));
+            }
+            b"LIMIT" => self.limit = Some(parse.next_int()?.max(0) as usize),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn threshold(&mut self, parse: &mut Parse) -> Result<Bytes, CacheError> {
+        let arg = parse.next_bytes()?;
+        match &arg[..] {
+            b"~" => {
+                self.approximate = true;
+                parse.next_bytes()
+            }
+            b"=" => parse.next_bytes(),
+            _ => Ok(arg),
+        }
+    }
+
+    pub(crate) fn finish(self) -> Result<Option<Trim>, CacheError> {
+        if self.limit.is_some() && !self.approximate {
+            return Err(
+                "ERR syntax error, LIMIT cannot be used without the special ~ option".into(),
+            );
+        }
+        Ok(self.strategy.map(|strategy| Trim {
+            strategy,
+            limit: self.limit,
+        }))
+    }
+}
+
+impl XTrim {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<XTrim, CacheError> {
+        let key = parse.next()?;
+        let mut args = TrimArgs::default();
+        loop {
+            match parse.next_bytes() {
+                Ok(arg) if args.parse_option(&arg, parse)? => {}
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        let trim = args.finish()?.ok_or("ERR syntax error")?;
+        Ok(XTrim { key, trim })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.xtrim(&self.key, self.trim).await {
+            Ok(removed) => Entity::Integer(removed as i64),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
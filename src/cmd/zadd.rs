@@ -0,0 +1,104 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        sorted_set::{ZAddFlags, ZAddOutcome, format_score, parse_score},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct ZAdd {
+    key: Entity,
+    pairs: Vec<(f64, Bytes)>,
+    flags: ZAddFlags,
+    ch: bool,
+    incr: bool,
+}
+
+impl ZAdd {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<ZAdd, CacheError> {
+        let key = parse.next()?;
+        let mut flags = ZAddFlags::default();
+        let mut ch = false;
+        let mut incr = false;
+
+        let score = loop {
+            let arg = parse.next_bytes()?;
+            match &arg.to_ascii_uppercase()[..] {
+                b"NX" => flags.nx = true,
+                b"XX" => flags.xx = true,
+                b"GT" => flags.gt = true,
+                b"LT" => flags.lt = true,
+                b"CH" => ch = true,
+                b"INCR" => incr = true,
+                _ => break next_score(&arg)?,
+            }
+        };
+
+        let mut pairs = vec![(score, parse.next_bytes()?)];
+        loop {
+            match parse.next_bytes() {
+                Ok(score) => pairs.push((next_score(&score)?, parse.next_bytes()?)),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        if flags.nx && flags.xx {
+            return Err("ERR XX and NX options at the same time are not compatible".into());
+        }
+        if (flags.gt && flags.lt) || ((flags.gt || flags.lt) && flags.nx) {
+            return Err("ERR GT, LT, and/or NX options at the same time are not compatible".into());
+        }
+        if incr && pairs.len() > 1 {
+            return Err("ERR INCR option supports a single increment-element pair".into());
+        }
+
+        Ok(ZAdd {
+            key,
+            pairs,
+            flags,
+            ch,
+            incr,
+        })
+    }
+
+    pub(crate) async fn apply(mut self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = if self.incr {
+            let (increment, member) = self.pairs.remove(0);
+            match db.zincrby(self.key, increment, member, self.flags).await {
+                Ok(Some(score)) => Entity::Bulk(format_score(score)),
+                Ok(None) => Entity::Null,
+                Err(err) => Entity::Error(err.to_string()),
+            }
+        } else {
+            match db.zadd(self.key, self.pairs, self.flags).await {
+                Ok(outcomes) => {
+                    let counted = outcomes
+                        .into_iter()
+                        .filter(|outcome| {
+                            *outcome == ZAddOutcome::Added
+                                || (self.ch && *outcome == ZAddOutcome::Updated)
+                        })
+                        .count();
+                    Entity::Integer(counted as i64)
+                }
+                Err(err) => Entity::Error(err.to_string()),
+            }
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Reads a score argument, rejecting anything that is not a float.
+pub(crate) fn next_score(raw: &[u8]) -> Result<f64, CacheError> {
+    parse_score(raw).ok_or_else(|| "ERR value is not a valid float".into())
+}
@@ -0,0 +1,48 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    cmd::zadd::next_score,
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        sorted_set::{ZAddFlags, format_score},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct ZIncrBy {
+    key: Entity,
+    increment: f64,
+    member: Bytes,
+}
+
+impl ZIncrBy {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<ZIncrBy, CacheError> {
+        let key = parse.next()?;
+        let increment = next_score(&parse.next_bytes()?)?;
+        let member = parse.next_bytes()?;
+        Ok(ZIncrBy {
+            key,
+            increment,
+            member,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let incremented = db
+            .zincrby(self.key, self.increment, self.member, ZAddFlags::default())
+            .await;
+        let response = match incremented {
+            Ok(Some(score)) => Entity::Bulk(format_score(score)),
+            Ok(None) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
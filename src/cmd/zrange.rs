@@ -0,0 +1,123 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        sorted_set::{RangeBy, RangeSpec, format_score, parse_lex_bound, parse_score_bound},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct ZRange {
+    key: Entity,
+    spec: RangeSpec,
+    with_scores: bool,
+}
+
+enum By {
+    Rank,
+    Score,
+    Lex,
+}
+
+impl ZRange {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<ZRange, CacheError> {
+        let key = parse.next()?;
+        let start = parse.next_bytes()?;
+        let stop = parse.next_bytes()?;
+
+        let mut by = By::Rank;
+        let mut rev = false;
+        let mut limit = None;
+        let mut with_scores = false;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "BYSCORE" => by = By::Score,
+                Ok(s) if s.to_uppercase() == "BYLEX" => by = By::Lex,
+                Ok(s) if s.to_uppercase() == "REV" => rev = true,
+                Ok(s) if s.to_uppercase() == "LIMIT" => limit = Some(parse_limit(parse)?),
+                Ok(s) if s.to_uppercase() == "WITHSCORES" => with_scores = true,
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        // `REV` queries name the upper bound first.
+        let (min, max) = if rev {
+            (stop.clone(), start.clone())
+        } else {
+            (start.clone(), stop.clone())
+        };
+        let by = match by {
+            By::Rank if limit.is_some() => {
+                return Err("ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX".into());
+            }
+            By::Rank => RangeBy::Rank(parse_index(&start)?, parse_index(&stop)?),
+            By::Score => RangeBy::Score(
+                parse_score_bound(&min).ok_or("ERR min or max is not a float")?,
+                parse_score_bound(&max).ok_or("ERR min or max is not a float")?,
+            ),
+            By::Lex if with_scores => {
+                return Err(
+                    "ERR syntax error, WITHSCORES not supported in combination with BYLEX".into(),
+                );
+            }
+            By::Lex => RangeBy::Lex(
+                parse_lex_bound(&min).ok_or("ERR min or max not valid string range item")?,
+                parse_lex_bound(&max).ok_or("ERR min or max not valid string range item")?,
+            ),
+        };
+
+        Ok(ZRange {
+            key,
+            spec: RangeSpec { by, rev, limit },
+            with_scores,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.zrange(&self.key, &self.spec).await {
+            Ok(items) => range_response(items, self.with_scores),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+/// Reads the `offset count` pair following `LIMIT`. A negative count means
+/// "everything after the offset", while a negative offset selects nothing.
+pub(crate) fn parse_limit(parse: &mut Parse) -> Result<(usize, Option<usize>), CacheError> {
+    let offset = parse.next_int()?;
+    let count = parse.next_int()?;
+    if offset < 0 {
+        return Ok((0, Some(0)));
+    }
+    Ok((offset as usize, (count >= 0).then_some(count as usize)))
+}
+
+pub(crate) fn range_response(items: Vec<(Bytes, f64)>, with_scores: bool) -> Entity {
+    let mut response = Entity::array();
+    for (member, score) in items {
+        response.push_bulk(member);
+        if with_scores {
+            response.push_bulk(format_score(score));
+        }
+    }
+    response
+}
+
+fn parse_index(raw: &[u8]) -> Result<i64, CacheError> {
+    str::from_utf8(raw)
+        .ok()
+        .and_then(|raw| raw.parse().ok())
+        .ok_or_else(|| "ERR value is not an integer or out of range".into())
+}
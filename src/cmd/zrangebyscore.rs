@@ -0,0 +1,61 @@
+use tracing::debug;
+
+use crate::{
+    cmd::zrange::{parse_limit, range_response},
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{
+        Db,
+        entity::Entity,
+        sorted_set::{RangeBy, RangeSpec, parse_score_bound},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct ZRangeByScore {
+    key: Entity,
+    spec: RangeSpec,
+    with_scores: bool,
+}
+
+impl ZRangeByScore {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<ZRangeByScore, CacheError> {
+        let key = parse.next()?;
+        let min = parse_score_bound(&parse.next_bytes()?).ok_or("ERR min or max is not a float")?;
+        let max = parse_score_bound(&parse.next_bytes()?).ok_or("ERR min or max is not a float")?;
+
+        let mut limit = None;
+        let mut with_scores = false;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "LIMIT" => limit = Some(parse_limit(parse)?),
+                Ok(s) if s.to_uppercase() == "WITHSCORES" => with_scores = true,
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(ZRangeByScore {
+            key,
+            spec: RangeSpec {
+                by: RangeBy::Score(min, max),
+                rev: false,
+                limit,
+            },
+            with_scores,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.zrange(&self.key, &self.spec).await {
+            Ok(items) => range_response(items, self.with_scores),
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
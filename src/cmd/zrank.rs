@@ -0,0 +1,49 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, sorted_set::format_score},
+};
+
+#[derive(Debug)]
+pub(crate) struct ZRank {
+    key: Entity,
+    member: Bytes,
+    with_score: bool,
+}
+
+impl ZRank {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<ZRank, CacheError> {
+        let key = parse.next()?;
+        let member = parse.next_bytes()?;
+        let with_score = match parse.next_string() {
+            Ok(s) if s.to_uppercase() == "WITHSCORE" => true,
+            Ok(_) => return Err("ERR syntax error".into()),
+            Err(CacheError::EndOfStream) => false,
+            Err(err) => return Err(err),
+        };
+        Ok(ZRank {
+            key,
+            member,
+            with_score,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let response = match db.zrank(&self.key, &self.member).await {
+            Ok(Some((rank, score))) if self.with_score => Entity::Array(vec![
+                Entity::Integer(rank as i64),
+                Entity::Bulk(format_score(score)),
+            ]),
+            Ok(Some((rank, _))) => Entity::Integer(rank as i64),
+            Ok(None) => Entity::Null,
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
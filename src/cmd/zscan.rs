@@ -0,0 +1,64 @@
+use bytes::Bytes;
+use tracing::debug;
+
+use crate::{
+    connection::Connection,
+    error::CacheError,
+    parse::Parse,
+    storage::{Db, entity::Entity, scan::DEFAULT_COUNT, sorted_set::format_score},
+};
+
+#[derive(Debug)]
+pub(crate) struct ZScan {
+    key: Entity,
+    cursor: u64,
+    pattern: Option<Bytes>,
+    count: usize,
+}
+
+impl ZScan {
+    /// `ZSCAN key cursor [MATCH pattern] [COUNT count]`.
+    pub(crate) fn parse_frames(parse: &mut Parse) -> Result<ZScan, CacheError> {
+        let key = parse.next()?;
+        let cursor = parse.next_cursor()?;
+        let mut pattern = None;
+        let mut count = DEFAULT_COUNT;
+
+        loop {
+            match parse.next_string() {
+                Ok(s) if s.to_uppercase() == "MATCH" => pattern = Some(parse.next_bytes()?),
+                Ok(s) if s.to_uppercase() == "COUNT" => count = parse.next_count()?,
+                Ok(_) => return Err("ERR syntax error".into()),
+                Err(CacheError::EndOfStream) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(ZScan {
+            key,
+            cursor,
+            pattern,
+            count,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> Result<(), CacheError> {
+        let page = db
+            .zscan(&self.key, self.cursor, self.pattern.as_deref(), self.count)
+            .await;
+        let response = match page {
+            Ok((next, pairs)) => {
+                let mut items = Entity::array();
+                for (member, score) in pairs {
+                    items.push_bulk(member);
+                    items.push_bulk(format_score(score));
+                }
+                Entity::Array(vec![Entity::Bulk(Bytes::from(next.to_string())), items])
+            }
+            Err(err) => Entity::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
@@ -55,16 +55,7 @@ impl Connection {
     }
 
     pub async fn write_frame(&mut self, entity: &Entity) -> io::Result<()> {
-        match entity {
-            Entity::Array(val) => {
-                self.stream.write_u8(b'*').await?;
-                self.write_decimal(val.len() as i64).await?;
-                for e in &**val {
-                    self.write_value(e).await?;
-                }
-            }
-            _ => self.write_value(entity).await?,
-        }
+        self.write_value(entity).await?;
         self.stream.flush().await
     }
 
@@ -94,7 +85,13 @@ impl Connection {
                 self.stream.write_all(val).await?;
                 self.stream.write_all(b"\r\n").await?;
             }
-            Entity::Array(_) => unreachable!(),
+            Entity::Array(val) => {
+                self.stream.write_u8(b'*').await?;
+                self.write_decimal(val.len() as i64).await?;
+                for e in val {
+                    Box::pin(self.write_value(e)).await?;
+                }
+            }
         }
         Ok(())
     }
@@ -7,14 +7,30 @@ const INVALID_FRAME: &str = "protocol error: invalid frame format";
 pub enum CacheError {
     EndOfStream,
     Incomplete,
+    WrongType,
     Other(String),
 }
 
+impl CacheError {
+    /// Whether the client sent something that is not a command frame at all,
+    /// after which the connection cannot go on. Other errors are replies.
+    pub(crate) fn is_protocol(&self) -> bool {
+        match self {
+            CacheError::EndOfStream | CacheError::Incomplete => true,
+            CacheError::WrongType => false,
+            CacheError::Other(err) => err.starts_with("protocol error"),
+        }
+    }
+}
+
 impl fmt::Display for CacheError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CacheError::EndOfStream => "unexpected end of stream".fmt(f),
             CacheError::Incomplete => "stream ended early".fmt(f),
+            CacheError::WrongType => {
+                "WRONGTYPE Operation against a key holding the wrong kind of value".fmt(f)
+            }
             CacheError::Other(err) => err.fmt(f),
         }
     }
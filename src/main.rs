@@ -12,6 +12,7 @@ mod shutdown;
 mod storage;
 
 const DEFAULT_PORT: u16 = 6789;
+const DEFAULT_DATABASES: u64 = 16;
 
 pub type BoxedError = Box<dyn Error + Send + Sync>;
 
@@ -24,6 +25,9 @@ fn set_up_loggin() {
 struct Cli {
     #[arg(long)]
     port: Option<u16>,
+    /// Number of databases clients can `SELECT`.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    databases: Option<u64>,
 }
 
 #[tokio::main]
@@ -32,10 +36,11 @@ async fn main() -> Result<(), BoxedError> {
 
     let cli = Cli::parse();
     let port = cli.port.unwrap_or(DEFAULT_PORT);
+    let databases = cli.databases.unwrap_or(DEFAULT_DATABASES) as usize;
 
     let listener = TcpListener::bind(&format!("127.0.0.1:{}", port)).await?;
 
-    server::run(listener, signal::ctrl_c()).await;
+    server::run(listener, databases, signal::ctrl_c()).await;
 
     Ok(())
 }
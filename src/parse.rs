@@ -2,18 +2,134 @@ use bytes::Bytes;
 
 use crate::{
     cmd::{
+        append::Append,
+        bf_add::BfAdd,
+        bf_exists::BfExists,
+        bf_reserve::BfReserve,
+        bitcount::BitCount,
+        bitfield::BitField,
+        bitop::BitOp,
+        bitpos::BitPos,
+        blmove::BLMove,
+        bpop::BPop,
+        cf_add::CfAdd,
+        cf_del::CfDel,
+        cf_exists::CfExists,
+        cf_reserve::CfReserve,
+        cms_incrby::CmsIncrBy,
+        cms_initbydim::CmsInitByDim,
+        cms_query::CmsQuery,
+        copy::CopyKey,
+        dbsize::DbSize,
         del::Del,
+        exists::Exists,
+        expire::Expire,
+        expiretime::ExpireTime,
+        flush::Flush,
+        ft_create::FtCreate,
+        ft_dropindex::FtDropIndex,
+        ft_search::FtSearch,
+        geoadd::GeoAdd,
+        geodist::GeoDist,
+        geohash::GeoHash,
+        geopos::GeoPos,
+        geosearch::GeoSearch,
         get::Get,
+        getbit::GetBit,
+        getdel::GetDel,
+        getex::GetEx,
+        getrange::GetRange,
+        getset::GetSet,
+        hdel::HDel,
+        hexpire::HExpire,
+        hget::HGet,
+        hgetall::HGetAll,
+        hincrby::HIncrBy,
+        hmget::HMGet,
+        hpersist::HPersist,
+        hscan::HScan,
+        hset::HSet,
+        httl::HTtl,
+        incr::Incr,
+        incrbyfloat::IncrByFloat,
+        json_arrappend::JsonArrAppend,
+        json_del::JsonDel,
+        json_get::JsonGet,
+        json_numincrby::JsonNumIncrBy,
+        json_set::JsonSet,
+        key_type::Type,
+        keys::Keys,
+        lindex::LIndex,
+        llen::LLen,
+        lrange::LRange,
+        lrem::LRem,
+        lset::LSet,
+        ltrim::LTrim,
+        mget::MGet,
+        move_key::Move,
+        mset::MSet,
+        persist::Persist,
+        pfadd::PfAdd,
+        pfcount::PfCount,
+        pfmerge::PfMerge,
         ping::Ping,
+        pop::Pop,
         publish::Publish,
+        push::Push,
+        randomkey::RandomKey,
+        rename::Rename,
+        sadd::SAdd,
+        scan::Scan,
+        scard::SCard,
+        select::Select,
         set::Set,
+        setbit::SetBit,
+        setop::SetOp,
+        setop::SetOpStore,
+        setrange::SetRange,
+        sismember::SIsMember,
+        smembers::SMembers,
+        srem::SRem,
+        sscan::SScan,
+        strlen::StrLen,
         subscribe::{Subscribe, Unsubscribe},
+        swapdb::SwapDb,
+        topk_add::TopKAdd,
+        topk_list::TopKList,
+        topk_reserve::TopKReserve,
+        ts_add::TsAdd,
+        ts_create::TsCreate,
+        ts_createrule::TsCreateRule,
+        ts_mrange::TsMRange,
+        ts_range::TsRange,
+        ttl::Ttl,
         unknown::Unknown,
+        vindex_add::VIndexAdd,
+        vindex_create::VIndexCreate,
+        vindex_del::VIndexDel,
+        vindex_knn::VIndexKnn,
+        xack::XAck,
+        xadd::XAdd,
+        xautoclaim::XAutoClaim,
+        xclaim::XClaim,
+        xgroup::XGroup,
+        xlen::XLen,
+        xpending::XPending,
+        xrange::XRange,
+        xreadgroup::XReadGroup,
+        xtrim::XTrim,
+        zadd::ZAdd,
+        zincrby::ZIncrBy,
+        zrange::ZRange,
+        zrangebyscore::ZRangeByScore,
+        zrank::ZRank,
+        zrem::ZRem,
+        zscan::ZScan,
     },
     connection::Connection,
     error::CacheError,
     shutdown::Shutdown,
-    storage::{Db, entity::Entity},
+    storage::{databases::Databases, entity::Entity, list::End, set::SetOperation},
 };
 use std::vec;
 
@@ -23,6 +139,122 @@ pub enum Command {
     Publish(Publish),
     Set(Set),
     Del(Del),
+    Push(Push),
+    Pop(Pop),
+    LRange(LRange),
+    LLen(LLen),
+    LIndex(LIndex),
+    LSet(LSet),
+    LRem(LRem),
+    LTrim(LTrim),
+    HSet(HSet),
+    HGet(HGet),
+    HMGet(HMGet),
+    HDel(HDel),
+    HGetAll(HGetAll),
+    HIncrBy(HIncrBy),
+    HScan(HScan),
+    SAdd(SAdd),
+    SRem(SRem),
+    SIsMember(SIsMember),
+    SMembers(SMembers),
+    SCard(SCard),
+    SetOp(SetOp),
+    SetOpStore(SetOpStore),
+    ZAdd(ZAdd),
+    ZRange(ZRange),
+    ZRangeByScore(ZRangeByScore),
+    ZRank(ZRank),
+    ZIncrBy(ZIncrBy),
+    ZRem(ZRem),
+    XAdd(XAdd),
+    XLen(XLen),
+    XTrim(XTrim),
+    XRange(XRange),
+    XGroup(XGroup),
+    XAck(XAck),
+    XPending(XPending),
+    XClaim(XClaim),
+    XAutoClaim(XAutoClaim),
+    XReadGroup(XReadGroup),
+    BPop(BPop),
+    BLMove(BLMove),
+    Incr(Incr),
+    IncrByFloat(IncrByFloat),
+    Append(Append),
+    StrLen(StrLen),
+    GetRange(GetRange),
+    SetRange(SetRange),
+    GetDel(GetDel),
+    GetEx(GetEx),
+    GetSet(GetSet),
+    SetBit(SetBit),
+    GetBit(GetBit),
+    BitCount(BitCount),
+    BitPos(BitPos),
+    BitOp(BitOp),
+    BitField(BitField),
+    PfAdd(PfAdd),
+    PfCount(PfCount),
+    PfMerge(PfMerge),
+    GeoAdd(GeoAdd),
+    GeoDist(GeoDist),
+    GeoHash(GeoHash),
+    GeoPos(GeoPos),
+    GeoSearch(GeoSearch),
+    JsonSet(JsonSet),
+    JsonGet(JsonGet),
+    JsonDel(JsonDel),
+    JsonNumIncrBy(JsonNumIncrBy),
+    JsonArrAppend(JsonArrAppend),
+    BfReserve(BfReserve),
+    BfAdd(BfAdd),
+    BfExists(BfExists),
+    CfReserve(CfReserve),
+    CfAdd(CfAdd),
+    CfExists(CfExists),
+    CfDel(CfDel),
+    CmsInitByDim(CmsInitByDim),
+    CmsIncrBy(CmsIncrBy),
+    CmsQuery(CmsQuery),
+    TopKReserve(TopKReserve),
+    TopKAdd(TopKAdd),
+    TopKList(TopKList),
+    TsCreate(TsCreate),
+    TsAdd(TsAdd),
+    TsRange(TsRange),
+    TsMRange(TsMRange),
+    TsCreateRule(TsCreateRule),
+    VIndexCreate(VIndexCreate),
+    VIndexAdd(VIndexAdd),
+    VIndexDel(VIndexDel),
+    VIndexKnn(VIndexKnn),
+    FtCreate(FtCreate),
+    FtDropIndex(FtDropIndex),
+    FtSearch(FtSearch),
+    HExpire(HExpire),
+    HTtl(HTtl),
+    HPersist(HPersist),
+    Expire(Expire),
+    Ttl(Ttl),
+    ExpireTime(ExpireTime),
+    Persist(Persist),
+    MGet(MGet),
+    MSet(MSet),
+    Exists(Exists),
+    Scan(Scan),
+    Keys(Keys),
+    SScan(SScan),
+    ZScan(ZScan),
+    Rename(Rename),
+    CopyKey(CopyKey),
+    Type(Type),
+    RandomKey(RandomKey),
+    DbSize(DbSize),
+    Flush(Flush),
+    Select(Select),
+    Move(Move),
+    SwapDb(SwapDb),
     Subscribe(Subscribe),
     Unsubscribe(Unsubscribe),
     Ping(Ping),
@@ -35,21 +267,181 @@ impl Command {
 
         let command_name = parse.next_string()?.to_lowercase();
 
-        let command = match &command_name[..] {
-            "get" => Command::Get(Get::parse_frames(&mut parse)?),
-            "set" => Command::Set(Set::parse_frames(&mut parse)?),
-            "del" => Command::Del(Del::parse_frames(&mut parse)?),
-            "publish" => Command::Publish(Publish::parse_frames(&mut parse)?),
-            "ping" => Command::Ping(Ping::parse_frames(&mut parse)?),
-            "subscribe" => Command::Subscribe(Subscribe::parse_frames(&mut parse)?),
-            "unsubscribe" => Command::Unsubscribe(Unsubscribe::parse_frames(&mut parse)?),
-            _ => {
-                return Ok(Command::Unknown(Unknown::new(command_name)));
-            }
+        let wrong_arity = || {
+            CacheError::from(format!(
+                "ERR wrong number of arguments for '{}' command",
+                command_name
+            ))
+        };
+        let command = match Command::parse_arguments(&command_name, &mut parse) {
+            Ok(Command::Unknown(cmd)) => return Ok(Command::Unknown(cmd)),
+            Ok(command) => command,
+            Err(CacheError::EndOfStream) => return Err(wrong_arity()),
+            Err(err) => return Err(err),
         };
+        if parse.finish().is_err() {
+            return Err(wrong_arity());
+        }
 
-        parse.finish()?;
+        Ok(command)
+    }
 
+    /// Parses the arguments of the command called `command_name`.
+    fn parse_arguments(command_name: &str, parse: &mut Parse) -> Result<Command, CacheError> {
+        let command = match command_name {
+            "get" => Command::Get(Get::parse_frames(parse)?),
+            "set" => Command::Set(Set::parse_frames(parse)?),
+            "del" => Command::Del(Del::parse_frames(parse, false)?),
+            "unlink" => Command::Del(Del::parse_frames(parse, true)?),
+            "lpush" => Command::Push(Push::parse_frames(parse, End::Left)?),
+            "rpush" => Command::Push(Push::parse_frames(parse, End::Right)?),
+            "lpop" => Command::Pop(Pop::parse_frames(parse, End::Left)?),
+            "rpop" => Command::Pop(Pop::parse_frames(parse, End::Right)?),
+            "lrange" => Command::LRange(LRange::parse_frames(parse)?),
+            "llen" => Command::LLen(LLen::parse_frames(parse)?),
+            "lindex" => Command::LIndex(LIndex::parse_frames(parse)?),
+            "lset" => Command::LSet(LSet::parse_frames(parse)?),
+            "lrem" => Command::LRem(LRem::parse_frames(parse)?),
+            "ltrim" => Command::LTrim(LTrim::parse_frames(parse)?),
+            "publish" => Command::Publish(Publish::parse_frames(parse)?),
+            "ping" => Command::Ping(Ping::parse_frames(parse)?),
+            "subscribe" => Command::Subscribe(Subscribe::parse_frames(parse)?),
+            "unsubscribe" => Command::Unsubscribe(Unsubscribe::parse_frames(parse)?),
+            "hset" => Command::HSet(HSet::parse_frames(parse)?),
+            "hget" => Command::HGet(HGet::parse_frames(parse)?),
+            "hmget" => Command::HMGet(HMGet::parse_frames(parse)?),
+            "hdel" => Command::HDel(HDel::parse_frames(parse)?),
+            "hgetall" => Command::HGetAll(HGetAll::parse_frames(parse)?),
+            "hincrby" => Command::HIncrBy(HIncrBy::parse_frames(parse)?),
+            "hscan" => Command::HScan(HScan::parse_frames(parse)?),
+            "sadd" => Command::SAdd(SAdd::parse_frames(parse)?),
+            "srem" => Command::SRem(SRem::parse_frames(parse)?),
+            "sismember" => Command::SIsMember(SIsMember::parse_frames(parse)?),
+            "smembers" => Command::SMembers(SMembers::parse_frames(parse)?),
+            "scard" => Command::SCard(SCard::parse_frames(parse)?),
+            "sinter" => Command::SetOp(SetOp::parse_frames(parse, SetOperation::Inter)?),
+            "sunion" => Command::SetOp(SetOp::parse_frames(parse, SetOperation::Union)?),
+            "sdiff" => Command::SetOp(SetOp::parse_frames(parse, SetOperation::Diff)?),
+            "sinterstore" => {
+                Command::SetOpStore(SetOpStore::parse_frames(parse, SetOperation::Inter)?)
+            }
+            "sunionstore" => {
+                Command::SetOpStore(SetOpStore::parse_frames(parse, SetOperation::Union)?)
+            }
+            "sdiffstore" => {
+                Command::SetOpStore(SetOpStore::parse_frames(parse, SetOperation::Diff)?)
+            }
+            "zadd" => Command::ZAdd(ZAdd::parse_frames(parse)?),
+            "zrange" => Command::ZRange(ZRange::parse_frames(parse)?),
+            "zrangebyscore" => Command::ZRangeByScore(ZRangeByScore::parse_frames(parse)?),
+            "zrank" => Command::ZRank(ZRank::parse_frames(parse)?),
+            "zincrby" => Command::ZIncrBy(ZIncrBy::parse_frames(parse)?),
+            "zrem" => Command::ZRem(ZRem::parse_frames(parse)?),
+            "xadd" => Command::XAdd(XAdd::parse_frames(parse)?),
+            "xlen" => Command::XLen(XLen::parse_frames(parse)?),
+            "xtrim" => Command::XTrim(XTrim::parse_frames(parse)?),
+            "xrange" => Command::XRange(XRange::parse_frames(parse, false)?),
+            "xrevrange" => Command::XRange(XRange::parse_frames(parse, true)?),
+            "xgroup" => Command::XGroup(XGroup::parse_frames(parse)?),
+            "xack" => Command::XAck(XAck::parse_frames(parse)?),
+            "xpending" => Command::XPending(XPending::parse_frames(parse)?),
+            "xclaim" => Command::XClaim(XClaim::parse_frames(parse)?),
+            "xautoclaim" => Command::XAutoClaim(XAutoClaim::parse_frames(parse)?),
+            "xreadgroup" => Command::XReadGroup(XReadGroup::parse_frames(parse)?),
+            "blpop" => Command::BPop(BPop::parse_frames(parse, End::Left)?),
+            "brpop" => Command::BPop(BPop::parse_frames(parse, End::Right)?),
+            "blmove" => Command::BLMove(BLMove::parse_frames(parse)?),
+            "incr" => Command::Incr(Incr::parse_frames(parse, false, false)?),
+            "decr" => Command::Incr(Incr::parse_frames(parse, true, false)?),
+            "incrby" => Command::Incr(Incr::parse_frames(parse, false, true)?),
+            "decrby" => Command::Incr(Incr::parse_frames(parse, true, true)?),
+            "incrbyfloat" => Command::IncrByFloat(IncrByFloat::parse_frames(parse)?),
+            "append" => Command::Append(Append::parse_frames(parse)?),
+            "strlen" => Command::StrLen(StrLen::parse_frames(parse)?),
+            "getrange" => Command::GetRange(GetRange::parse_frames(parse)?),
+            "setrange" => Command::SetRange(SetRange::parse_frames(parse)?),
+            "getdel" => Command::GetDel(GetDel::parse_frames(parse)?),
+            "getex" => Command::GetEx(GetEx::parse_frames(parse)?),
+            "getset" => Command::GetSet(GetSet::parse_frames(parse)?),
+            "setbit" => Command::SetBit(SetBit::parse_frames(parse)?),
+            "getbit" => Command::GetBit(GetBit::parse_frames(parse)?),
+            "bitcount" => Command::BitCount(BitCount::parse_frames(parse)?),
+            "bitpos" => Command::BitPos(BitPos::parse_frames(parse)?),
+            "bitop" => Command::BitOp(BitOp::parse_frames(parse)?),
+            "bitfield" => Command::BitField(BitField::parse_frames(parse)?),
+            "pfadd" => Command::PfAdd(PfAdd::parse_frames(parse)?),
+            "pfcount" => Command::PfCount(PfCount::parse_frames(parse)?),
+            "pfmerge" => Command::PfMerge(PfMerge::parse_frames(parse)?),
+            "geoadd" => Command::GeoAdd(GeoAdd::parse_frames(parse)?),
+            "geodist" => Command::GeoDist(GeoDist::parse_frames(parse)?),
+            "geohash" => Command::GeoHash(GeoHash::parse_frames(parse)?),
+            "geopos" => Command::GeoPos(GeoPos::parse_frames(parse)?),
+            "geosearch" => Command::GeoSearch(GeoSearch::parse_frames(parse)?),
+            "json.set" => Command::JsonSet(JsonSet::parse_frames(parse)?),
+            "json.get" => Command::JsonGet(JsonGet::parse_frames(parse)?),
+            "json.del" => Command::JsonDel(JsonDel::parse_frames(parse)?),
+            "json.numincrby" => Command::JsonNumIncrBy(JsonNumIncrBy::parse_frames(parse)?),
+            "json.arrappend" => Command::JsonArrAppend(JsonArrAppend::parse_frames(parse)?),
+            "bf.reserve" => Command::BfReserve(BfReserve::parse_frames(parse)?),
+            "bf.add" => Command::BfAdd(BfAdd::parse_frames(parse)?),
+            "bf.exists" => Command::BfExists(BfExists::parse_frames(parse)?),
+            "cf.reserve" => Command::CfReserve(CfReserve::parse_frames(parse)?),
+            "cf.add" => Command::CfAdd(CfAdd::parse_frames(parse)?),
+            "cf.exists" => Command::CfExists(CfExists::parse_frames(parse)?),
+            "cf.del" => Command::CfDel(CfDel::parse_frames(parse)?),
+            "cms.initbydim" => Command::CmsInitByDim(CmsInitByDim::parse_frames(parse)?),
+            "cms.incrby" => Command::CmsIncrBy(CmsIncrBy::parse_frames(parse)?),
+            "cms.query" => Command::CmsQuery(CmsQuery::parse_frames(parse)?),
+            "topk.reserve" => Command::TopKReserve(TopKReserve::parse_frames(parse)?),
+            "topk.add" => Command::TopKAdd(TopKAdd::parse_frames(parse)?),
+            "topk.list" => Command::TopKList(TopKList::parse_frames(parse)?),
+            "ts.create" => Command::TsCreate(TsCreate::parse_frames(parse)?),
+            "ts.add" => Command::TsAdd(TsAdd::parse_frames(parse)?),
+            "ts.range" => Command::TsRange(TsRange::parse_frames(parse)?),
+            "ts.mrange" => Command::TsMRange(TsMRange::parse_frames(parse)?),
+            "ts.createrule" => Command::TsCreateRule(TsCreateRule::parse_frames(parse)?),
+            "vindex.create" => Command::VIndexCreate(VIndexCreate::parse_frames(parse)?),
+            "vindex.add" => Command::VIndexAdd(VIndexAdd::parse_frames(parse)?),
+            "vindex.del" => Command::VIndexDel(VIndexDel::parse_frames(parse)?),
+            "vindex.knn" => Command::VIndexKnn(VIndexKnn::parse_frames(parse)?),
+            "ft.create" => Command::FtCreate(FtCreate::parse_frames(parse)?),
+            "ft.dropindex" => Command::FtDropIndex(FtDropIndex::parse_frames(parse)?),
+            "ft.search" => Command::FtSearch(FtSearch::parse_frames(parse)?),
+            "hexpire" => Command::HExpire(HExpire::parse_frames(parse, false)?),
+            "hpexpire" => Command::HExpire(HExpire::parse_frames(parse, true)?),
+            "httl" => Command::HTtl(HTtl::parse_frames(parse)?),
+            "hpersist" => Command::HPersist(HPersist::parse_frames(parse)?),
+            "expire" => Command::Expire(Expire::parse_frames(parse, false, false)?),
+            "pexpire" => Command::Expire(Expire::parse_frames(parse, true, false)?),
+            "expireat" => Command::Expire(Expire::parse_frames(parse, false, true)?),
+            "pexpireat" => Command::Expire(Expire::parse_frames(parse, true, true)?),
+            "ttl" => Command::Ttl(Ttl::parse_frames(parse, false)?),
+            "pttl" => Command::Ttl(Ttl::parse_frames(parse, true)?),
+            "expiretime" => Command::ExpireTime(ExpireTime::parse_frames(parse, false)?),
+            "pexpiretime" => Command::ExpireTime(ExpireTime::parse_frames(parse, true)?),
+            "persist" => Command::Persist(Persist::parse_frames(parse)?),
+            "mget" => Command::MGet(MGet::parse_frames(parse)?),
+            "mset" => Command::MSet(MSet::parse_frames(parse, false)?),
+            "msetnx" => Command::MSet(MSet::parse_frames(parse, true)?),
+            "exists" => Command::Exists(Exists::parse_frames(parse, false)?),
+            "touch" => Command::Exists(Exists::parse_frames(parse, true)?),
+            "scan" => Command::Scan(Scan::parse_frames(parse)?),
+            "keys" => Command::Keys(Keys::parse_frames(parse)?),
+            "sscan" => Command::SScan(SScan::parse_frames(parse)?),
+            "zscan" => Command::ZScan(ZScan::parse_frames(parse)?),
+            "rename" => Command::Rename(Rename::parse_frames(parse, false)?),
+            "renamenx" => Command::Rename(Rename::parse_frames(parse, true)?),
+            "copy" => Command::CopyKey(CopyKey::parse_frames(parse)?),
+            "type" => Command::Type(Type::parse_frames(parse)?),
+            "randomkey" => Command::RandomKey(RandomKey::parse_frames(parse)?),
+            "dbsize" => Command::DbSize(DbSize::parse_frames(parse)?),
+            "flushdb" => Command::Flush(Flush::parse_frames(parse, false)?),
+            "flushall" => Command::Flush(Flush::parse_frames(parse, true)?),
+            "select" => Command::Select(Select::parse_frames(parse)?),
+            "move" => Command::Move(Move::parse_frames(parse)?),
+            "swapdb" => Command::SwapDb(SwapDb::parse_frames(parse)?),
+            _ => Command::Unknown(Unknown::new(command_name.to_string())),
+        };
         Ok(command)
     }
 
@@ -57,30 +449,267 @@ impl Command {
         match self {
             Command::Set(_) => "set",
             Command::Get(_) => "get",
-            Command::Del(_) => "del",
+            Command::Del(cmd) => cmd.get_name(),
+            Command::Push(cmd) => cmd.get_name(),
+            Command::Pop(cmd) => cmd.get_name(),
+            Command::LRange(_) => "lrange",
+            Command::LLen(_) => "llen",
+            Command::LIndex(_) => "lindex",
+            Command::LSet(_) => "lset",
+            Command::LRem(_) => "lrem",
+            Command::LTrim(_) => "ltrim",
             Command::Publish(_) => "pub",
             Command::Subscribe(_) => "subscribe",
             Command::Unsubscribe(_) => "unsubsribe",
             Command::Ping(_) => "ping",
+            Command::HSet(_) => "hset",
+            Command::HGet(_) => "hget",
+            Command::HMGet(_) => "hmget",
+            Command::HDel(_) => "hdel",
+            Command::HGetAll(_) => "hgetall",
+            Command::HIncrBy(_) => "hincrby",
+            Command::HScan(_) => "hscan",
+            Command::SAdd(_) => "sadd",
+            Command::SRem(_) => "srem",
+            Command::SIsMember(_) => "sismember",
+            Command::SMembers(_) => "smembers",
+            Command::SCard(_) => "scard",
+            Command::SetOp(cmd) => cmd.get_name(),
+            Command::SetOpStore(cmd) => cmd.get_name(),
+            Command::ZAdd(_) => "zadd",
+            Command::ZRange(_) => "zrange",
+            Command::ZRangeByScore(_) => "zrangebyscore",
+            Command::ZRank(_) => "zrank",
+            Command::ZIncrBy(_) => "zincrby",
+            Command::ZRem(_) => "zrem",
+            Command::XAdd(_) => "xadd",
+            Command::XLen(_) => "xlen",
+            Command::XTrim(_) => "xtrim",
+            Command::XRange(cmd) => cmd.get_name(),
+            Command::XGroup(_) => "xgroup",
+            Command::XAck(_) => "xack",
+            Command::XPending(_) => "xpending",
+            Command::XClaim(_) => "xclaim",
+            Command::XAutoClaim(_) => "xautoclaim",
+            Command::XReadGroup(_) => "xreadgroup",
+            Command::BPop(cmd) => cmd.get_name(),
+            Command::BLMove(_) => "blmove",
+            Command::Incr(cmd) => cmd.get_name(),
+            Command::IncrByFloat(_) => "incrbyfloat",
+            Command::Append(_) => "append",
+            Command::StrLen(_) => "strlen",
+            Command::GetRange(_) => "getrange",
+            Command::SetRange(_) => "setrange",
+            Command::GetDel(_) => "getdel",
+            Command::GetEx(_) => "getex",
+            Command::GetSet(_) => "getset",
+            Command::SetBit(_) => "setbit",
+            Command::GetBit(_) => "getbit",
+            Command::BitCount(_) => "bitcount",
+            Command::BitPos(_) => "bitpos",
+            Command::BitOp(_) => "bitop",
+            Command::BitField(_) => "bitfield",
+            Command::PfAdd(_) => "pfadd",
+            Command::PfCount(_) => "pfcount",
+            Command::PfMerge(_) => "pfmerge",
+            Command::GeoAdd(_) => "geoadd",
+            Command::GeoDist(_) => "geodist",
+            Command::GeoHash(_) => "geohash",
+            Command::GeoPos(_) => "geopos",
+            Command::GeoSearch(_) => "geosearch",
+            Command::JsonSet(_) => "json.set",
+            Command::JsonGet(_) => "json.get",
+            Command::JsonDel(_) => "json.del",
+            Command::JsonNumIncrBy(_) => "json.numincrby",
+            Command::JsonArrAppend(_) => "json.arrappend",
+            Command::BfReserve(_) => "bf.reserve",
+            Command::BfAdd(_) => "bf.add",
+            Command::BfExists(_) => "bf.exists",
+            Command::CfReserve(_) => "cf.reserve",
+            Command::CfAdd(_) => "cf.add",
+            Command::CfExists(_) => "cf.exists",
+            Command::CfDel(_) => "cf.del",
+            Command::CmsInitByDim(_) => "cms.initbydim",
+            Command::CmsIncrBy(_) => "cms.incrby",
+            Command::CmsQuery(_) => "cms.query",
+            Command::TopKReserve(_) => "topk.reserve",
+            Command::TopKAdd(_) => "topk.add",
+            Command::TopKList(_) => "topk.list",
+            Command::TsCreate(_) => "ts.create",
+            Command::TsAdd(_) => "ts.add",
+            Command::TsRange(_) => "ts.range",
+            Command::TsMRange(_) => "ts.mrange",
+            Command::TsCreateRule(_) => "ts.createrule",
+            Command::VIndexCreate(_) => "vindex.create",
+            Command::VIndexAdd(_) => "vindex.add",
+            Command::VIndexDel(_) => "vindex.del",
+            Command::VIndexKnn(_) => "vindex.knn",
+            Command::FtCreate(_) => "ft.create",
+            Command::FtDropIndex(_) => "ft.dropindex",
+            Command::FtSearch(_) => "ft.search",
+            Command::HExpire(cmd) => cmd.get_name(),
+            Command::HTtl(_) => "httl",
+            Command::HPersist(_) => "hpersist",
+            Command::Expire(cmd) => cmd.get_name(),
+            Command::Ttl(cmd) => cmd.get_name(),
+            Command::ExpireTime(cmd) => cmd.get_name(),
+            Command::Persist(_) => "persist",
+            Command::MGet(_) => "mget",
+            Command::MSet(cmd) => cmd.get_name(),
+            Command::Exists(cmd) => cmd.get_name(),
+            Command::Scan(_) => "scan",
+            Command::Keys(_) => "keys",
+            Command::SScan(_) => "sscan",
+            Command::ZScan(_) => "zscan",
+            Command::Rename(cmd) => cmd.get_name(),
+            Command::CopyKey(_) => "copy",
+            Command::Type(_) => "type",
+            Command::RandomKey(_) => "randomkey",
+            Command::DbSize(_) => "dbsize",
+            Command::Flush(cmd) => cmd.get_name(),
+            Command::Select(_) => "select",
+            Command::Move(_) => "move",
+            Command::SwapDb(_) => "swapdb",
             Command::Unknown(cmd) => cmd.get_name(),
         }
     }
 
+    /// Runs the command against the database `selected` by the connection,
+    /// which `SELECT` changes.
     pub async fn apply(
         self,
-        db: &Db,
+        databases: &Databases,
+        selected: &mut usize,
         dst: &mut Connection,
         shutdown: &mut Shutdown,
     ) -> Result<(), CacheError> {
         use Command::*;
 
+        let db = databases.get(*selected);
+
         match self {
             Get(cmd) => cmd.apply(db, dst).await,
             Del(cmd) => cmd.apply(db, dst).await,
             Set(cmd) => cmd.apply(db, dst).await,
-            Publish(cmd) => cmd.apply(db, dst).await,
-            Subscribe(cmd) => cmd.apply(db, dst, shutdown).await,
+            Push(cmd) => cmd.apply(db, dst).await,
+            Pop(cmd) => cmd.apply(db, dst).await,
+            LRange(cmd) => cmd.apply(db, dst).await,
+            LLen(cmd) => cmd.apply(db, dst).await,
+            LIndex(cmd) => cmd.apply(db, dst).await,
+            LSet(cmd) => cmd.apply(db, dst).await,
+            LRem(cmd) => cmd.apply(db, dst).await,
+            LTrim(cmd) => cmd.apply(db, dst).await,
+            Publish(cmd) => cmd.apply(databases.pub_sub(), dst).await,
+            Subscribe(cmd) => cmd.apply(databases.pub_sub(), dst, shutdown).await,
             Ping(cmd) => cmd.apply(dst).await,
+            HSet(cmd) => cmd.apply(db, dst).await,
+            HGet(cmd) => cmd.apply(db, dst).await,
+            HMGet(cmd) => cmd.apply(db, dst).await,
+            HDel(cmd) => cmd.apply(db, dst).await,
+            HGetAll(cmd) => cmd.apply(db, dst).await,
+            HIncrBy(cmd) => cmd.apply(db, dst).await,
+            HScan(cmd) => cmd.apply(db, dst).await,
+            SAdd(cmd) => cmd.apply(db, dst).await,
+            SRem(cmd) => cmd.apply(db, dst).await,
+            SIsMember(cmd) => cmd.apply(db, dst).await,
+            SMembers(cmd) => cmd.apply(db, dst).await,
+            SCard(cmd) => cmd.apply(db, dst).await,
+            SetOp(cmd) => cmd.apply(db, dst).await,
+            SetOpStore(cmd) => cmd.apply(db, dst).await,
+            ZAdd(cmd) => cmd.apply(db, dst).await,
+            ZRange(cmd) => cmd.apply(db, dst).await,
+            ZRangeByScore(cmd) => cmd.apply(db, dst).await,
+            ZRank(cmd) => cmd.apply(db, dst).await,
+            ZIncrBy(cmd) => cmd.apply(db, dst).await,
+            ZRem(cmd) => cmd.apply(db, dst).await,
+            XAdd(cmd) => cmd.apply(db, dst).await,
+            XLen(cmd) => cmd.apply(db, dst).await,
+            XTrim(cmd) => cmd.apply(db, dst).await,
+            XRange(cmd) => cmd.apply(db, dst).await,
+            XGroup(cmd) => cmd.apply(db, dst).await,
+            XAck(cmd) => cmd.apply(db, dst).await,
+            XPending(cmd) => cmd.apply(db, dst).await,
+            XClaim(cmd) => cmd.apply(db, dst).await,
+            XAutoClaim(cmd) => cmd.apply(db, dst).await,
+            XReadGroup(cmd) => cmd.apply(db, dst, shutdown).await,
+            BPop(cmd) => cmd.apply(db, dst, shutdown).await,
+            BLMove(cmd) => cmd.apply(db, dst, shutdown).await,
+            Incr(cmd) => cmd.apply(db, dst).await,
+            IncrByFloat(cmd) => cmd.apply(db, dst).await,
+            Append(cmd) => cmd.apply(db, dst).await,
+            StrLen(cmd) => cmd.apply(db, dst).await,
+            GetRange(cmd) => cmd.apply(db, dst).await,
+            SetRange(cmd) => cmd.apply(db, dst).await,
+            GetDel(cmd) => cmd.apply(db, dst).await,
+            GetEx(cmd) => cmd.apply(db, dst).await,
+            GetSet(cmd) => cmd.apply(db, dst).await,
+            SetBit(cmd) => cmd.apply(db, dst).await,
+            GetBit(cmd) => cmd.apply(db, dst).await,
+            BitCount(cmd) => cmd.apply(db, dst).await,
+            BitPos(cmd) => cmd.apply(db, dst).await,
+            BitOp(cmd) => cmd.apply(db, dst).await,
+            BitField(cmd) => cmd.apply(db, dst).await,
+            PfAdd(cmd) => cmd.apply(db, dst).await,
+            PfCount(cmd) => cmd.apply(db, dst).await,
+            PfMerge(cmd) => cmd.apply(db, dst).await,
+            GeoAdd(cmd) => cmd.apply(db, dst).await,
+            GeoDist(cmd) => cmd.apply(db, dst).await,
+            GeoHash(cmd) => cmd.apply(db, dst).await,
+            GeoPos(cmd) => cmd.apply(db, dst).await,
+            GeoSearch(cmd) => cmd.apply(db, dst).await,
+            JsonSet(cmd) => cmd.apply(db, dst).await,
+            JsonGet(cmd) => cmd.apply(db, dst).await,
+            JsonDel(cmd) => cmd.apply(db, dst).await,
+            JsonNumIncrBy(cmd) => cmd.apply(db, dst).await,
+            JsonArrAppend(cmd) => cmd.apply(db, dst).await,
+            BfReserve(cmd) => cmd.apply(db, dst).await,
+            BfAdd(cmd) => cmd.apply(db, dst).await,
+            BfExists(cmd) => cmd.apply(db, dst).await,
+            CfReserve(cmd) => cmd.apply(db, dst).await,
+            CfAdd(cmd) => cmd.apply(db, dst).await,
+            CfExists(cmd) => cmd.apply(db, dst).await,
+            CfDel(cmd) => cmd.apply(db, dst).await,
+            CmsInitByDim(cmd) => cmd.apply(db, dst).await,
+            CmsIncrBy(cmd) => cmd.apply(db, dst).await,
+            CmsQuery(cmd) => cmd.apply(db, dst).await,
+            TopKReserve(cmd) => cmd.apply(db, dst).await,
+            TopKAdd(cmd) => cmd.apply(db, dst).await,
+            TopKList(cmd) => cmd.apply(db, dst).await,
+            TsCreate(cmd) => cmd.apply(db, dst).await,
+            TsAdd(cmd) => cmd.apply(db, dst).await,
+            TsRange(cmd) => cmd.apply(db, dst).await,
+            TsMRange(cmd) => cmd.apply(db, dst).await,
+            TsCreateRule(cmd) => cmd.apply(db, dst).await,
+            VIndexCreate(cmd) => cmd.apply(db, dst).await,
+            VIndexAdd(cmd) => cmd.apply(db, dst).await,
+            VIndexDel(cmd) => cmd.apply(db, dst).await,
+            VIndexKnn(cmd) => cmd.apply(db, dst).await,
+            FtCreate(cmd) => cmd.apply(db, dst).await,
+            FtDropIndex(cmd) => cmd.apply(db, dst).await,
+            FtSearch(cmd) => cmd.apply(db, dst).await,
+            HExpire(cmd) => cmd.apply(db, dst).await,
+            HTtl(cmd) => cmd.apply(db, dst).await,
+            HPersist(cmd) => cmd.apply(db, dst).await,
+            Expire(cmd) => cmd.apply(db, dst).await,
+            Ttl(cmd) => cmd.apply(db, dst).await,
+            ExpireTime(cmd) => cmd.apply(db, dst).await,
+            Persist(cmd) => cmd.apply(db, dst).await,
+            MGet(cmd) => cmd.apply(db, dst).await,
+            MSet(cmd) => cmd.apply(db, dst).await,
+            Exists(cmd) => cmd.apply(db, dst).await,
+            Scan(cmd) => cmd.apply(db, dst).await,
+            Keys(cmd) => cmd.apply(db, dst).await,
+            SScan(cmd) => cmd.apply(db, dst).await,
+            ZScan(cmd) => cmd.apply(db, dst).await,
+            Rename(cmd) => cmd.apply(db, dst).await,
+            CopyKey(cmd) => cmd.apply(db, dst).await,
+            Type(cmd) => cmd.apply(db, dst).await,
+            RandomKey(cmd) => cmd.apply(db, dst).await,
+            DbSize(cmd) => cmd.apply(db, dst).await,
+            Flush(cmd) => cmd.apply(databases, *selected, dst).await,
+            Select(cmd) => cmd.apply(databases, selected, dst).await,
+            Move(cmd) => cmd.apply(databases, *selected, dst).await,
+            SwapDb(cmd) => cmd.apply(databases, dst).await,
             Unknown(cmd) => cmd.apply(dst).await,
             Unsubscribe(_) => Err("`Unsubsribe` is unsuppored in this context".into()),
         }
@@ -122,12 +751,34 @@ impl Parse {
     }
 
     pub(crate) fn next_int(&mut self) -> Result<i64, CacheError> {
+        const MSG: &str = "ERR value is not an integer or out of range";
+
         match self.next()? {
             Entity::Integer(i) => Ok(i),
+            Entity::Simple(data) => data.parse().map_err(|_| MSG.into()),
+            Entity::Bulk(data) => str::from_utf8(&data)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| MSG.into()),
             frame => Err(format!("protocol error; expected number, got {:?}", frame).into()),
         }
     }
 
+    /// Reads a `SCAN`-family cursor, which spans the whole `u64` range.
+    pub(crate) fn next_cursor(&mut self) -> Result<u64, CacheError> {
+        self.next_string()?
+            .parse()
+            .map_err(|_| "ERR invalid cursor".into())
+    }
+
+    /// Reads a strictly positive `COUNT` argument.
+    pub(crate) fn next_count(&mut self) -> Result<usize, CacheError> {
+        match self.next_int()? {
+            count if count > 0 => Ok(count as usize),
+            _ => Err("ERR value is out of range, must be positive".into()),
+        }
+    }
+
     pub(crate) fn finish(&mut self) -> Result<(), CacheError> {
         if self.parts.next().is_none() {
             Ok(())
@@ -136,6 +787,29 @@ impl Parse {
         }
     }
 
+    /// Collects the remaining arguments, at least one, as keys. Each becomes
+    /// a bulk string however it was sent, so multi-key commands find the same
+    /// keys whatever their position.
+    pub(crate) fn rest_keys(&mut self) -> Result<Vec<Entity>, CacheError> {
+        let keys: Vec<_> = self.rest_bytes()?.into_iter().map(Entity::Bulk).collect();
+        if keys.is_empty() {
+            return Err(CacheError::EndOfStream);
+        }
+        Ok(keys)
+    }
+
+    /// Collects every remaining argument as raw bytes, for variadic commands.
+    pub(crate) fn rest_bytes(&mut self) -> Result<Vec<Bytes>, CacheError> {
+        let mut rest = vec![];
+        loop {
+            match self.next_bytes() {
+                Ok(bytes) => rest.push(bytes),
+                Err(CacheError::EndOfStream) => return Ok(rest),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
     pub(crate) fn next_bytes(&mut self) -> Result<Bytes, CacheError> {
         match self.next()? {
             Entity::Simple(s) => Ok(Bytes::from(s.into_bytes())),
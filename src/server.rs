@@ -12,7 +12,7 @@ use crate::{
     error::CacheError,
     parse::Command,
     shutdown::Shutdown,
-    storage::{Db, DbDropGuard},
+    storage::{DbDropGuard, databases::Databases, entity::Entity},
 };
 
 struct Listener {
@@ -24,7 +24,10 @@ struct Listener {
 }
 
 struct Handler {
-    db: Db,
+    databases: Databases,
+    /// The database this connection's commands run against, set by
+    /// `SELECT`.
+    selected: usize,
     connection: Connection,
     shutdown: Shutdown,
     _shutdown_complete: mpsc::Sender<()>,
@@ -32,13 +35,13 @@ struct Handler {
 
 const MAX_CONNECTIONS: usize = 256;
 
-pub async fn run(listener: TcpListener, shutdown: impl Future) {
+pub async fn run(listener: TcpListener, databases: usize, shutdown: impl Future) {
     let (notify_shutdown, _) = broadcast::channel(1);
     let (shutdown_complete_tx, mut shutdown_complete_rx) = mpsc::channel(1);
 
     let mut server = Listener {
         listener,
-        db_holder: DbDropGuard::new(),
+        db_holder: DbDropGuard::new(databases),
         limit_connections: Arc::new(Semaphore::new(MAX_CONNECTIONS)),
         notify_shutdown,
         shutdown_complete_tx,
@@ -81,7 +84,8 @@ impl Listener {
             let socket = self.accept().await?;
 
             let mut handler = Handler {
-                db: self.db_holder.db(),
+                databases: self.db_holder.databases(),
+                selected: 0,
                 connection: Connection::new(socket),
                 shutdown: Shutdown::new(self.notify_shutdown.subscribe()),
                 _shutdown_complete: self.shutdown_complete_tx.clone(),
@@ -131,12 +135,26 @@ impl Handler {
                 None => return Ok(()),
             };
 
-            let cmd = Command::from_frame(entity)?;
+            let cmd = match Command::from_frame(entity) {
+                Ok(cmd) => cmd,
+                Err(err) if !err.is_protocol() => {
+                    let response = Entity::Error(err.to_string());
+                    debug!(?response);
+                    self.connection.write_frame(&response).await?;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
 
             debug!(?cmd);
 
-            cmd.apply(&self.db, &mut self.connection, &mut self.shutdown)
-                .await?;
+            cmd.apply(
+                &self.databases,
+                &mut self.selected,
+                &mut self.connection,
+                &mut self.shutdown,
+            )
+            .await?;
         }
         Ok(())
     }
@@ -188,9 +206,9 @@ mod tests {
             .await
             .unwrap();
 
-        let mut response = [0; 5];
+        let mut response = [0; 4];
         stream.read_exact(&mut response).await.unwrap();
-        assert_eq!(b"+OK\r\n", &response);
+        assert_eq!(b":1\r\n", &response);
 
         stream
             .write_all(b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n")
@@ -311,11 +329,438 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn list_push_pop() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        stream
+            .write_all(b"*4\r\n$5\r\nRPUSH\r\n$4\r\njobs\r\n$1\r\na\r\n$1\r\nb\r\n")
+            .await
+            .unwrap();
+
+        let mut response = [0; 4];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(b":2\r\n", &response);
+
+        stream
+            .write_all(b"*4\r\n$6\r\nLRANGE\r\n$4\r\njobs\r\n$1\r\n0\r\n$2\r\n-1\r\n")
+            .await
+            .unwrap();
+
+        let mut response = [0; 18];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(b"*2\r\n$1\r\na\r\n$1\r\nb\r\n", &response);
+
+        stream
+            .write_all(b"*2\r\n$4\r\nLPOP\r\n$4\r\njobs\r\n")
+            .await
+            .unwrap();
+
+        let mut response = [0; 7];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(b"$1\r\na\r\n", &response);
+
+        stream
+            .write_all(b"*2\r\n$3\r\nGET\r\n$4\r\njobs\r\n")
+            .await
+            .unwrap();
+
+        let expected = b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+        let mut response = [0; 68];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(expected, &response);
+    }
+
+    #[tokio::test]
+    async fn select_is_per_connection() {
+        let addr = start_server().await;
+
+        let mut first = TcpStream::connect(addr).await.unwrap();
+        let mut second = TcpStream::connect(addr).await.unwrap();
+
+        first
+            .write_all(b"*2\r\n$6\r\nSELECT\r\n$1\r\n1\r\n")
+            .await
+            .unwrap();
+
+        let mut response = [0; 5];
+        first.read_exact(&mut response).await.unwrap();
+        assert_eq!(b"+OK\r\n", &response);
+
+        first
+            .write_all(b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n")
+            .await
+            .unwrap();
+
+        let mut response = [0; 5];
+        first.read_exact(&mut response).await.unwrap();
+        assert_eq!(b"+OK\r\n", &response);
+
+        second
+            .write_all(b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n")
+            .await
+            .unwrap();
+
+        let mut response = [0; 5];
+        second.read_exact(&mut response).await.unwrap();
+        assert_eq!(b"$-1\r\n", &response);
+
+        second
+            .write_all(b"*2\r\n$6\r\nSELECT\r\n$2\r\n16\r\n")
+            .await
+            .unwrap();
+
+        let expected = b"-ERR DB index is out of range\r\n";
+        let mut response = [0; 31];
+        second.read_exact(&mut response).await.unwrap();
+        assert_eq!(expected, &response);
+
+        second
+            .write_all(b"*2\r\n$6\r\nSELECT\r\n$1\r\n1\r\n")
+            .await
+            .unwrap();
+
+        let mut response = [0; 5];
+        second.read_exact(&mut response).await.unwrap();
+        assert_eq!(b"+OK\r\n", &response);
+
+        second
+            .write_all(b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n")
+            .await
+            .unwrap();
+
+        let mut response = [0; 11];
+        second.read_exact(&mut response).await.unwrap();
+        assert_eq!(b"$5\r\nvalue\r\n", &response);
+    }
+
+    #[tokio::test]
+    async fn getex_expire_time_out_of_range() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n",
+            b"+OK\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*4\r\n$5\r\nGETEX\r\n$1\r\nk\r\n$2\r\nEX\r\n$19\r\n9223372036854775807\r\n",
+            b"-ERR invalid expire time in 'getex' command\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*4\r\n$5\r\nGETEX\r\n$1\r\nk\r\n$2\r\nPX\r\n$19\r\n9223372036854775807\r\n",
+            b"-ERR invalid expire time in 'getex' command\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*2\r\n$3\r\nGET\r\n$1\r\nk\r\n",
+            b"$1\r\nv\r\n",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn set_expire_time_out_of_range() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*5\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nEX\r\n$19\r\n9223372036854775807\r\n",
+            b"-ERR invalid expire time in 'set' command\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*5\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nPX\r\n$19\r\n9223372036854775807\r\n",
+            b"-ERR invalid expire time in 'set' command\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*5\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nEX\r\n$1\r\n0\r\n",
+            b"-ERR invalid expire time in 'set' command\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*5\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n$4\r\nPXAT\r\n$2\r\n-5\r\n",
+            b"-ERR invalid expire time in 'set' command\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*5\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nEX\r\n$3\r\nabc\r\n",
+            b"-ERR value is not an integer or out of range\r\n",
+        )
+        .await;
+        assert_reply(&mut stream, b"*2\r\n$3\r\nGET\r\n$1\r\nk\r\n", b"$-1\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn blpop_timeout_out_of_range() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$5\r\nBLPOP\r\n$1\r\nq\r\n$5\r\n1e300\r\n",
+            b"-ERR timeout is out of range\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$5\r\nBLPOP\r\n$1\r\nq\r\n$2\r\n-1\r\n",
+            b"-ERR timeout is negative\r\n",
+        )
+        .await;
+        assert_reply(&mut stream, b"*1\r\n$4\r\nPING\r\n", b"+PONG\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn xreadgroup_count_zero_and_long_block() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*6\r\n$6\r\nXGROUP\r\n$6\r\nCREATE\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\n$\r\n$8\r\nMKSTREAM\r\n",
+            b"+OK\r\n",
+        )
+        .await;
+        for id in [b"1-0", b"2-0"] {
+            let mut request = b"*5\r\n$4\r\nXADD\r\n$1\r\ns\r\n$3\r\n".to_vec();
+            request.extend_from_slice(id);
+            request.extend_from_slice(b"\r\n$1\r\nf\r\n$1\r\nv\r\n");
+            let mut expected = b"$3\r\n".to_vec();
+            expected.extend_from_slice(id);
+            expected.extend_from_slice(b"\r\n");
+            assert_reply(&mut stream, &request, &expected).await;
+        }
+        assert_reply(
+            &mut stream,
+            b"*11\r\n$10\r\nXREADGROUP\r\n$5\r\nGROUP\r\n$1\r\ng\r\n$1\r\nc\r\n$5\r\nCOUNT\r\n$1\r\n0\r\n$5\r\nBLOCK\r\n$19\r\n9223372036854775807\r\n$7\r\nSTREAMS\r\n$1\r\ns\r\n$1\r\n>\r\n",
+            b"*1\r\n*2\r\n$1\r\ns\r\n*2\r\n\
+              *2\r\n$3\r\n1-0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n\
+              *2\r\n$3\r\n2-0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn incr_by_invalid_amount() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$6\r\nINCRBY\r\n$1\r\nk\r\n$3\r\nabc\r\n",
+            b"-ERR value is not an integer or out of range\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$11\r\nINCRBYFLOAT\r\n$1\r\nk\r\n$3\r\nabc\r\n",
+            b"-ERR value is not a valid float\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$6\r\nINCRBY\r\n$1\r\nk\r\n$1\r\n5\r\n",
+            b":5\r\n",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn multi_key_commands_normalise_keys() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*5\r\n$4\r\nMSET\r\n+a\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n",
+            b"+OK\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$6\r\nEXISTS\r\n+a\r\n$1\r\na\r\n",
+            b":2\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$4\r\nMGET\r\n+b\r\n$1\r\na\r\n",
+            b"*2\r\n$1\r\n2\r\n$1\r\n1\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$3\r\nDEL\r\n+a\r\n$1\r\nb\r\n",
+            b":2\r\n",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn invalid_arguments_get_error_replies() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$4\r\nLPOP\r\n$1\r\nl\r\n$2\r\n-1\r\n",
+            b"-ERR value is out of range, must be positive\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$6\r\nEXPIRE\r\n$1\r\nk\r\n$3\r\nabc\r\n",
+            b"-ERR value is not an integer or out of range\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$3\r\nGET\r\n$1\r\na\r\n$1\r\nb\r\n",
+            b"-ERR wrong number of arguments for 'get' command\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*1\r\n$3\r\nGET\r\n",
+            b"-ERR wrong number of arguments for 'get' command\r\n",
+        )
+        .await;
+        assert_reply(&mut stream, b"*1\r\n$4\r\nPING\r\n", b"+PONG\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn expire_time_out_of_range() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n",
+            b"+OK\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$6\r\nEXPIRE\r\n$1\r\nk\r\n$19\r\n9223372036854775807\r\n",
+            b"-ERR invalid expire time in 'expire' command\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*3\r\n$8\r\nEXPIREAT\r\n$1\r\nk\r\n$19\r\n9223372036854775807\r\n",
+            b"-ERR invalid expire time in 'expireat' command\r\n",
+        )
+        .await;
+        assert_reply(&mut stream, b"*2\r\n$3\r\nTTL\r\n$1\r\nk\r\n", b":-1\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn bitfield_offset_out_of_range() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*5\r\n$8\r\nBITFIELD\r\n$1\r\nk\r\n$3\r\nGET\r\n$3\r\ni64\r\n$20\r\n18446744073709551615\r\n",
+            b"-ERR bit offset is not an integer or out of range\r\n",
+        )
+        .await;
+        assert_reply(&mut stream, b"*1\r\n$4\r\nPING\r\n", b"+PONG\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn ts_timestamp_out_of_range() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*4\r\n$6\r\nTS.ADD\r\n$1\r\nt\r\n$19\r\n9223372036854775808\r\n$1\r\n1\r\n",
+            b"-ERR TSDB: invalid timestamp\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*4\r\n$6\r\nTS.ADD\r\n$1\r\nt\r\n$19\r\n9223372036854775807\r\n$1\r\n1\r\n",
+            b":9223372036854775807\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*4\r\n$8\r\nTS.RANGE\r\n$1\r\nt\r\n$1\r\n-\r\n$20\r\n18446744073709551615\r\n",
+            b"-ERR TSDB: invalid timestamp\r\n",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn zrange_negative_limit_offset() {
+        let addr = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        assert_reply(
+            &mut stream,
+            b"*4\r\n$4\r\nZADD\r\n$1\r\nz\r\n$1\r\n1\r\n$1\r\na\r\n",
+            b":1\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*8\r\n$6\r\nZRANGE\r\n$1\r\nz\r\n$4\r\n-inf\r\n$4\r\n+inf\r\n$7\r\nBYSCORE\r\n$5\r\nLIMIT\r\n$2\r\n-1\r\n$1\r\n5\r\n",
+            b"*0\r\n",
+        )
+        .await;
+        assert_reply(
+            &mut stream,
+            b"*8\r\n$6\r\nZRANGE\r\n$1\r\nz\r\n$4\r\n-inf\r\n$4\r\n+inf\r\n$7\r\nBYSCORE\r\n$5\r\nLIMIT\r\n$1\r\n0\r\n$2\r\n-1\r\n",
+            b"*1\r\n$1\r\na\r\n",
+        )
+        .await;
+    }
+
+    /// Sends `request` and checks that exactly `expected` comes back.
+    async fn assert_reply(stream: &mut TcpStream, request: &[u8], expected: &[u8]) {
+        stream.write_all(request).await.unwrap();
+
+        let mut response = vec![0; expected.len()];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(expected),
+            String::from_utf8_lossy(&response)
+        );
+    }
+
     async fn start_server() -> SocketAddr {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
         let addr = listener.local_addr().unwrap();
 
-        tokio::spawn(async move { run(listener, tokio::signal::ctrl_c()).await });
+        tokio::spawn(async move { run(listener, 16, tokio::signal::ctrl_c()).await });
 
         addr
     }
@@ -1,34 +1,143 @@
+use bytes::Bytes;
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeSet, HashMap, VecDeque},
     sync::Arc,
 };
 use tokio::time::{Duration, Instant};
 
 use tokio::sync::{Mutex, Notify, broadcast};
 
-use crate::storage::entity::Entity;
+use crate::{
+    error::CacheError,
+    storage::{
+        bloom::BloomFilter,
+        count_min::CountMinSketch,
+        cuckoo::CuckooFilter,
+        databases::Databases,
+        entity::Entity,
+        hash::FieldExpirations,
+        hyperloglog::HyperLogLog,
+        list::blocking::BlockedClients,
+        search::SearchIndex,
+        sorted_set::SortedSet,
+        stream::{Stream, unix_millis},
+        string::{Expiry, SetCondition, SetOptions},
+        table::{Table, TableSet},
+        time_series::TimeSeries,
+        top_k::TopK,
+        vector::VectorIndex,
+    },
+};
 
+pub(crate) mod bitmap;
+pub(crate) mod bloom;
+pub(crate) mod count_min;
+pub(crate) mod cuckoo;
+pub(crate) mod databases;
 pub(crate) mod entity;
+pub(crate) mod geo;
+pub(crate) mod hash;
+mod hyperloglog;
+pub(crate) mod json;
+mod keyspace;
+pub(crate) mod list;
+pub(crate) mod scan;
+pub(crate) mod search;
+pub(crate) mod set;
+pub(crate) mod sorted_set;
+pub(crate) mod stream;
+pub(crate) mod string;
+pub(crate) mod table;
+pub(crate) mod time_series;
+pub(crate) mod top_k;
+pub(crate) mod vector;
 
 const CHANNEL_SIZE: usize = 1024;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub(crate) enum Value {
+    String(Entity),
+    List(VecDeque<Bytes>),
+    Hash(Table<Bytes, Bytes>),
+    Set(TableSet<Bytes>),
+    SortedSet(SortedSet),
+    Stream(Stream),
+    HyperLogLog(HyperLogLog),
+    Json(serde_json::Value),
+    Bloom(BloomFilter),
+    Cuckoo(CuckooFilter),
+    CountMinSketch(CountMinSketch),
+    TopK(TopK),
+    TimeSeries(TimeSeries),
+    Vector(VectorIndex),
+}
+
+impl Value {
+    /// The name `TYPE` reports, which is also what `SCAN ... TYPE` filters
+    /// on. HyperLogLogs are strings, as in Redis, and the module types use
+    /// the names of their Redis modules.
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) | Value::HyperLogLog(_) => "string",
+            Value::List(_) => "list",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::SortedSet(_) => "zset",
+            Value::Stream(_) => "stream",
+            Value::Json(_) => "ReJSON-RL",
+            Value::Bloom(_) => "MBbloom--",
+            Value::Cuckoo(_) => "MBbloomCF",
+            Value::CountMinSketch(_) => "CMSk-TYPE",
+            Value::TopK(_) => "TopK-TYPE",
+            Value::TimeSeries(_) => "TSDB-TYPE",
+            Value::Vector(_) => "vectorset",
+        }
+    }
+
+    /// Collections are never stored empty: the key goes away with the last
+    /// element. Streams are the exception, as in Redis, since they also carry
+    /// their last ID, and so are JSON documents, where `{}` is a value.
+    /// Sketches, filters, time series and vector indexes are never empty
+    /// either: they keep their settings.
+    fn is_empty(&self) -> bool {
+        match self {
+            Value::String(_)
+            | Value::Stream(_)
+            | Value::HyperLogLog(_)
+            | Value::Json(_)
+            | Value::Bloom(_)
+            | Value::Cuckoo(_)
+            | Value::CountMinSketch(_)
+            | Value::TopK(_)
+            | Value::TimeSeries(_)
+            | Value::Vector(_) => false,
+            Value::List(list) => list.is_empty(),
+            Value::Hash(hash) => hash.is_empty(),
+            Value::Set(set) => set.is_empty(),
+            Value::SortedSet(zset) => zset.is_empty(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 struct Entry {
-    data: Entity,
+    data: Value,
     expires_at: Option<Instant>,
 }
 
 pub(crate) struct DbDropGuard {
-    db: Db,
+    databases: Databases,
 }
 
 impl DbDropGuard {
-    pub(crate) fn new() -> Self {
-        Self { db: Db::new() }
+    pub(crate) fn new(count: usize) -> Self {
+        Self {
+            databases: Databases::new(count),
+        }
     }
 
-    pub(crate) fn db(&self) -> Db {
-        self.db.clone()
+    pub(crate) fn databases(&self) -> Databases {
+        self.databases.clone()
     }
 }
 
@@ -37,7 +146,7 @@ impl Drop for DbDropGuard {
         tokio::runtime::Builder::new_current_thread()
             .build()
             .unwrap()
-            .block_on(self.db.shutdown_purge_task());
+            .block_on(self.databases.shutdown_purge_tasks());
     }
 }
 
@@ -50,12 +159,16 @@ impl Db {
     pub(crate) fn new() -> Db {
         let shared = Arc::new(Shared {
             state: Mutex::new(State {
-                entities: HashMap::new(),
+                entities: Table::new(),
                 pub_sub: HashMap::new(),
                 expirations: BTreeSet::new(),
+                indexes: HashMap::new(),
+                field_expirations: FieldExpirations::default(),
+                blocked: BlockedClients::default(),
                 shutdown: false,
             }),
             background_task: Notify::new(),
+            stream_written: Notify::new(),
         });
 
         tokio::spawn(purge_expired_tasks(shared.clone()));
@@ -63,51 +176,106 @@ impl Db {
         Db { shared }
     }
 
-    pub(crate) async fn get(&self, key: &Entity) -> Option<Entity> {
+    pub(crate) async fn get(&self, key: &Entity) -> Result<Option<Entity>, CacheError> {
         let state = self.shared.state.lock().await;
-        state.entities.get(key).map(|entry| entry.data.clone())
+        match state.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(CacheError::WrongType),
+        }
     }
 
-    pub(crate) async fn del(&self, key: &Entity) -> Option<Entity> {
-        let mut state = self.shared.state.lock().await;
-        state.entities.remove(key).map(|entry| entry.data.clone())
+    /// Looks up several strings at once. Missing keys and other types of
+    /// values read as `None`.
+    pub(crate) async fn mget(&self, keys: &[Entity]) -> Vec<Option<Entity>> {
+        let state = self.shared.state.lock().await;
+        keys.iter()
+            .map(
+                |key| match state.entities.get(key).map(|entry| &entry.data) {
+                    Some(Value::String(value)) => Some(value.clone()),
+                    _ => None,
+                },
+            )
+            .collect()
     }
 
-    pub(crate) async fn set(&self, key: Entity, value: Entity, expire: Option<Duration>) {
+    /// Removes every key in `keys`. Returns how many of them existed.
+    pub(crate) async fn del(&self, keys: &[Entity]) -> usize {
         let mut state = self.shared.state.lock().await;
-        let mut notify = false;
-
-        let expires_at = expire.map(|duration| {
-            let when = Instant::now() + duration;
-            notify = state
-                .next_expiration()
-                .map(|expiration| expiration > when)
-                .unwrap_or(true);
-            when
-        });
+        keys.iter()
+            .filter(|key| state.remove(key).is_some())
+            .count()
+    }
 
-        let prev = state.entities.insert(
-            key.clone(),
-            Entry {
-                data: value,
-                expires_at,
-            },
-        );
+    /// Counts the keys in `keys` that exist, as often as they are given.
+    pub(crate) async fn exists(&self, keys: &[Entity]) -> usize {
+        let state = self.shared.state.lock().await;
+        keys.iter()
+            .filter(|key| state.entities.contains_key(key))
+            .count()
+    }
 
-        if let Some(prev) = prev
-            && let Some(when) = prev.expires_at
-        {
-            state.expirations.remove(&(when, key.clone()));
+    /// Stores the string `value` at `key` unless the `NX`/`XX` condition of
+    /// `options` fails. Returns whether it was written and, with `GET`, the
+    /// previous string, which must not be another type of value.
+    pub(crate) async fn set(
+        &self,
+        key: Entity,
+        value: Entity,
+        options: SetOptions,
+    ) -> Result<(bool, Option<Entity>), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let previous = state.entities.get(&key);
+        let old = match previous.map(|entry| &entry.data) {
+            Some(Value::String(old)) if options.get => Some(old.clone()),
+            Some(_) if options.get => return Err(CacheError::WrongType),
+            _ => None,
+        };
+        let write = match options.condition {
+            None => true,
+            Some(SetCondition::Nx) => previous.is_none(),
+            Some(SetCondition::Xx) => previous.is_some(),
+        };
+        if !write {
+            return Ok((false, old));
         }
 
-        if let Some(when) = expires_at {
-            state.expirations.insert((when, key));
-        }
+        let expires_at = match options.expiry {
+            Expiry::Keep => previous.and_then(|entry| entry.expires_at),
+            Expiry::Persist => None,
+            Expiry::At(when) => Some(when),
+        };
+        // `EXAT` and `PXAT` may name a time that has already passed.
+        let notify = if expires_at.is_some_and(|when| when <= Instant::now()) {
+            state.remove(&key);
+            false
+        } else {
+            state.insert(key, Value::String(value), expires_at)
+        };
 
         drop(state);
         if notify {
             self.shared.background_task.notify_one();
         }
+        Ok((true, old))
+    }
+
+    /// Stores every string in `pairs`, dropping any TTLs of the keys. With
+    /// `nx` nothing is written if any of the keys exists. Returns whether
+    /// the pairs were written.
+    pub(crate) async fn mset(&self, pairs: Vec<(Entity, Entity)>, nx: bool) -> bool {
+        let mut state = self.shared.state.lock().await;
+        if nx
+            && pairs
+                .iter()
+                .any(|(key, _)| state.entities.contains_key(key))
+        {
+            return false;
+        }
+        for (key, value) in pairs {
+            state.insert(key, Value::String(value), None);
+        }
+        true
     }
 
     pub(crate) async fn subscribe(&self, key: String) -> broadcast::Receiver<Entity> {
@@ -147,6 +315,8 @@ impl Db {
 struct Shared {
     state: Mutex<State>,
     background_task: Notify,
+    /// Woken on every `XADD` so blocked `XREADGROUP` calls can retry.
+    stream_written: Notify,
 }
 
 impl Shared {
@@ -159,12 +329,13 @@ impl Shared {
         let now = Instant::now();
         while let Some(&(when, ref key)) = state.expirations.iter().next() {
             if when > now {
-                return Some(when);
+                break;
             }
-            state.entities.remove(key);
-            state.expirations.remove(&(when, key.clone()));
+            let key = key.clone();
+            state.remove(&key);
         }
-        None
+        state.expire_fields(now);
+        state.next_deadline()
     }
 
     async fn is_shutdown(&self) -> bool {
@@ -174,19 +345,179 @@ impl Shared {
 
 #[derive(Debug)]
 struct State {
-    entities: HashMap<Entity, Entry>,
+    entities: Table<Entity, Entry>,
     pub_sub: HashMap<String, broadcast::Sender<Entity>>,
     expirations: BTreeSet<(Instant, Entity)>,
+    /// Search indexes by name, updated through `reindex`.
+    indexes: HashMap<String, SearchIndex>,
+    /// Hash fields with a TTL of their own.
+    field_expirations: FieldExpirations,
+    blocked: BlockedClients,
     shutdown: bool,
 }
 
 impl State {
+    /// Removes `key` together with its pending expiration, so a stale
+    /// expiration can never evict a value written later under the same key.
+    fn remove(&mut self, key: &Entity) -> Option<Entry> {
+        let entry = self.entities.remove(key)?;
+        if let Some(when) = entry.expires_at {
+            self.expirations.remove(&(when, key.clone()));
+        }
+        self.field_expirations.clear_key(key);
+        self.reindex(key);
+        Some(entry)
+    }
+
+    /// Stores `data` at `key`, replacing any previous value and expiration.
+    /// Returns whether the purge task must be woken up because the new
+    /// expiration is the earliest one.
+    fn insert(&mut self, key: Entity, data: Value, expires_at: Option<Instant>) -> bool {
+        self.remove(&key);
+        let notify = expires_at.is_some_and(|when| {
+            self.next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true)
+        });
+        if let Some(when) = expires_at {
+            self.expirations.insert((when, key.clone()));
+        }
+        self.entities
+            .insert(key.clone(), Entry { data, expires_at });
+        self.reindex(&key);
+        notify
+    }
+
+    /// Moves the expiration of an existing key to `expires_at`, or makes it
+    /// persistent with `None`. A deadline in the past deletes the key right
+    /// away. Returns whether the purge task must be woken up.
+    fn set_expiration(&mut self, key: &Entity, expires_at: Option<Instant>) -> bool {
+        if expires_at.is_some_and(|when| when <= Instant::now()) {
+            self.remove(key);
+            return false;
+        }
+        let Some(entry) = self.entities.get_mut(key) else {
+            return false;
+        };
+        if let Some(when) = std::mem::replace(&mut entry.expires_at, expires_at) {
+            self.expirations.remove(&(when, key.clone()));
+        }
+        let Some(when) = expires_at else {
+            return false;
+        };
+        let notify = self
+            .next_expiration()
+            .map(|expiration| expiration > when)
+            .unwrap_or(true);
+        self.expirations.insert((when, key.clone()));
+        notify
+    }
+
+    fn remove_if_empty(&mut self, key: &Entity) {
+        if self
+            .entities
+            .get(key)
+            .is_some_and(|entry| entry.data.is_empty())
+        {
+            self.remove(key);
+        }
+    }
+
     fn next_expiration(&self) -> Option<Instant> {
         self.expirations
             .iter()
             .next()
             .map(|expiration| expiration.0)
     }
+
+    /// The next time the purge task has work: an expiration of a key or a
+    /// hash field.
+    fn next_deadline(&self) -> Option<Instant> {
+        [self.next_expiration(), self.field_expirations.next()]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+}
+
+/// The time to live of a key or hash field, as `TTL` and `HTTL` report it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Ttl {
+    Missing,
+    Persistent,
+    ExpiresAt(Instant),
+}
+
+/// The `NX`, `XX`, `GT` and `LT` conditions of the `EXPIRE` commands. A
+/// missing TTL counts as infinite, so `GT` never applies to it and `LT`
+/// always does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExpireCondition {
+    Nx,
+    Xx,
+    Gt,
+    Lt,
+}
+
+impl ExpireCondition {
+    pub(crate) fn parse(name: &str) -> Option<ExpireCondition> {
+        match &name.to_uppercase()[..] {
+            "NX" => Some(ExpireCondition::Nx),
+            "XX" => Some(ExpireCondition::Xx),
+            "GT" => Some(ExpireCondition::Gt),
+            "LT" => Some(ExpireCondition::Lt),
+            _ => None,
+        }
+    }
+
+    /// Whether a TTL of `current` may be replaced by one ending at `new`.
+    fn allows(self, current: Option<Instant>, new: Instant) -> bool {
+        match (self, current) {
+            (ExpireCondition::Nx, current) => current.is_none(),
+            (ExpireCondition::Xx, current) => current.is_some(),
+            (ExpireCondition::Gt, Some(current)) => new > current,
+            (ExpireCondition::Gt, None) => false,
+            (ExpireCondition::Lt, Some(current)) => new < current,
+            (ExpireCondition::Lt, None) => true,
+        }
+    }
+}
+
+/// Resolves Redis-style inclusive `start`/`stop` offsets, where negative values
+/// count from the end, into a half-open range over a sequence of `len` items.
+pub(crate) fn range_bounds(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let stop = if stop < 0 { len + stop } else { stop }.min(len - 1);
+    if start > stop || start >= len {
+        return None;
+    }
+    Some((start as usize, stop as usize + 1))
+}
+
+/// Converts a Unix time in milliseconds into an `Instant`, for commands that
+/// take absolute expiration times.
+pub(crate) fn instant_from_unix_millis(ms: u64) -> Instant {
+    let now = Instant::now();
+    let unix_now = unix_millis();
+    if ms >= unix_now {
+        now + Duration::from_millis(ms - unix_now)
+    } else {
+        now.checked_sub(Duration::from_millis(unix_now - ms))
+            .unwrap_or(now)
+    }
+}
+
+/// Converts an `Instant` back into a Unix time in milliseconds, for
+/// commands that report absolute expiration times.
+pub(crate) fn unix_millis_from_instant(when: Instant) -> u64 {
+    let now = Instant::now();
+    let unix_now = unix_millis();
+    if when >= now {
+        unix_now.saturating_add((when - now).as_millis() as u64)
+    } else {
+        unix_now.saturating_sub((now - when).as_millis() as u64)
+    }
 }
 
 async fn purge_expired_tasks(shared: Arc<Shared>) {
@@ -201,3 +532,15 @@ async fn purge_expired_tasks(shared: Arc<Shared>) {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use bytes::Bytes;
+
+    use crate::storage::entity::Entity;
+
+    /// A key as clients send it.
+    pub(crate) fn key(name: &str) -> Entity {
+        Entity::Bulk(Bytes::copy_from_slice(name.as_bytes()))
+    }
+}
@@ -0,0 +1,471 @@
+use bytes::{Bytes, BytesMut};
+
+use crate::{
+    error::CacheError,
+    storage::{Db, Value, entity::Entity, range_bounds},
+};
+
+/// Bit offsets address at most a 512 MB string.
+pub(crate) const MAX_BIT_OFFSET: u64 = (1 << 32) - 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BitOperation {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+/// Whether `BITCOUNT`/`BITPOS` offsets count bytes or bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BitUnit {
+    Byte,
+    Bit,
+}
+
+/// Inclusive `start`/`end` offsets; a missing `end` means the end of the
+/// string.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BitRange {
+    pub(crate) start: i64,
+    pub(crate) end: Option<i64>,
+    pub(crate) unit: BitUnit,
+}
+
+/// A `BITFIELD` integer type such as `i5` or `u16`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FieldType {
+    pub(crate) signed: bool,
+    pub(crate) bits: u32,
+}
+
+impl FieldType {
+    pub(crate) fn parse(raw: &[u8]) -> Option<FieldType> {
+        let (signed, bits) = match raw.split_first()? {
+            (b'i' | b'I', bits) => (true, bits),
+            (b'u' | b'U', bits) => (false, bits),
+            _ => return None,
+        };
+        let bits: u32 = str::from_utf8(bits).ok()?.parse().ok()?;
+        let max = if signed { 64 } else { 63 };
+        (1..=max)
+            .contains(&bits)
+            .then_some(FieldType { signed, bits })
+    }
+
+    fn min(self) -> i128 {
+        if self.signed {
+            -(1 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    fn max(self) -> i128 {
+        if self.signed {
+            (1 << (self.bits - 1)) - 1
+        } else {
+            (1 << self.bits) - 1
+        }
+    }
+
+    /// Interprets the low `bits` bits of `raw`.
+    fn decode(self, raw: u64) -> i64 {
+        if self.signed && self.bits < 64 {
+            let shift = 64 - self.bits;
+            ((raw << shift) as i64) >> shift
+        } else {
+            raw as i64
+        }
+    }
+
+    /// Fits `value` into the type, or `None` when it overflows under `FAIL`.
+    fn fit(self, value: i128, overflow: Overflow) -> Option<i64> {
+        if (self.min()..=self.max()).contains(&value) {
+            return Some(value as i64);
+        }
+        match overflow {
+            Overflow::Wrap => {
+                let modulus = 1i128 << self.bits;
+                let wrapped = value.rem_euclid(modulus);
+                Some(if self.signed && wrapped > self.max() {
+                    wrapped - modulus
+                } else {
+                    wrapped
+                } as i64)
+            }
+            Overflow::Sat => Some(value.clamp(self.min(), self.max()) as i64),
+            Overflow::Fail => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Overflow {
+    Wrap,
+    Sat,
+    Fail,
+}
+
+/// One `BITFIELD` subcommand, with `offset` already resolved to bits.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum FieldOp {
+    Get(FieldType, u64),
+    Set(FieldType, u64, i64, Overflow),
+    IncrBy(FieldType, u64, i64, Overflow),
+}
+
+impl Db {
+    /// Sets or clears one bit, growing the string with zero bytes as needed.
+    /// Returns the previous bit.
+    pub(crate) async fn setbit(
+        &self,
+        key: Entity,
+        offset: u64,
+        on: bool,
+    ) -> Result<bool, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        state.edit_string(key, |buf| {
+            let previous = get_bit(buf, offset);
+            set_bit(buf, offset, on);
+            previous
+        })
+    }
+
+    pub(crate) async fn getbit(&self, key: &Entity, offset: u64) -> Result<bool, CacheError> {
+        let state = self.shared.state.lock().await;
+        let value = state.string(key)?.unwrap_or_default();
+        Ok(get_bit(&value, offset))
+    }
+
+    pub(crate) async fn bitcount(
+        &self,
+        key: &Entity,
+        range: Option<BitRange>,
+    ) -> Result<u64, CacheError> {
+        let state = self.shared.state.lock().await;
+        let value = state.string(key)?.unwrap_or_default();
+        let Some((from, to)) = bit_span(&value, range) else {
+            return Ok(0);
+        };
+        Ok(count_ones(&value, from, to))
+    }
+
+    /// The first bit set to `bit` within `range`, or `-1`. When looking for a
+    /// clear bit without an explicit end, the string counts as padded with
+    /// zeros, so the first bit past its end is returned.
+    pub(crate) async fn bitpos(
+        &self,
+        key: &Entity,
+        bit: bool,
+        range: Option<BitRange>,
+    ) -> Result<i64, CacheError> {
+        let state = self.shared.state.lock().await;
+        let Some(value) = state.string(key)? else {
+            return Ok(if bit { -1 } else { 0 });
+        };
+        let Some((from, to)) = bit_span(&value, range) else {
+            return Ok(-1);
+        };
+        if let Some(pos) = (from..to).find(|pos| get_bit(&value, *pos) == bit) {
+            return Ok(pos as i64);
+        }
+        let open_ended = range.is_none_or(|range| range.end.is_none());
+        Ok(if !bit && open_ended { to as i64 } else { -1 })
+    }
+
+    /// Combines the strings at `keys` byte by byte into `destination`, padding
+    /// shorter ones with zeros. Returns the length of the result.
+    pub(crate) async fn bitop(
+        &self,
+        operation: BitOperation,
+        destination: Entity,
+        keys: &[Entity],
+    ) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let values = keys
+            .iter()
+            .map(|key| Ok(state.string(key)?.unwrap_or_default()))
+            .collect::<Result<Vec<_>, CacheError>>()?;
+        let len = values.iter().map(Bytes::len).max().unwrap_or(0);
+        let byte = |value: &Bytes, i: usize| value.get(i).copied().unwrap_or(0);
+
+        let mut result = vec![0; len];
+        for (i, out) in result.iter_mut().enumerate() {
+            let mut bytes = values.iter().map(|value| byte(value, i));
+            let first = bytes.next().unwrap_or(0);
+            *out = match operation {
+                BitOperation::And => bytes.fold(first, |acc, b| acc & b),
+                BitOperation::Or => bytes.fold(first, |acc, b| acc | b),
+                BitOperation::Xor => bytes.fold(first, |acc, b| acc ^ b),
+                BitOperation::Not => !first,
+            };
+        }
+
+        state.remove(&destination);
+        if len > 0 {
+            state.insert(
+                destination,
+                Value::String(Entity::Bulk(Bytes::from(result))),
+                None,
+            );
+        }
+        Ok(len)
+    }
+
+    /// Runs `BITFIELD` subcommands in order. Each yields the value read, the
+    /// previous value for `SET`, the new value for `INCRBY`, or `None` when a
+    /// write overflowed under `FAIL` and was skipped. Only `GET`s leave a
+    /// missing key missing.
+    pub(crate) async fn bitfield(
+        &self,
+        key: Entity,
+        ops: &[FieldOp],
+    ) -> Result<Vec<Option<i64>>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if ops.iter().all(|op| matches!(op, FieldOp::Get(..))) {
+            let value = state.string(&key)?.unwrap_or_default();
+            return Ok(ops
+                .iter()
+                .map(|op| match *op {
+                    FieldOp::Get(ty, offset) => {
+                        Some(ty.decode(read_field(&value, offset, ty.bits)))
+                    }
+                    _ => unreachable!("only reads"),
+                })
+                .collect());
+        }
+        state.edit_string(key, |buf| {
+            ops.iter().map(|op| run_field_op(buf, *op)).collect()
+        })
+    }
+}
+
+fn run_field_op(buf: &mut BytesMut, op: FieldOp) -> Option<i64> {
+    match op {
+        FieldOp::Get(ty, offset) => Some(ty.decode(read_field(buf, offset, ty.bits))),
+        FieldOp::Set(ty, offset, value, overflow) => {
+            let previous = ty.decode(read_field(buf, offset, ty.bits));
+            ty.fit(value as i128, overflow).map(|value| {
+                write_field(buf, offset, ty.bits, value as u64);
+                previous
+            })
+        }
+        FieldOp::IncrBy(ty, offset, increment, overflow) => {
+            let current = ty.decode(read_field(buf, offset, ty.bits));
+            ty.fit(current as i128 + increment as i128, overflow)
+                .inspect(|value| write_field(buf, offset, ty.bits, *value as u64))
+        }
+    }
+}
+
+/// Resolves `range` into a half-open span of bit positions within `value`.
+fn bit_span(value: &[u8], range: Option<BitRange>) -> Option<(u64, u64)> {
+    let len = value.len();
+    let Some(range) = range else {
+        return (len > 0).then_some((0, len as u64 * 8));
+    };
+    let end = range.end.unwrap_or(-1);
+    match range.unit {
+        BitUnit::Byte => {
+            range_bounds(range.start, end, len).map(|(from, to)| (from as u64 * 8, to as u64 * 8))
+        }
+        BitUnit::Bit => {
+            range_bounds(range.start, end, len * 8).map(|(from, to)| (from as u64, to as u64))
+        }
+    }
+}
+
+/// Bit `0` is the most significant bit of the first byte.
+fn get_bit(buf: &[u8], pos: u64) -> bool {
+    buf.get((pos / 8) as usize)
+        .is_some_and(|byte| byte & (0x80 >> (pos % 8)) != 0)
+}
+
+fn set_bit(buf: &mut BytesMut, pos: u64, on: bool) {
+    let index = (pos / 8) as usize;
+    if buf.len() <= index {
+        buf.resize(index + 1, 0);
+    }
+    let mask = 0x80 >> (pos % 8);
+    if on {
+        buf[index] |= mask;
+    } else {
+        buf[index] &= !mask;
+    }
+}
+
+fn count_ones(buf: &[u8], from: u64, to: u64) -> u64 {
+    let mut count = 0;
+    let mut pos = from;
+    // Count bit by bit up to a byte boundary, then whole bytes at a time.
+    while pos < to && (!pos.is_multiple_of(8) || to - pos < 8) {
+        count += get_bit(buf, pos) as u64;
+        pos += 1;
+    }
+    while to - pos >= 8 {
+        count += buf[(pos / 8) as usize].count_ones() as u64;
+        pos += 8;
+    }
+    count + (pos..to).filter(|pos| get_bit(buf, *pos)).count() as u64
+}
+
+fn read_field(buf: &[u8], offset: u64, bits: u32) -> u64 {
+    (0..bits as u64).fold(0, |acc, i| acc << 1 | get_bit(buf, offset + i) as u64)
+}
+
+fn write_field(buf: &mut BytesMut, offset: u64, bits: u32, value: u64) {
+    for i in 0..bits as u64 {
+        let on = (value >> (bits as u64 - 1 - i)) & 1 == 1;
+        set_bit(buf, offset + i, on);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{string::SetOptions, test_support::key};
+
+    fn field(raw: &str) -> FieldType {
+        FieldType::parse(raw.as_bytes()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn bits_count_and_pos() {
+        let db = Db::new();
+
+        assert!(!db.setbit(key("b"), 7, true).await.unwrap());
+        assert!(db.setbit(key("b"), 7, true).await.unwrap());
+        db.setbit(key("b"), 13, true).await.unwrap();
+        assert!(db.getbit(&key("b"), 13).await.unwrap());
+        assert!(!db.getbit(&key("b"), 1000).await.unwrap());
+
+        assert_eq!(2, db.bitcount(&key("b"), None).await.unwrap());
+        let second_byte = BitRange {
+            start: 1,
+            end: Some(1),
+            unit: BitUnit::Byte,
+        };
+        assert_eq!(1, db.bitcount(&key("b"), Some(second_byte)).await.unwrap());
+        let some_bits = BitRange {
+            start: 5,
+            end: Some(-3),
+            unit: BitUnit::Bit,
+        };
+        assert_eq!(2, db.bitcount(&key("b"), Some(some_bits)).await.unwrap());
+
+        assert_eq!(7, db.bitpos(&key("b"), true, None).await.unwrap());
+        assert_eq!(
+            13,
+            db.bitpos(&key("b"), true, Some(second_byte)).await.unwrap()
+        );
+        assert_eq!(0, db.bitpos(&key("b"), false, None).await.unwrap());
+
+        db.set(
+            key("ones"),
+            Entity::Bulk(Bytes::from_static(b"\xff\xff")),
+            SetOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(16, db.bitpos(&key("ones"), false, None).await.unwrap());
+        let bits = BitRange {
+            start: 0,
+            end: Some(15),
+            unit: BitUnit::Bit,
+        };
+        assert_eq!(
+            -1,
+            db.bitpos(&key("ones"), false, Some(bits)).await.unwrap()
+        );
+        assert_eq!(-1, db.bitpos(&key("missing"), true, None).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn bitop() {
+        let db = Db::new();
+        db.set(
+            key("a"),
+            Entity::Bulk(Bytes::from_static(b"\xf0\x0f")),
+            SetOptions::default(),
+        )
+        .await
+        .unwrap();
+        db.set(
+            key("b"),
+            Entity::Bulk(Bytes::from_static(b"\xff")),
+            SetOptions::default(),
+        )
+        .await
+        .unwrap();
+        let keys = [key("a"), key("b")];
+
+        let check = async |operation, expected: &'static [u8]| {
+            let len = db.bitop(operation, key("dst"), &keys).await.unwrap();
+            assert_eq!(expected.len(), len);
+            assert_eq!(
+                Some(Entity::Bulk(Bytes::from_static(expected))),
+                db.get(&key("dst")).await.unwrap()
+            );
+        };
+        check(BitOperation::And, b"\xf0\x00").await;
+        check(BitOperation::Or, b"\xff\x0f").await;
+        check(BitOperation::Xor, b"\x0f\x0f").await;
+        check(BitOperation::Not, b"\x0f\xf0").await;
+
+        assert_eq!(
+            0,
+            db.bitop(BitOperation::Or, key("dst"), &[key("missing")])
+                .await
+                .unwrap()
+        );
+        assert_eq!(None, db.get(&key("dst")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn bitfield_overflow() {
+        let db = Db::new();
+        let u8 = field("u8");
+        let i8 = field("i8");
+
+        let results = db
+            .bitfield(
+                key("f"),
+                &[
+                    FieldOp::Set(u8, 0, 200, Overflow::Wrap),
+                    FieldOp::IncrBy(u8, 0, 100, Overflow::Wrap),
+                    FieldOp::IncrBy(u8, 0, 300, Overflow::Sat),
+                    FieldOp::IncrBy(u8, 0, 1, Overflow::Fail),
+                    FieldOp::Get(i8, 0),
+                    FieldOp::IncrBy(i8, 8, -129, Overflow::Sat),
+                    FieldOp::Set(field("u4"), 8, 17, Overflow::Wrap),
+                    FieldOp::Get(field("i64"), 0),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![
+                Some(0),
+                Some(44),
+                Some(255),
+                None,
+                Some(-1),
+                Some(-128),
+                Some(8),
+                Some(-67_553_994_410_557_440),
+            ],
+            results
+        );
+
+        assert!(FieldType::parse(b"u64").is_none());
+        assert!(FieldType::parse(b"i0").is_none());
+        assert_eq!(
+            vec![Some(0)],
+            db.bitfield(key("empty"), &[FieldOp::Get(u8, 0)])
+                .await
+                .unwrap()
+        );
+        assert_eq!(None, db.get(&key("empty")).await.unwrap());
+    }
+}
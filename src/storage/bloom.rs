@@ -0,0 +1,266 @@
+use std::{
+    f64::consts::LN_2,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, State, Value, entity::Entity},
+};
+
+/// The filter `BF.ADD` creates for a missing key, as in RedisBloom.
+const DEFAULT_ERROR_RATE: f64 = 0.01;
+const DEFAULT_CAPACITY: u64 = 100;
+const DEFAULT_EXPANSION: u32 = 2;
+/// Each new sub-filter halves the error rate, so the compound rate stays
+/// below twice the requested one however far the filter grows.
+const TIGHTENING_RATIO: f64 = 0.5;
+/// The most bits one sub-filter may have, 512 MiB worth.
+const MAX_BITS: u64 = 1 << 32;
+
+/// `BF.RESERVE` arguments. No expansion makes a filter that refuses new
+/// items once it holds `capacity` of them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BloomParams {
+    pub(crate) error_rate: f64,
+    pub(crate) capacity: u64,
+    pub(crate) expansion: Option<u32>,
+}
+
+impl Default for BloomParams {
+    fn default() -> Self {
+        BloomParams {
+            error_rate: DEFAULT_ERROR_RATE,
+            capacity: DEFAULT_CAPACITY,
+            expansion: Some(DEFAULT_EXPANSION),
+        }
+    }
+}
+
+/// A scalable Bloom filter: a chain of fixed-size filters, each bigger and
+/// stricter than the previous one, that grows as items arrive.
+#[derive(Debug, Clone)]
+pub(crate) struct BloomFilter {
+    expansion: Option<u32>,
+    filters: Vec<Bloom>,
+}
+
+#[derive(Debug, Clone)]
+struct Bloom {
+    bits: Box<[u64]>,
+    bit_count: u64,
+    hashes: u32,
+    capacity: u64,
+    error_rate: f64,
+    items: u64,
+}
+
+impl Bloom {
+    /// Sizes the filter for `capacity` items at `error_rate` with the
+    /// optimal number of hash functions. Fails if that takes more than
+    /// `MAX_BITS`.
+    fn new(capacity: u64, error_rate: f64) -> Result<Bloom, CacheError> {
+        let bits_per_item = -error_rate.ln() / (LN_2 * LN_2);
+        let bits = (capacity as f64 * bits_per_item).ceil();
+        // A vanishing error rate makes it infinite, or NaN for no capacity.
+        if bits.is_nan() || bits > MAX_BITS as f64 {
+            return Err("ERR filter is too large".into());
+        }
+        let bit_count = (bits as u64).max(64);
+        let hashes = (bits_per_item * LN_2).ceil().max(1.0) as u32;
+        Ok(Bloom {
+            bits: vec![0; bit_count.div_ceil(64) as usize].into_boxed_slice(),
+            bit_count,
+            hashes,
+            capacity,
+            error_rate,
+            items: 0,
+        })
+    }
+
+    /// The bits of `item`, by double hashing.
+    fn positions(&self, item: &[u8]) -> impl Iterator<Item = u64> + use<> {
+        let (h1, h2) = (hash_item(item, 0), hash_item(item, 1) | 1);
+        let (hashes, bit_count) = (self.hashes as u64, self.bit_count);
+        (0..hashes).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bit_count)
+    }
+
+    fn contains(&self, item: &[u8]) -> bool {
+        self.positions(item)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    fn add(&mut self, item: &[u8]) {
+        for bit in self.positions(item) {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.items += 1;
+    }
+}
+
+impl BloomFilter {
+    fn new(params: BloomParams) -> Result<BloomFilter, CacheError> {
+        Ok(BloomFilter {
+            expansion: params.expansion,
+            filters: vec![Bloom::new(params.capacity, params.error_rate)?],
+        })
+    }
+
+    fn contains(&self, item: &[u8]) -> bool {
+        self.filters.iter().any(|filter| filter.contains(item))
+    }
+
+    /// Returns whether `item` was new. Items that may already be present are
+    /// not counted again, so they do not use up capacity.
+    fn add(&mut self, item: &[u8]) -> Result<bool, CacheError> {
+        if self.contains(item) {
+            return Ok(false);
+        }
+        let last = self.filters.last().expect("at least one filter");
+        if last.items >= last.capacity {
+            let Some(expansion) = self.expansion else {
+                return Err("ERR non scaling filter is full".into());
+            };
+            let capacity = last
+                .capacity
+                .checked_mul(expansion as u64)
+                .ok_or("ERR filter is too large")?;
+            let next = Bloom::new(capacity, last.error_rate * TIGHTENING_RATIO)?;
+            self.filters.push(next);
+        }
+        self.filters
+            .last_mut()
+            .expect("at least one filter")
+            .add(item);
+        Ok(true)
+    }
+}
+
+/// Hashes `item` with `seed`, for structures that need several independent
+/// hash functions.
+pub(super) fn hash_item(item: &[u8], seed: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Db {
+    pub(crate) async fn bf_reserve(
+        &self,
+        key: Entity,
+        params: BloomParams,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if state.entities.contains_key(&key) {
+            return Err("ERR item exists".into());
+        }
+        let filter = BloomFilter::new(params)?;
+        state.insert(key, Value::Bloom(filter), None);
+        Ok(())
+    }
+
+    /// Adds `item`, creating a default filter for a missing key. Returns
+    /// whether the item was new.
+    pub(crate) async fn bf_add(&self, key: Entity, item: &Bytes) -> Result<bool, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if let Some(filter) = state.bloom_mut(&key)? {
+            return filter.add(item);
+        }
+        let mut filter = BloomFilter::new(BloomParams::default())?;
+        filter.add(item)?;
+        state.insert(key, Value::Bloom(filter), None);
+        Ok(true)
+    }
+
+    /// Whether `item` may have been added. False positives happen at about
+    /// the filter's error rate; false negatives never do.
+    pub(crate) async fn bf_exists(&self, key: &Entity, item: &Bytes) -> Result<bool, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state
+            .bloom(key)?
+            .is_some_and(|filter| filter.contains(item)))
+    }
+}
+
+impl State {
+    fn bloom(&self, key: &Entity) -> Result<Option<&BloomFilter>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::Bloom(filter)) => Ok(Some(filter)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn bloom_mut(&mut self, key: &Entity) -> Result<Option<&mut BloomFilter>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::Bloom(filter)) => Ok(Some(filter)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::key;
+
+    fn item(i: u32) -> Bytes {
+        Bytes::from(format!("item{}", i))
+    }
+
+    #[test]
+    fn scales_within_error_rate() {
+        let mut filter = BloomFilter::new(BloomParams {
+            error_rate: 0.01,
+            capacity: 1000,
+            expansion: Some(2),
+        })
+        .unwrap();
+        for i in 0..5000 {
+            filter.add(&item(i)).unwrap();
+        }
+        assert_eq!(3, filter.filters.len());
+        assert!((0..5000).all(|i| filter.contains(&item(i))));
+
+        let false_positives = (5000..105_000)
+            .filter(|i| filter.contains(&item(*i)))
+            .count();
+        assert!(
+            false_positives < 2000,
+            "{} false positives",
+            false_positives
+        );
+    }
+
+    #[tokio::test]
+    async fn reserve_add_exists() {
+        let db = Db::new();
+        let params = BloomParams {
+            error_rate: 0.001,
+            capacity: 2,
+            expansion: None,
+        };
+        db.bf_reserve(key("f"), params).await.unwrap();
+        assert!(db.bf_reserve(key("f"), params).await.is_err());
+        let huge = BloomParams {
+            capacity: u64::MAX,
+            ..params
+        };
+        assert!(db.bf_reserve(key("huge"), huge).await.is_err());
+        assert_eq!(1, db.dbsize().await);
+
+        assert!(db.bf_add(key("f"), &item(1)).await.unwrap());
+        assert!(!db.bf_add(key("f"), &item(1)).await.unwrap());
+        assert!(db.bf_add(key("f"), &item(2)).await.unwrap());
+        assert!(db.bf_add(key("f"), &item(3)).await.is_err());
+        assert!(db.bf_exists(&key("f"), &item(2)).await.unwrap());
+        assert!(!db.bf_exists(&key("missing"), &item(2)).await.unwrap());
+
+        assert!(db.bf_add(key("auto"), &item(1)).await.unwrap());
+        assert!(db.bf_exists(&key("auto"), &item(1)).await.unwrap());
+    }
+}
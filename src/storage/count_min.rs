@@ -0,0 +1,150 @@
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, State, Value, bloom::hash_item, entity::Entity},
+};
+
+const MISSING_KEY: &str = "ERR CMS: key does not exist";
+/// The most counters one sketch may have, 512 MiB worth.
+const MAX_CELLS: u64 = 1 << 26;
+
+/// A count-min sketch: `depth` rows of `width` counters, one hash function
+/// per row. Estimates never undercount, and overcount by at most
+/// `2 / width` of the total with probability `1 - 0.5^depth`.
+#[derive(Debug, Clone)]
+pub(crate) struct CountMinSketch {
+    width: u64,
+    depth: u64,
+    counters: Box<[u64]>,
+}
+
+impl CountMinSketch {
+    /// Fails if the sketch takes more than `MAX_CELLS`.
+    fn new(width: u64, depth: u64) -> Result<CountMinSketch, CacheError> {
+        let cells = width
+            .checked_mul(depth)
+            .filter(|cells| *cells <= MAX_CELLS)
+            .ok_or("ERR CMS: sketch is too large")?;
+        Ok(CountMinSketch {
+            width,
+            depth,
+            counters: vec![0; cells as usize].into_boxed_slice(),
+        })
+    }
+
+    /// The counter of `item` in each row.
+    fn cells(&self, item: &[u8]) -> Vec<usize> {
+        (0..self.depth)
+            .map(|row| (row * self.width + hash_item(item, row) % self.width) as usize)
+            .collect()
+    }
+
+    fn increment(&mut self, item: &[u8], by: u64) -> u64 {
+        for cell in self.cells(item) {
+            self.counters[cell] = self.counters[cell].saturating_add(by);
+        }
+        self.query(item)
+    }
+
+    fn query(&self, item: &[u8]) -> u64 {
+        self.cells(item)
+            .into_iter()
+            .map(|cell| self.counters[cell])
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+impl Db {
+    pub(crate) async fn cms_init(
+        &self,
+        key: Entity,
+        width: u64,
+        depth: u64,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if state.entities.contains_key(&key) {
+            return Err("ERR CMS: key already exists".into());
+        }
+        let sketch = CountMinSketch::new(width, depth)?;
+        state.insert(key, Value::CountMinSketch(sketch), None);
+        Ok(())
+    }
+
+    /// Adds each increment to its item and returns the new estimates.
+    pub(crate) async fn cms_incrby(
+        &self,
+        key: &Entity,
+        increments: &[(Bytes, u64)],
+    ) -> Result<Vec<u64>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let sketch = state.count_min_mut(key)?.ok_or(MISSING_KEY)?;
+        Ok(increments
+            .iter()
+            .map(|(item, by)| sketch.increment(item, *by))
+            .collect())
+    }
+
+    pub(crate) async fn cms_query(
+        &self,
+        key: &Entity,
+        items: &[Bytes],
+    ) -> Result<Vec<u64>, CacheError> {
+        let state = self.shared.state.lock().await;
+        let sketch = state.count_min(key)?.ok_or(MISSING_KEY)?;
+        Ok(items.iter().map(|item| sketch.query(item)).collect())
+    }
+}
+
+impl State {
+    fn count_min(&self, key: &Entity) -> Result<Option<&CountMinSketch>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::CountMinSketch(sketch)) => Ok(Some(sketch)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn count_min_mut(&mut self, key: &Entity) -> Result<Option<&mut CountMinSketch>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::CountMinSketch(sketch)) => Ok(Some(sketch)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::key;
+
+    fn item(i: u64) -> Bytes {
+        Bytes::from(format!("item{}", i))
+    }
+
+    #[tokio::test]
+    async fn incrby_and_query() {
+        let db = Db::new();
+        assert!(db.cms_query(&key("cms"), &[item(0)]).await.is_err());
+        db.cms_init(key("cms"), 2000, 5).await.unwrap();
+        assert!(db.cms_init(key("cms"), 10, 1).await.is_err());
+        assert!(db.cms_init(key("big"), 1 << 30, 1 << 29).await.is_err());
+        assert!(db.cms_init(key("big"), MAX_CELLS, 2).await.is_err());
+        assert_eq!(1, db.dbsize().await);
+
+        let increments: Vec<_> = (0..1000).map(|i| (item(i), i)).collect();
+        let estimates = db.cms_incrby(&key("cms"), &increments).await.unwrap();
+        // Never below the true count, and the error bound is 2 * 499500 / 2000.
+        for (i, estimate) in estimates.into_iter().enumerate() {
+            assert!((i as u64..=i as u64 + 500).contains(&estimate));
+        }
+        let counts = db
+            .cms_query(&key("cms"), &[item(999), item(5000)])
+            .await
+            .unwrap();
+        assert!(counts[0] >= 999);
+        assert!(counts[1] <= 500);
+    }
+}
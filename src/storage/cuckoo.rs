@@ -0,0 +1,326 @@
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, State, Value, bloom::hash_item, entity::Entity},
+};
+
+/// The filter `CF.ADD` creates for a missing key, as in RedisBloom.
+const DEFAULT_CAPACITY: u64 = 1024;
+const DEFAULT_BUCKET_SIZE: usize = 2;
+const DEFAULT_MAX_ITERATIONS: u32 = 20;
+const DEFAULT_EXPANSION: u32 = 1;
+/// An empty slot. Fingerprints are never zero.
+const EMPTY: u8 = 0;
+/// The most slots one table may have, 512 MiB worth.
+const MAX_SLOTS: u64 = 1 << 29;
+
+/// `CF.RESERVE` arguments. An expansion of `0` makes a filter that refuses
+/// new items once an insertion fails.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CuckooParams {
+    pub(crate) capacity: u64,
+    pub(crate) bucket_size: usize,
+    pub(crate) max_iterations: u32,
+    pub(crate) expansion: u32,
+}
+
+impl Default for CuckooParams {
+    fn default() -> Self {
+        CuckooParams {
+            capacity: DEFAULT_CAPACITY,
+            bucket_size: DEFAULT_BUCKET_SIZE,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            expansion: DEFAULT_EXPANSION,
+        }
+    }
+}
+
+/// A cuckoo filter storing 8-bit fingerprints in buckets, each item in one
+/// of two candidate buckets. Unlike a Bloom filter it supports deletion.
+/// When an insertion fails it chains a new, larger table.
+#[derive(Debug, Clone)]
+pub(crate) struct CuckooFilter {
+    bucket_size: usize,
+    max_iterations: u32,
+    expansion: u32,
+    tables: Vec<Table>,
+}
+
+#[derive(Debug, Clone)]
+struct Table {
+    /// Always a power of two, so the alternate bucket is an XOR away.
+    buckets: u64,
+    slots: Box<[u8]>,
+}
+
+impl Table {
+    /// Fails if `capacity` takes more than `MAX_SLOTS`.
+    fn new(capacity: u64, bucket_size: usize) -> Result<Table, CacheError> {
+        let slots = capacity
+            .div_ceil(bucket_size as u64)
+            .checked_next_power_of_two()
+            .and_then(|buckets| Some((buckets, buckets.checked_mul(bucket_size as u64)?)))
+            .filter(|(_, slots)| *slots <= MAX_SLOTS);
+        let Some((buckets, slots)) = slots else {
+            return Err("ERR filter is too large".into());
+        };
+        Ok(Table {
+            buckets,
+            slots: vec![EMPTY; slots as usize].into_boxed_slice(),
+        })
+    }
+
+    fn bucket(&self, index: u64, bucket_size: usize) -> &[u8] {
+        let start = index as usize * bucket_size;
+        &self.slots[start..start + bucket_size]
+    }
+
+    fn bucket_mut(&mut self, index: u64, bucket_size: usize) -> &mut [u8] {
+        let start = index as usize * bucket_size;
+        &mut self.slots[start..start + bucket_size]
+    }
+
+    /// The two buckets a fingerprint may live in.
+    fn indexes(&self, hash: u64, fingerprint: u8) -> (u64, u64) {
+        let first = hash & (self.buckets - 1);
+        (first, self.alternate(first, fingerprint))
+    }
+
+    fn alternate(&self, index: u64, fingerprint: u8) -> u64 {
+        (index ^ (fingerprint as u64).wrapping_mul(0x5bd1_e995)) & (self.buckets - 1)
+    }
+}
+
+/// The fingerprint of `hash`, using the bits its bucket index does not.
+fn fingerprint(hash: u64) -> u8 {
+    match (hash >> 56) as u8 {
+        EMPTY => 1,
+        fingerprint => fingerprint,
+    }
+}
+
+impl CuckooFilter {
+    fn new(params: CuckooParams) -> Result<CuckooFilter, CacheError> {
+        Ok(CuckooFilter {
+            bucket_size: params.bucket_size,
+            max_iterations: params.max_iterations,
+            expansion: params.expansion,
+            tables: vec![Table::new(params.capacity, params.bucket_size)?],
+        })
+    }
+
+    fn contains(&self, item: &[u8]) -> bool {
+        let hash = hash_item(item, 0);
+        let fp = fingerprint(hash);
+        self.tables.iter().any(|table| {
+            let (first, second) = table.indexes(hash, fp);
+            table.bucket(first, self.bucket_size).contains(&fp)
+                || table.bucket(second, self.bucket_size).contains(&fp)
+        })
+    }
+
+    /// Adds `item`, even if it is already present.
+    fn add(&mut self, item: &[u8]) -> Result<(), CacheError> {
+        let hash = hash_item(item, 0);
+        let fp = fingerprint(hash);
+        let table = self.tables.last_mut().expect("at least one table");
+        if insert(table, hash, fp, self.bucket_size, self.max_iterations) {
+            return Ok(());
+        }
+        if self.expansion == 0 {
+            return Err("ERR Filter is full".into());
+        }
+        let capacity = table.slots.len() as u64 * self.expansion as u64;
+        let mut table = Table::new(capacity, self.bucket_size)?;
+        let inserted = insert(&mut table, hash, fp, self.bucket_size, self.max_iterations);
+        debug_assert!(inserted, "an empty table has room");
+        self.tables.push(table);
+        Ok(())
+    }
+
+    /// Removes one copy of `item`, newest table first. Returns whether one
+    /// was found.
+    fn delete(&mut self, item: &[u8]) -> bool {
+        let hash = hash_item(item, 0);
+        let fp = fingerprint(hash);
+        let bucket_size = self.bucket_size;
+        for table in self.tables.iter_mut().rev() {
+            let (first, second) = table.indexes(hash, fp);
+            for index in [first, second] {
+                let bucket = table.bucket_mut(index, bucket_size);
+                if let Some(slot) = bucket.iter_mut().find(|slot| **slot == fp) {
+                    *slot = EMPTY;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Places `fp` in one of its buckets, evicting residents to their alternate
+/// buckets when both are full. Undoes the evictions and returns false if no
+/// room turns up within `max_iterations` moves.
+fn insert(table: &mut Table, hash: u64, fp: u8, bucket_size: usize, max_iterations: u32) -> bool {
+    let (first, second) = table.indexes(hash, fp);
+    for index in [first, second] {
+        if let Some(slot) = table
+            .bucket_mut(index, bucket_size)
+            .iter_mut()
+            .find(|slot| **slot == EMPTY)
+        {
+            *slot = fp;
+            return true;
+        }
+    }
+
+    let mut moves = vec![];
+    let (mut index, mut fp) = (second, fp);
+    for iteration in 0..max_iterations as usize {
+        // Rotate through the slots instead of picking them at random, which
+        // keeps the filter deterministic.
+        let slot = index as usize * bucket_size + iteration % bucket_size;
+        moves.push(slot);
+        std::mem::swap(&mut fp, &mut table.slots[slot]);
+        index = table.alternate(index, fp);
+        if let Some(empty) = table
+            .bucket_mut(index, bucket_size)
+            .iter_mut()
+            .find(|slot| **slot == EMPTY)
+        {
+            *empty = fp;
+            return true;
+        }
+    }
+    for slot in moves.into_iter().rev() {
+        std::mem::swap(&mut fp, &mut table.slots[slot]);
+    }
+    false
+}
+
+impl Db {
+    pub(crate) async fn cf_reserve(
+        &self,
+        key: Entity,
+        params: CuckooParams,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if state.entities.contains_key(&key) {
+            return Err("ERR item exists".into());
+        }
+        let filter = CuckooFilter::new(params)?;
+        state.insert(key, Value::Cuckoo(filter), None);
+        Ok(())
+    }
+
+    /// Adds `item`, creating a default filter for a missing key.
+    pub(crate) async fn cf_add(&self, key: Entity, item: &Bytes) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if let Some(filter) = state.cuckoo_mut(&key)? {
+            return filter.add(item);
+        }
+        let mut filter = CuckooFilter::new(CuckooParams::default())?;
+        filter.add(item)?;
+        state.insert(key, Value::Cuckoo(filter), None);
+        Ok(())
+    }
+
+    /// Whether `item` may have been added and not deleted since.
+    pub(crate) async fn cf_exists(&self, key: &Entity, item: &Bytes) -> Result<bool, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state
+            .cuckoo(key)?
+            .is_some_and(|filter| filter.contains(item)))
+    }
+
+    /// Deletes one copy of `item`. Only items that were added may be
+    /// deleted: deleting a false positive removes another item's fingerprint.
+    pub(crate) async fn cf_del(&self, key: &Entity, item: &Bytes) -> Result<bool, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        Ok(state
+            .cuckoo_mut(key)?
+            .is_some_and(|filter| filter.delete(item)))
+    }
+}
+
+impl State {
+    fn cuckoo(&self, key: &Entity) -> Result<Option<&CuckooFilter>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::Cuckoo(filter)) => Ok(Some(filter)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn cuckoo_mut(&mut self, key: &Entity) -> Result<Option<&mut CuckooFilter>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::Cuckoo(filter)) => Ok(Some(filter)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::key;
+
+    fn item(i: u32) -> Bytes {
+        Bytes::from(format!("item{}", i))
+    }
+
+    #[test]
+    fn add_delete_and_grow() {
+        let mut filter = CuckooFilter::new(CuckooParams {
+            capacity: 1000,
+            ..CuckooParams::default()
+        })
+        .unwrap();
+        for i in 0..3000 {
+            filter.add(&item(i)).unwrap();
+        }
+        assert!(filter.tables.len() > 1);
+        assert!((0..3000).all(|i| filter.contains(&item(i))));
+
+        for i in 0..3000 {
+            assert!(filter.delete(&item(i)));
+        }
+        let false_positives = (0..3000).filter(|i| filter.contains(&item(*i))).count();
+        assert_eq!(0, false_positives);
+    }
+
+    #[tokio::test]
+    async fn full_filter() {
+        let db = Db::new();
+        let params = CuckooParams {
+            capacity: 4,
+            bucket_size: 1,
+            max_iterations: 5,
+            expansion: 0,
+        };
+        db.cf_reserve(key("f"), params).await.unwrap();
+        let huge = CuckooParams {
+            capacity: u64::MAX,
+            ..params
+        };
+        assert!(db.cf_reserve(key("huge"), huge).await.is_err());
+        assert_eq!(1, db.dbsize().await);
+        let mut added = 0;
+        while db.cf_add(key("f"), &item(added)).await.is_ok() {
+            added += 1;
+        }
+        assert!((1..=4).contains(&added));
+        for i in 0..added {
+            assert!(db.cf_exists(&key("f"), &item(i)).await.unwrap());
+        }
+
+        assert!(db.cf_del(&key("f"), &item(0)).await.unwrap());
+        assert!(!db.cf_del(&key("missing"), &item(0)).await.unwrap());
+        db.cf_add(key("auto"), &item(1)).await.unwrap();
+        db.cf_add(key("auto"), &item(1)).await.unwrap();
+        assert!(db.cf_del(&key("auto"), &item(1)).await.unwrap());
+        assert!(db.cf_exists(&key("auto"), &item(1)).await.unwrap());
+    }
+}
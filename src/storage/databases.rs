@@ -0,0 +1,180 @@
+use std::{mem, sync::Arc};
+
+use tokio::sync::MutexGuard;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, State, entity::Entity},
+};
+
+/// The numbered databases of a server. Each is a `Db` of its own, with its
+/// own lock and purge task, and connections pick one with `SELECT`. Pub/sub
+/// channels are server-wide, as in Redis, so they all live in the first.
+#[derive(Debug, Clone)]
+pub(crate) struct Databases {
+    dbs: Arc<[Db]>,
+}
+
+impl Databases {
+    pub(crate) fn new(count: usize) -> Databases {
+        Databases {
+            dbs: (0..count.max(1)).map(|_| Db::new()).collect(),
+        }
+    }
+
+    pub(crate) fn get(&self, index: usize) -> &Db {
+        &self.dbs[index]
+    }
+
+    /// The database that holds every pub/sub channel.
+    pub(crate) fn pub_sub(&self) -> &Db {
+        &self.dbs[0]
+    }
+
+    /// Checks a database index given by a client.
+    pub(crate) fn index(&self, index: i64) -> Result<usize, CacheError> {
+        usize::try_from(index)
+            .ok()
+            .filter(|index| *index < self.dbs.len())
+            .ok_or_else(|| "ERR DB index is out of range".into())
+    }
+
+    /// Moves `key` from database `from` to database `to` along with its TTL
+    /// and those of its fields, unless `to` already holds the key. Returns
+    /// whether it was moved.
+    pub(crate) async fn move_key(
+        &self,
+        key: &Entity,
+        from: usize,
+        to: usize,
+    ) -> Result<bool, CacheError> {
+        if from == to {
+            return Err("ERR source and destination objects are the same".into());
+        }
+        let (mut source, mut destination) = self.lock_pair(from, to).await;
+        if !source.entities.contains_key(key) || destination.entities.contains_key(key) {
+            return Ok(false);
+        }
+        let fields = source.field_expirations.key_deadlines(key);
+        let entry = source.remove(key).expect("the key exists");
+        let notify = destination.place(key, entry, fields);
+
+        drop(source);
+        drop(destination);
+        if notify {
+            self.dbs[to].shared.background_task.notify_one();
+        }
+        Ok(true)
+    }
+
+    /// Swaps the keys of two databases, with their TTLs and search indexes,
+    /// in one step. Clients blocked on a database stay with it and are
+    /// served from the keys it now holds.
+    pub(crate) async fn swap(&self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        let (mut first, mut second) = self.lock_pair(a, b).await;
+        mem::swap(&mut first.entities, &mut second.entities);
+        mem::swap(&mut first.expirations, &mut second.expirations);
+        mem::swap(&mut first.indexes, &mut second.indexes);
+        mem::swap(&mut first.field_expirations, &mut second.field_expirations);
+        first.serve_all_blocked();
+        second.serve_all_blocked();
+
+        drop(first);
+        drop(second);
+        for index in [a, b] {
+            let shared = &self.dbs[index].shared;
+            // Both have a new next deadline, and maybe streams to read.
+            shared.background_task.notify_one();
+            shared.stream_written.notify_waiters();
+        }
+    }
+
+    pub(crate) async fn flush_all(&self, lazy: bool) {
+        for db in self.dbs.iter() {
+            db.flush(lazy).await;
+        }
+    }
+
+    pub(crate) async fn shutdown_purge_tasks(&self) {
+        for db in self.dbs.iter() {
+            db.shutdown_purge_task().await;
+        }
+    }
+
+    /// Locks two different databases, always the lower index first so that
+    /// two commands crossing the same pair cannot deadlock.
+    async fn lock_pair(
+        &self,
+        a: usize,
+        b: usize,
+    ) -> (MutexGuard<'_, State>, MutexGuard<'_, State>) {
+        debug_assert_ne!(a, b);
+        if a < b {
+            let first = self.dbs[a].shared.state.lock().await;
+            (first, self.dbs[b].shared.state.lock().await)
+        } else {
+            let second = self.dbs[b].shared.state.lock().await;
+            (self.dbs[a].shared.state.lock().await, second)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use tokio::time::Instant;
+
+    use super::*;
+    use crate::storage::{Ttl, string::SetOptions, test_support::key};
+
+    fn value(value: &'static str) -> Entity {
+        Entity::Bulk(Bytes::from_static(value.as_bytes()))
+    }
+
+    #[tokio::test]
+    async fn move_and_swap() {
+        let databases = Databases::new(3);
+        let when = Instant::now() + Duration::from_secs(60);
+        databases
+            .get(0)
+            .set(key("k"), value("a"), SetOptions::default())
+            .await
+            .unwrap();
+        databases.get(0).expire(&key("k"), when, None).await;
+        databases
+            .get(1)
+            .set(key("k"), value("b"), SetOptions::default())
+            .await
+            .unwrap();
+
+        assert!(databases.index(3).is_err());
+        assert!(databases.index(-1).is_err());
+        assert!(databases.move_key(&key("k"), 0, 0).await.is_err());
+        assert!(!databases.move_key(&key("k"), 0, 1).await.unwrap());
+        assert!(!databases.move_key(&key("nope"), 0, 2).await.unwrap());
+        assert!(databases.move_key(&key("k"), 0, 2).await.unwrap());
+        assert_eq!(0, databases.get(0).dbsize().await);
+        assert_eq!(Ttl::ExpiresAt(when), databases.get(2).ttl(&key("k")).await);
+
+        databases.swap(1, 2).await;
+        assert_eq!(
+            Some(value("a")),
+            databases.get(1).get(&key("k")).await.unwrap()
+        );
+        assert_eq!(
+            Some(value("b")),
+            databases.get(2).get(&key("k")).await.unwrap()
+        );
+        assert_eq!(Ttl::ExpiresAt(when), databases.get(1).ttl(&key("k")).await);
+        assert_eq!(Ttl::Persistent, databases.get(2).ttl(&key("k")).await);
+
+        databases.flush_all(false).await;
+        assert_eq!(0, databases.get(1).dbsize().await);
+        assert_eq!(0, databases.get(2).dbsize().await);
+    }
+}
@@ -216,6 +216,11 @@ fn get_line<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a [u8], CacheError> {
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::single_match,
+    clippy::get_first,
+    clippy::assertions_on_constants
+)]
 mod tests {
     use super::*;
 
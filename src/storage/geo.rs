@@ -0,0 +1,435 @@
+use std::ops::Bound;
+
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{
+        Db,
+        entity::Entity,
+        sorted_set::{RangeBy, RangeSpec, SortedSet},
+    },
+};
+
+/// Bits per coordinate: scores hold a 52-bit interleaved geohash, which a
+/// `f64` represents exactly.
+const STEP: u32 = 26;
+const LON_MIN: f64 = -180.0;
+const LON_MAX: f64 = 180.0;
+/// The Web Mercator limits, as in Redis.
+const LAT_MIN: f64 = -85.05112878;
+const LAT_MAX: f64 = 85.05112878;
+const EARTH_RADIUS_M: f64 = 6372797.560856;
+const BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Coord {
+    pub(crate) lon: f64,
+    pub(crate) lat: f64,
+}
+
+impl Coord {
+    /// Returns `None` outside the indexable area.
+    pub(crate) fn new(lon: f64, lat: f64) -> Option<Coord> {
+        ((LON_MIN..=LON_MAX).contains(&lon) && (LAT_MIN..=LAT_MAX).contains(&lat))
+            .then_some(Coord { lon, lat })
+    }
+
+    /// The sorted-set score of a member stored at this position.
+    pub(crate) fn score(self) -> f64 {
+        encode(self, LAT_MIN, LAT_MAX, STEP) as f64
+    }
+
+    fn from_score(score: f64) -> Coord {
+        let hash = score as u64;
+        let (lat_cell, lon_cell) = deinterleave(hash);
+        let cell = |index: u32, min: f64, max: f64| {
+            let width = (max - min) / (1u64 << STEP) as f64;
+            min + (index as f64 + 0.5) * width
+        };
+        Coord {
+            lon: cell(lon_cell, LON_MIN, LON_MAX).clamp(LON_MIN, LON_MAX),
+            lat: cell(lat_cell, LAT_MIN, LAT_MAX).clamp(LAT_MIN, LAT_MAX),
+        }
+    }
+
+    /// The 11-character standard geohash, which spans latitudes ±90 rather
+    /// than the Mercator range used for scores.
+    fn geohash(self) -> String {
+        let hash = encode(self, -90.0, 90.0, STEP);
+        (0..11)
+            .map(|i| {
+                // 52 bits fill ten characters and two bits of the eleventh.
+                let index = if i == 10 {
+                    0
+                } else {
+                    (hash >> (52 - (i + 1) * 5)) & 0x1f
+                };
+                BASE32[index as usize] as char
+            })
+            .collect()
+    }
+
+    /// Great-circle distance in meters.
+    fn distance(self, other: Coord) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let u = ((lat2 - lat1) / 2.0).sin();
+        let v = ((other.lon - self.lon).to_radians() / 2.0).sin();
+        let a = u * u + lat1.cos() * lat2.cos() * v * v;
+        2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+    }
+}
+
+/// Meters per unit of a distance unit argument.
+pub(crate) fn unit_factor(unit: &str) -> Option<f64> {
+    match &unit.to_lowercase()[..] {
+        "m" => Some(1.0),
+        "km" => Some(1000.0),
+        "mi" => Some(1609.34),
+        "ft" => Some(0.3048),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum GeoFrom {
+    Member(Bytes),
+    Coord(Coord),
+}
+
+/// The search area, in meters.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum GeoShape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct GeoQuery {
+    pub(crate) from: GeoFrom,
+    pub(crate) shape: GeoShape,
+    /// `Some(true)` for `ASC`, `Some(false)` for `DESC`.
+    pub(crate) ascending: Option<bool>,
+    /// `COUNT n [ANY]`.
+    pub(crate) count: Option<(usize, bool)>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct GeoMatch {
+    pub(crate) member: Bytes,
+    /// Meters from the search center.
+    pub(crate) distance: f64,
+    pub(crate) hash: u64,
+    pub(crate) coord: Coord,
+}
+
+impl Db {
+    /// Distance in meters between two members, or `None` if either is missing.
+    pub(crate) async fn geodist(
+        &self,
+        key: &Entity,
+        from: &Bytes,
+        to: &Bytes,
+    ) -> Result<Option<f64>, CacheError> {
+        let state = self.shared.state.lock().await;
+        let Some(zset) = state.sorted_set(key)? else {
+            return Ok(None);
+        };
+        let from = zset.score(from).map(Coord::from_score);
+        let to = zset.score(to).map(Coord::from_score);
+        Ok(from.zip(to).map(|(from, to)| from.distance(to)))
+    }
+
+    pub(crate) async fn geopos(
+        &self,
+        key: &Entity,
+        members: &[Bytes],
+    ) -> Result<Vec<Option<Coord>>, CacheError> {
+        let state = self.shared.state.lock().await;
+        let zset = state.sorted_set(key)?;
+        Ok(members
+            .iter()
+            .map(|member| {
+                zset.and_then(|zset| zset.score(member))
+                    .map(Coord::from_score)
+            })
+            .collect())
+    }
+
+    pub(crate) async fn geohash(
+        &self,
+        key: &Entity,
+        members: &[Bytes],
+    ) -> Result<Vec<Option<String>>, CacheError> {
+        Ok(self
+            .geopos(key, members)
+            .await?
+            .into_iter()
+            .map(|coord| coord.map(Coord::geohash))
+            .collect())
+    }
+
+    pub(crate) async fn geosearch(
+        &self,
+        key: &Entity,
+        query: &GeoQuery,
+    ) -> Result<Vec<GeoMatch>, CacheError> {
+        let state = self.shared.state.lock().await;
+        let Some(zset) = state.sorted_set(key)? else {
+            return Ok(vec![]);
+        };
+        let center = match &query.from {
+            GeoFrom::Coord(coord) => *coord,
+            GeoFrom::Member(member) => zset
+                .score(member)
+                .map(Coord::from_score)
+                .ok_or("ERR could not decode requested zset member")?,
+        };
+
+        let (limit, any) = query.count.unwrap_or((usize::MAX, false));
+        let mut matches = vec![];
+        'cells: for (min, max) in covering_ranges(center, query.shape) {
+            for (member, score) in score_range(zset, min, max) {
+                let coord = Coord::from_score(score);
+                let Some(distance) = within(center, coord, query.shape) else {
+                    continue;
+                };
+                matches.push(GeoMatch {
+                    member,
+                    distance,
+                    hash: score as u64,
+                    coord,
+                });
+                if any && matches.len() == limit {
+                    break 'cells;
+                }
+            }
+        }
+
+        // A plain `COUNT` keeps the closest matches.
+        let ascending = query
+            .ascending
+            .or((query.count.is_some() && !any).then_some(true));
+        if let Some(ascending) = ascending {
+            matches.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+            if !ascending {
+                matches.reverse();
+            }
+        }
+        matches.truncate(limit);
+        Ok(matches)
+    }
+}
+
+/// The distance from `center` if `coord` lies inside `shape`.
+fn within(center: Coord, coord: Coord, shape: GeoShape) -> Option<f64> {
+    let distance = center.distance(coord);
+    match shape {
+        GeoShape::Radius(radius) => (distance <= radius).then_some(distance),
+        GeoShape::Box { width, height } => {
+            let lat_distance = center.distance(Coord {
+                lon: center.lon,
+                lat: coord.lat,
+            });
+            let lon_distance = coord.distance(Coord {
+                lon: center.lon,
+                lat: coord.lat,
+            });
+            (lat_distance <= height / 2.0 && lon_distance <= width / 2.0).then_some(distance)
+        }
+    }
+}
+
+/// Score ranges of the geohash cells that cover the bounding box of `shape`,
+/// at the finest precision where at most nine cells do.
+fn covering_ranges(center: Coord, shape: GeoShape) -> Vec<(u64, u64)> {
+    let (half_width, half_height) = match shape {
+        GeoShape::Radius(radius) => (radius, radius),
+        GeoShape::Box { width, height } => (width / 2.0, height / 2.0),
+    };
+    let lat_delta = (half_height / EARTH_RADIUS_M).to_degrees();
+    let min_lat = (center.lat - lat_delta).max(LAT_MIN);
+    let max_lat = (center.lat + lat_delta).min(LAT_MAX);
+    // Longitude degrees shrink towards the poles, so size the box for the
+    // latitude farthest from the equator.
+    let widest = min_lat.abs().max(max_lat.abs()).to_radians().cos();
+    let lon_delta = (half_width / EARTH_RADIUS_M / widest).to_degrees();
+    let lon_delta = if lon_delta.is_finite() {
+        lon_delta.min(180.0)
+    } else {
+        180.0
+    };
+
+    let cells = |value: f64, min: f64, max: f64, step: u32| {
+        ((value - min) / (max - min) * (1u64 << step) as f64).floor() as i64
+    };
+    for step in (1..=STEP).rev() {
+        let lon_from = cells(center.lon - lon_delta, LON_MIN, LON_MAX, step);
+        let lon_to = cells(center.lon + lon_delta, LON_MIN, LON_MAX, step);
+        let side = 1i64 << step;
+        let lat_from = cells(min_lat, LAT_MIN, LAT_MAX, step).clamp(0, side - 1);
+        let lat_to = cells(max_lat, LAT_MIN, LAT_MAX, step).clamp(0, side - 1);
+        let lon_cells = (lon_to - lon_from + 1).min(side);
+        if lon_cells * (lat_to - lat_from + 1) > 9 && step > 1 {
+            continue;
+        }
+
+        let shift = 2 * (STEP - step);
+        let mut ranges = vec![];
+        for lat in lat_from..=lat_to {
+            // Longitude wraps around the antimeridian.
+            for lon in (lon_from..lon_from + lon_cells).map(|lon| lon.rem_euclid(side)) {
+                let hash = interleave(lat as u32, lon as u32);
+                ranges.push((hash << shift, (hash + 1) << shift));
+            }
+        }
+        return ranges;
+    }
+    unreachable!("a single step always covers the box")
+}
+
+fn score_range(zset: &SortedSet, min: u64, max: u64) -> Vec<(Bytes, f64)> {
+    zset.range(&RangeSpec {
+        by: RangeBy::Score(Bound::Included(min as f64), Bound::Excluded(max as f64)),
+        rev: false,
+        limit: None,
+    })
+}
+
+fn encode(coord: Coord, lat_min: f64, lat_max: f64, step: u32) -> u64 {
+    let scale = (1u64 << step) as f64;
+    let lat = ((coord.lat - lat_min) / (lat_max - lat_min) * scale) as u32;
+    let lon = ((coord.lon - LON_MIN) / (LON_MAX - LON_MIN) * scale) as u32;
+    let max = (1u32 << step) - 1;
+    interleave(lat.min(max), lon.min(max))
+}
+
+/// Interleaves the bits of `lat` (even positions) and `lon` (odd positions),
+/// so the most significant bit of a geohash is a longitude bit.
+fn interleave(lat: u32, lon: u32) -> u64 {
+    spread(lat) | (spread(lon) << 1)
+}
+
+fn deinterleave(hash: u64) -> (u32, u32) {
+    (squash(hash), squash(hash >> 1))
+}
+
+fn spread(value: u32) -> u64 {
+    let mut x = value as u64;
+    x = (x | (x << 16)) & 0x0000_FFFF_0000_FFFF;
+    x = (x | (x << 8)) & 0x00FF_00FF_00FF_00FF;
+    x = (x | (x << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    x = (x | (x << 2)) & 0x3333_3333_3333_3333;
+    (x | (x << 1)) & 0x5555_5555_5555_5555
+}
+
+fn squash(hash: u64) -> u32 {
+    let mut x = hash & 0x5555_5555_5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+    x = (x | (x >> 2)) & 0x0F0F_0F0F_0F0F_0F0F;
+    x = (x | (x >> 4)) & 0x00FF_00FF_00FF_00FF;
+    x = (x | (x >> 8)) & 0x0000_FFFF_0000_FFFF;
+    ((x | (x >> 16)) & 0x0000_0000_FFFF_FFFF) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{sorted_set::ZAddFlags, test_support::key};
+
+    fn coord(lon: f64, lat: f64) -> Coord {
+        Coord::new(lon, lat).unwrap()
+    }
+
+    async fn sicily() -> Db {
+        let db = Db::new();
+        let places = [
+            (13.361389, 38.115556, "Palermo"),
+            (15.087269, 37.502669, "Catania"),
+            (12.758489, 38.788135, "edge1"),
+            (17.241510, 38.788135, "edge2"),
+        ];
+        let pairs = places
+            .iter()
+            .map(|(lon, lat, name)| (coord(*lon, *lat).score(), Bytes::from(*name)))
+            .collect();
+        db.zadd(key("Sicily"), pairs, ZAddFlags::default())
+            .await
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let palermo = coord(13.361389, 38.115556);
+        let decoded = Coord::from_score(palermo.score());
+        assert!((decoded.lon - palermo.lon).abs() < 1e-5);
+        assert!((decoded.lat - palermo.lat).abs() < 1e-5);
+        assert_eq!("sqc8b49rny0", decoded.geohash());
+        assert_eq!(3479099956230698.0, palermo.score());
+
+        assert!(Coord::new(0.0, 86.0).is_none());
+        assert!(Coord::new(181.0, 0.0).is_none());
+    }
+
+    #[tokio::test]
+    async fn distance_and_positions() {
+        let db = sicily().await;
+        let dist = db
+            .geodist(
+                &key("Sicily"),
+                &Bytes::from("Palermo"),
+                &Bytes::from("Catania"),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((dist - 166274.1516).abs() < 0.01);
+
+        let positions = db
+            .geopos(&key("Sicily"), &[Bytes::from("Palermo"), Bytes::from("x")])
+            .await
+            .unwrap();
+        assert!(positions[0].is_some());
+        assert!(positions[1].is_none());
+    }
+
+    #[tokio::test]
+    async fn search() {
+        let db = sicily().await;
+        let names = |matches: Vec<GeoMatch>| -> Vec<Bytes> {
+            matches.into_iter().map(|m| m.member).collect()
+        };
+
+        let mut query = GeoQuery {
+            from: GeoFrom::Coord(coord(15.0, 37.0)),
+            shape: GeoShape::Radius(200_000.0),
+            ascending: Some(true),
+            count: None,
+        };
+        let found = db.geosearch(&key("Sicily"), &query).await.unwrap();
+        assert_eq!(vec!["Catania", "Palermo"], names(found));
+
+        query.shape = GeoShape::Radius(300_000.0);
+        query.ascending = Some(false);
+        query.count = Some((2, false));
+        let found = db.geosearch(&key("Sicily"), &query).await.unwrap();
+        assert_eq!(vec!["edge1", "edge2"], names(found));
+
+        query.ascending = None;
+        let found = db.geosearch(&key("Sicily"), &query).await.unwrap();
+        assert_eq!(vec!["Catania", "Palermo"], names(found));
+
+        let query = GeoQuery {
+            from: GeoFrom::Member(Bytes::from("Palermo")),
+            shape: GeoShape::Box {
+                width: 400_000.0,
+                height: 400_000.0,
+            },
+            ascending: Some(true),
+            count: None,
+        };
+        let found = db.geosearch(&key("Sicily"), &query).await.unwrap();
+        assert_eq!(vec!["Palermo", "edge1", "Catania"], names(found));
+    }
+}
@@ -0,0 +1,525 @@
+use std::collections::{BTreeSet, HashMap};
+
+use bytes::Bytes;
+use tokio::time::Instant;
+
+use crate::{
+    error::CacheError,
+    storage::{
+        Db, Entry, ExpireCondition, State, Ttl, Value, entity::Entity, scan::glob_match,
+        table::Table,
+    },
+};
+
+/// `HEXPIRE` and `HPERSIST` replies for a field that does not exist.
+const NO_FIELD: i64 = -2;
+
+/// The deadlines of hash fields with a TTL, in order for the purge task and
+/// by key for lookups. Writing a field with `HSET` or removing its key drops
+/// its TTL, as in Redis.
+#[derive(Debug, Default)]
+pub(super) struct FieldExpirations {
+    queue: BTreeSet<(Instant, Entity, Bytes)>,
+    deadlines: HashMap<Entity, HashMap<Bytes, Instant>>,
+}
+
+impl FieldExpirations {
+    fn get(&self, key: &Entity, field: &Bytes) -> Option<Instant> {
+        self.deadlines.get(key)?.get(field).copied()
+    }
+
+    /// Returns whether `when` is now the earliest deadline, so the purge
+    /// task must be woken up.
+    fn set(&mut self, key: &Entity, field: &Bytes, when: Instant) -> bool {
+        self.clear(key, field);
+        let notify = self.next().is_none_or(|next| next > when);
+        self.queue.insert((when, key.clone(), field.clone()));
+        self.deadlines
+            .entry(key.clone())
+            .or_default()
+            .insert(field.clone(), when);
+        notify
+    }
+
+    /// Returns whether the field had a TTL.
+    fn clear(&mut self, key: &Entity, field: &Bytes) -> bool {
+        let Some(fields) = self.deadlines.get_mut(key) else {
+            return false;
+        };
+        let Some(when) = fields.remove(field) else {
+            return false;
+        };
+        if fields.is_empty() {
+            self.deadlines.remove(key);
+        }
+        self.queue.remove(&(when, key.clone(), field.clone()));
+        true
+    }
+
+    pub(super) fn clear_key(&mut self, key: &Entity) {
+        for (field, when) in self.deadlines.remove(key).into_iter().flatten() {
+            self.queue.remove(&(when, key.clone(), field));
+        }
+    }
+
+    pub(super) fn next(&self) -> Option<Instant> {
+        self.queue.first().map(|expiration| expiration.0)
+    }
+
+    /// The deadlines of every field of `key`, to carry them to another key.
+    pub(super) fn key_deadlines(&self, key: &Entity) -> HashMap<Bytes, Instant> {
+        self.deadlines.get(key).cloned().unwrap_or_default()
+    }
+
+    /// Gives the fields of `key` the `deadlines`. Returns whether the purge
+    /// task must be woken up.
+    pub(super) fn set_key(&mut self, key: &Entity, deadlines: HashMap<Bytes, Instant>) -> bool {
+        deadlines.into_iter().fold(false, |notify, (field, when)| {
+            self.set(key, &field, when) || notify
+        })
+    }
+}
+
+impl Db {
+    /// Sets every `(field, value)` pair and returns how many fields are new.
+    pub(crate) async fn hset(
+        &self,
+        key: Entity,
+        pairs: Vec<(Bytes, Bytes)>,
+    ) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let entry = state.entities.get_or_insert_with(key.clone(), || Entry {
+            data: Value::Hash(Table::new()),
+            expires_at: None,
+        });
+        let Value::Hash(hash) = &mut entry.data else {
+            return Err(CacheError::WrongType);
+        };
+        let mut added = 0;
+        let mut written = Vec::with_capacity(pairs.len());
+        for (field, value) in pairs {
+            if hash.insert(field.clone(), value).is_none() {
+                added += 1;
+            }
+            written.push(field);
+        }
+        for field in &written {
+            state.field_expirations.clear(&key, field);
+        }
+        state.reindex(&key);
+        Ok(added)
+    }
+
+    pub(crate) async fn hget(
+        &self,
+        key: &Entity,
+        field: &Bytes,
+    ) -> Result<Option<Bytes>, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state.hash(key)?.and_then(|hash| hash.get(field).cloned()))
+    }
+
+    pub(crate) async fn hmget(
+        &self,
+        key: &Entity,
+        fields: &[Bytes],
+    ) -> Result<Vec<Option<Bytes>>, CacheError> {
+        let state = self.shared.state.lock().await;
+        let hash = state.hash(key)?;
+        Ok(fields
+            .iter()
+            .map(|field| hash.and_then(|hash| hash.get(field).cloned()))
+            .collect())
+    }
+
+    pub(crate) async fn hdel(&self, key: &Entity, fields: &[Bytes]) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let Some(hash) = state.hash_mut(key)? else {
+            return Ok(0);
+        };
+        let removed = fields
+            .iter()
+            .filter(|field| hash.remove(*field).is_some())
+            .count();
+        for field in fields {
+            state.field_expirations.clear(key, field);
+        }
+        state.remove_if_empty(key);
+        state.reindex(key);
+        Ok(removed)
+    }
+
+    pub(crate) async fn hgetall(&self, key: &Entity) -> Result<Vec<(Bytes, Bytes)>, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state
+            .hash(key)?
+            .map(|hash| {
+                hash.iter()
+                    .map(|(field, value)| (field.clone(), value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    pub(crate) async fn hincrby(
+        &self,
+        key: Entity,
+        field: Bytes,
+        increment: i64,
+    ) -> Result<i64, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let entry = state.entities.get_or_insert_with(key.clone(), || Entry {
+            data: Value::Hash(Table::new()),
+            expires_at: None,
+        });
+        let Value::Hash(hash) = &mut entry.data else {
+            return Err(CacheError::WrongType);
+        };
+        let current = match hash.get(&field) {
+            Some(value) => str::from_utf8(value)
+                .ok()
+                .and_then(|value| value.parse::<i64>().ok())
+                .ok_or("ERR hash value is not an integer")?,
+            None => 0,
+        };
+        let value = current
+            .checked_add(increment)
+            .ok_or("ERR increment or decrement would overflow")?;
+        hash.insert(field.clone(), Bytes::from(value.to_string()));
+        state.field_expirations.clear(&key, &field);
+        state.reindex(&key);
+        Ok(value)
+    }
+
+    /// Sets the deadline of each of `fields` to `when`, if `condition`
+    /// allows. Replies per field as `HEXPIRE` does: `-2` for a missing field,
+    /// `0` when the condition fails, `1` when set, and `2` when the field was
+    /// deleted because `when` has already passed.
+    pub(crate) async fn hexpire(
+        &self,
+        key: &Entity,
+        fields: &[Bytes],
+        when: Instant,
+        condition: Option<ExpireCondition>,
+    ) -> Result<Vec<i64>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let now = Instant::now();
+        let mut replies = Vec::with_capacity(fields.len());
+        let mut notify = false;
+        for field in fields {
+            if !state
+                .hash(key)?
+                .is_some_and(|hash| hash.contains_key(field))
+            {
+                replies.push(NO_FIELD);
+                continue;
+            }
+            let current = state.field_expirations.get(key, field);
+            if condition.is_some_and(|condition| !condition.allows(current, when)) {
+                replies.push(0);
+            } else if when <= now {
+                if let Some(hash) = state.hash_mut(key)? {
+                    hash.remove(field);
+                }
+                state.field_expirations.clear(key, field);
+                replies.push(2);
+            } else {
+                notify |= state.field_expirations.set(key, field, when);
+                replies.push(1);
+            }
+        }
+        state.remove_if_empty(key);
+        state.reindex(key);
+
+        drop(state);
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+        Ok(replies)
+    }
+
+    pub(crate) async fn httl(
+        &self,
+        key: &Entity,
+        fields: &[Bytes],
+    ) -> Result<Vec<Ttl>, CacheError> {
+        let state = self.shared.state.lock().await;
+        let hash = state.hash(key)?;
+        Ok(fields
+            .iter()
+            .map(|field| {
+                if !hash.is_some_and(|hash| hash.contains_key(field)) {
+                    return Ttl::Missing;
+                }
+                match state.field_expirations.get(key, field) {
+                    Some(when) => Ttl::ExpiresAt(when),
+                    None => Ttl::Persistent,
+                }
+            })
+            .collect())
+    }
+
+    /// Drops the TTL of each of `fields`. Replies per field with `-2` for a
+    /// missing field, `-1` when it had no TTL and `1` when it was removed.
+    pub(crate) async fn hpersist(
+        &self,
+        key: &Entity,
+        fields: &[Bytes],
+    ) -> Result<Vec<i64>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let mut replies = Vec::with_capacity(fields.len());
+        for field in fields {
+            if !state
+                .hash(key)?
+                .is_some_and(|hash| hash.contains_key(field))
+            {
+                replies.push(NO_FIELD);
+            } else if state.field_expirations.clear(key, field) {
+                replies.push(1);
+            } else {
+                replies.push(-1);
+            }
+        }
+        Ok(replies)
+    }
+
+    pub(crate) async fn hscan(
+        &self,
+        key: &Entity,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+    ) -> Result<(u64, Vec<(Bytes, Bytes)>), CacheError> {
+        let state = self.shared.state.lock().await;
+        let Some(hash) = state.hash(key)? else {
+            return Ok((0, vec![]));
+        };
+        let (next, page) = hash.scan(cursor, count);
+        let page = page
+            .into_iter()
+            .filter(|(field, _)| pattern.is_none_or(|pattern| glob_match(pattern, field)))
+            .map(|(field, value)| (field.clone(), value.clone()))
+            .collect();
+        Ok((next, page))
+    }
+}
+
+impl State {
+    /// Deletes the hash fields whose TTL ran out by `now`, and the hashes
+    /// they leave empty.
+    pub(super) fn expire_fields(&mut self, now: Instant) {
+        while let Some((when, key, field)) = self.field_expirations.queue.first().cloned() {
+            if when > now {
+                break;
+            }
+            self.field_expirations.clear(&key, &field);
+            if let Some(Value::Hash(hash)) =
+                self.entities.get_mut(&key).map(|entry| &mut entry.data)
+            {
+                hash.remove(&field);
+            }
+            self.remove_if_empty(&key);
+            self.reindex(&key);
+        }
+    }
+
+    fn hash(&self, key: &Entity) -> Result<Option<&Table<Bytes, Bytes>>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn hash_mut(&mut self, key: &Entity) -> Result<Option<&mut Table<Bytes, Bytes>>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::storage::test_support::key;
+
+    fn bytes(value: &'static str) -> Bytes {
+        Bytes::from_static(value.as_bytes())
+    }
+
+    #[tokio::test]
+    async fn set_get_del() {
+        let db = Db::new();
+        let pairs = vec![
+            (bytes("name"), bytes("ann")),
+            (bytes("role"), bytes("admin")),
+        ];
+
+        assert_eq!(2, db.hset(key("user"), pairs).await.unwrap());
+        assert_eq!(
+            0,
+            db.hset(key("user"), vec![(bytes("name"), bytes("bob"))])
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            Some(bytes("bob")),
+            db.hget(&key("user"), &bytes("name")).await.unwrap()
+        );
+        assert_eq!(
+            vec![Some(bytes("admin")), None],
+            db.hmget(&key("user"), &[bytes("role"), bytes("age")])
+                .await
+                .unwrap()
+        );
+
+        assert_eq!(
+            2,
+            db.hdel(&key("user"), &[bytes("name"), bytes("role"), bytes("x")])
+                .await
+                .unwrap()
+        );
+        assert!(db.hgetall(&key("user")).await.unwrap().is_empty());
+        assert!(db.get(&key("user")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn incrby() {
+        let db = Db::new();
+
+        assert_eq!(5, db.hincrby(key("h"), bytes("n"), 5).await.unwrap());
+        assert_eq!(2, db.hincrby(key("h"), bytes("n"), -3).await.unwrap());
+        assert!(db.hincrby(key("h"), bytes("n"), i64::MAX).await.is_err());
+
+        db.hset(key("h"), vec![(bytes("s"), bytes("abc"))])
+            .await
+            .unwrap();
+        assert!(db.hincrby(key("h"), bytes("s"), 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn scan_with_match() {
+        let db = Db::new();
+        let pairs = (0..30)
+            .map(|i| (Bytes::from(format!("f{}", i)), Bytes::from(i.to_string())))
+            .collect();
+        db.hset(key("h"), pairs).await.unwrap();
+
+        let mut fields = vec![];
+        let mut cursor = 0;
+        loop {
+            let (next, page) = db.hscan(&key("h"), cursor, Some(b"f1*"), 4).await.unwrap();
+            fields.extend(page.into_iter().map(|(field, _)| field));
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        fields.sort();
+        assert_eq!(11, fields.len());
+    }
+
+    #[tokio::test]
+    async fn field_ttls() {
+        let db = Db::new();
+        let pairs = vec![
+            (bytes("a"), bytes("1")),
+            (bytes("b"), bytes("2")),
+            (bytes("c"), bytes("3")),
+        ];
+        db.hset(key("h"), pairs).await.unwrap();
+        let soon = Instant::now() + Duration::from_millis(30);
+        let later = Instant::now() + Duration::from_secs(60);
+
+        let fields = [bytes("a"), bytes("b"), bytes("x")];
+        assert_eq!(
+            vec![1, 1, -2],
+            db.hexpire(&key("h"), &fields, later, None).await.unwrap()
+        );
+        assert_eq!(
+            vec![1, 1],
+            db.hexpire(&key("h"), &fields[..2], soon, Some(ExpireCondition::Lt))
+                .await
+                .unwrap()
+        );
+        let fields_ac = [bytes("a"), bytes("c")];
+        assert_eq!(
+            vec![0, 1],
+            db.hexpire(&key("h"), &fields_ac, later, Some(ExpireCondition::Nx))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            vec![0],
+            db.hexpire(&key("h"), &[bytes("c")], soon, Some(ExpireCondition::Gt))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            vec![-2, -2],
+            db.hexpire(&key("missing"), &fields[..2], later, None)
+                .await
+                .unwrap()
+        );
+
+        // Rewriting a field drops its TTL.
+        db.hset(key("h"), vec![(bytes("b"), bytes("22"))])
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![Ttl::ExpiresAt(soon), Ttl::Persistent, Ttl::Missing],
+            db.httl(&key("h"), &fields).await.unwrap()
+        );
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(None, db.hget(&key("h"), &bytes("a")).await.unwrap());
+        assert_eq!(2, db.hgetall(&key("h")).await.unwrap().len());
+
+        assert_eq!(
+            vec![1, -1, -2],
+            db.hpersist(&key("h"), &[bytes("c"), bytes("b"), bytes("a")])
+                .await
+                .unwrap()
+        );
+        // A deadline in the past deletes the field, and the last one the key.
+        let past = Instant::now();
+        let fields = [bytes("b"), bytes("c")];
+        assert_eq!(
+            vec![2, 2],
+            db.hexpire(&key("h"), &fields, past, None).await.unwrap()
+        );
+        assert!(db.shared.state.lock().await.entities.is_empty());
+    }
+
+    #[tokio::test]
+    async fn field_ttls_dropped_with_writes_and_key() {
+        let db = Db::new();
+        db.hset(key("h"), vec![(bytes("n"), bytes("1"))])
+            .await
+            .unwrap();
+        let later = Instant::now() + Duration::from_secs(60);
+        db.hexpire(&key("h"), &[bytes("n")], later, None)
+            .await
+            .unwrap();
+        assert_eq!(2, db.hincrby(key("h"), bytes("n"), 1).await.unwrap());
+        assert_eq!(
+            vec![Ttl::Persistent],
+            db.httl(&key("h"), &[bytes("n")]).await.unwrap()
+        );
+
+        // An expired hash takes its field deadlines with it.
+        db.hexpire(&key("h"), &[bytes("n")], later, None)
+            .await
+            .unwrap();
+        db.expire(&key("h"), Instant::now() + Duration::from_millis(20), None)
+            .await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let state = db.shared.state.lock().await;
+        assert!(state.field_expirations.queue.is_empty());
+        assert!(state.field_expirations.deadlines.is_empty());
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    collections::BTreeMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, Entry, State, Value, entity::Entity},
+};
+
+/// Index bits: `2^14` registers give a standard error of `1.04 / sqrt(m)`,
+/// about 0.81%.
+const P: u32 = 14;
+const REGISTERS: usize = 1 << P;
+/// Bits of the hash left for the run of zeros once the index is taken.
+const Q: u32 = 64 - P;
+/// A sparse sketch holding more registers than this switches to dense.
+const SPARSE_MAX: usize = 2048;
+
+/// A HyperLogLog sketch. Small sketches store only their non-zero registers;
+/// past [`SPARSE_MAX`] of them a flat array of all registers is cheaper.
+#[derive(Debug, Clone)]
+pub(crate) enum HyperLogLog {
+    Sparse(BTreeMap<u16, u8>),
+    Dense(Box<[u8]>),
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog::Sparse(BTreeMap::new())
+    }
+}
+
+impl HyperLogLog {
+    /// Returns whether the sketch changed.
+    fn add(&mut self, element: &[u8]) -> bool {
+        let mut hasher = DefaultHasher::new();
+        element.hash(&mut hasher);
+        let hash = hasher.finish();
+        let index = (hash & (REGISTERS as u64 - 1)) as u16;
+        // Position of the first set bit in the remaining bits, counting from 1;
+        // the sentinel bit bounds it by `Q + 1`.
+        let rank = ((hash >> P) | (1 << Q)).trailing_zeros() as u8 + 1;
+        self.raise(index, rank)
+    }
+
+    /// Raises register `index` to `rank` if it is lower.
+    fn raise(&mut self, index: u16, rank: u8) -> bool {
+        match self {
+            HyperLogLog::Sparse(registers) => {
+                let register = registers.entry(index).or_default();
+                if *register >= rank {
+                    return false;
+                }
+                *register = rank;
+                if registers.len() > SPARSE_MAX {
+                    let mut dense = vec![0; REGISTERS].into_boxed_slice();
+                    for (index, rank) in registers.iter() {
+                        dense[*index as usize] = *rank;
+                    }
+                    *self = HyperLogLog::Dense(dense);
+                }
+                true
+            }
+            HyperLogLog::Dense(registers) => {
+                let register = &mut registers[index as usize];
+                if *register >= rank {
+                    return false;
+                }
+                *register = rank;
+                true
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &HyperLogLog) {
+        match other {
+            HyperLogLog::Sparse(registers) => {
+                for (index, rank) in registers {
+                    self.raise(*index, *rank);
+                }
+            }
+            HyperLogLog::Dense(registers) => {
+                for (index, rank) in registers.iter().enumerate() {
+                    if *rank > 0 {
+                        self.raise(index as u16, *rank);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Estimates the cardinality with Ertl's improved estimator, which stays
+    /// unbiased from empty sketches up, without range-specific corrections.
+    fn count(&self) -> u64 {
+        // histogram[k] counts the registers holding rank k.
+        let mut histogram = [0u32; Q as usize + 2];
+        let non_zero = match self {
+            HyperLogLog::Sparse(registers) => {
+                for rank in registers.values() {
+                    histogram[*rank as usize] += 1;
+                }
+                registers.len()
+            }
+            HyperLogLog::Dense(registers) => {
+                for rank in registers.iter() {
+                    histogram[*rank as usize] += 1;
+                }
+                REGISTERS - histogram[0] as usize
+            }
+        };
+        histogram[0] = (REGISTERS - non_zero) as u32;
+
+        let m = REGISTERS as f64;
+        let mut z = m * tau((m - histogram[Q as usize + 1] as f64) / m);
+        for k in (1..=Q as usize).rev() {
+            z += histogram[k] as f64;
+            z *= 0.5;
+        }
+        z += m * sigma(histogram[0] as f64 / m);
+        let alpha = 0.5 / std::f64::consts::LN_2;
+        (alpha * m * m / z).round() as u64
+    }
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if z == previous {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if z == previous {
+            return z / 3.0;
+        }
+    }
+}
+
+impl Db {
+    /// Adds `elements` to the sketch at `key`, creating it if needed. Returns
+    /// whether the estimate may have changed.
+    pub(crate) async fn pfadd(&self, key: Entity, elements: &[Bytes]) -> Result<bool, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let mut changed = !state.entities.contains_key(&key);
+        let entry = state.entities.get_or_insert_with(key, || Entry {
+            data: Value::HyperLogLog(HyperLogLog::default()),
+            expires_at: None,
+        });
+        let Value::HyperLogLog(hll) = &mut entry.data else {
+            return Err(CacheError::WrongType);
+        };
+        for element in elements {
+            changed |= hll.add(element);
+        }
+        Ok(changed)
+    }
+
+    /// Estimates the cardinality of the union of the sketches at `keys`.
+    pub(crate) async fn pfcount(&self, keys: &[Entity]) -> Result<u64, CacheError> {
+        let state = self.shared.state.lock().await;
+        if let [key] = keys {
+            return Ok(state.hyperloglog(key)?.map(HyperLogLog::count).unwrap_or(0));
+        }
+        Ok(state.union(keys)?.count())
+    }
+
+    /// Stores the union of `destination` and the sketches at `keys` in
+    /// `destination`.
+    pub(crate) async fn pfmerge(
+        &self,
+        destination: Entity,
+        keys: &[Entity],
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let merged = state.union(keys)?;
+        match state
+            .entities
+            .get_mut(&destination)
+            .map(|entry| &mut entry.data)
+        {
+            Some(Value::HyperLogLog(hll)) => hll.merge(&merged),
+            Some(_) => return Err(CacheError::WrongType),
+            None => {
+                state.insert(destination, Value::HyperLogLog(merged), None);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl State {
+    fn hyperloglog(&self, key: &Entity) -> Result<Option<&HyperLogLog>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::HyperLogLog(hll)) => Ok(Some(hll)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn union(&self, keys: &[Entity]) -> Result<HyperLogLog, CacheError> {
+        let mut union = HyperLogLog::default();
+        for key in keys {
+            if let Some(hll) = self.hyperloglog(key)? {
+                union.merge(hll);
+            }
+        }
+        Ok(union)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::key;
+
+    fn elements(range: std::ops::Range<u32>) -> Vec<Bytes> {
+        range.map(|i| Bytes::from(format!("e{}", i))).collect()
+    }
+
+    fn assert_close(expected: u64, estimate: u64) {
+        let error = (estimate as f64 - expected as f64).abs() / expected as f64;
+        assert!(error < 0.03, "estimate {} for {}", estimate, expected);
+    }
+
+    #[test]
+    fn sparse_then_dense() {
+        let mut hll = HyperLogLog::default();
+        assert_eq!(0, hll.count());
+        for i in 0..100 {
+            hll.add(format!("e{}", i).as_bytes());
+        }
+        assert!(matches!(hll, HyperLogLog::Sparse(_)));
+        assert_close(100, hll.count());
+        assert!(!hll.add(b"e1"));
+
+        for i in 100..50_000 {
+            hll.add(format!("e{}", i).as_bytes());
+        }
+        assert!(matches!(hll, HyperLogLog::Dense(_)));
+        assert_close(50_000, hll.count());
+    }
+
+    #[tokio::test]
+    async fn add_count_merge() {
+        let db = Db::new();
+
+        assert!(db.pfadd(key("a"), &elements(0..3000)).await.unwrap());
+        assert!(!db.pfadd(key("a"), &elements(0..10)).await.unwrap());
+        assert!(db.pfadd(key("b"), &elements(2000..6000)).await.unwrap());
+        assert_close(3000, db.pfcount(&[key("a")]).await.unwrap());
+        assert_close(
+            6000,
+            db.pfcount(&[key("a"), key("b"), key("missing")])
+                .await
+                .unwrap(),
+        );
+
+        db.pfmerge(key("a"), &[key("b")]).await.unwrap();
+        assert_close(6000, db.pfcount(&[key("a")]).await.unwrap());
+        db.pfmerge(key("empty"), &[]).await.unwrap();
+        assert_eq!(0, db.pfcount(&[key("empty")]).await.unwrap());
+
+        db.push(key("l"), elements(0..1), crate::storage::list::End::Left)
+            .await
+            .unwrap();
+        assert!(matches!(
+            db.pfadd(key("l"), &[]).await,
+            Err(CacheError::WrongType)
+        ));
+    }
+}
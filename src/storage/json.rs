@@ -0,0 +1,333 @@
+use serde_json::{Number, Value as Json};
+
+use crate::{
+    error::CacheError,
+    storage::{
+        Db, State, Value,
+        entity::Entity,
+        json::path::{JsonPath, Location, node_mut},
+    },
+};
+
+pub(crate) mod path;
+
+const MISSING_KEY: &str = "ERR could not perform this operation on a key that doesn't exist";
+
+/// `JSON.SET` conditions: `NX` only creates, `XX` only replaces.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct JsonSetFlags {
+    pub(crate) nx: bool,
+    pub(crate) xx: bool,
+}
+
+impl Db {
+    /// Sets the value at `path`, creating the key when `path` is the root.
+    /// A missing object member is created if its parent exists. Returns
+    /// whether anything was written.
+    pub(crate) async fn json_set(
+        &self,
+        key: Entity,
+        path: &JsonPath,
+        value: Json,
+        flags: JsonSetFlags,
+    ) -> Result<bool, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let Some(doc) = state.json_mut(&key)? else {
+            if !path.is_root() {
+                return Err("ERR new objects must be created at the root".into());
+            }
+            if flags.xx {
+                return Ok(false);
+            }
+            state.insert(key, Value::Json(value), None);
+            return Ok(true);
+        };
+
+        let matches = path.select(doc);
+        if !matches.is_empty() {
+            if flags.nx {
+                return Ok(false);
+            }
+            for location in &matches {
+                if let Some(node) = node_mut(doc, location) {
+                    *node = value.clone();
+                }
+            }
+            return Ok(true);
+        }
+        if flags.xx {
+            return Ok(false);
+        }
+        let Some((parent, name)) = path.parent() else {
+            return Ok(false);
+        };
+        let mut created = false;
+        for location in parent.select(doc) {
+            if let Some(Json::Object(obj)) = node_mut(doc, &location) {
+                obj.insert(name.to_string(), value.clone());
+                created = true;
+            }
+        }
+        Ok(created)
+    }
+
+    /// The values each of `paths` matches, or `None` for a missing key.
+    pub(crate) async fn json_get(
+        &self,
+        key: &Entity,
+        paths: &[JsonPath],
+    ) -> Result<Option<Vec<Vec<Json>>>, CacheError> {
+        let state = self.shared.state.lock().await;
+        let Some(doc) = state.json(key)? else {
+            return Ok(None);
+        };
+        let values = paths
+            .iter()
+            .map(|path| {
+                path.select(doc)
+                    .iter()
+                    .filter_map(|location| lookup(doc, location).cloned())
+                    .collect()
+            })
+            .collect();
+        Ok(Some(values))
+    }
+
+    /// Deletes every value `path` matches, and the key itself for the root.
+    /// Returns how many values went away.
+    pub(crate) async fn json_del(
+        &self,
+        key: &Entity,
+        path: &JsonPath,
+    ) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let Some(doc) = state.json_mut(key)? else {
+            return Ok(0);
+        };
+        if path.is_root() {
+            state.remove(key);
+            return Ok(1);
+        }
+
+        let mut matches = path.select(doc);
+        // Values inside another match go away with it. Deleting the rest in
+        // reverse order removes later array elements before earlier ones, so
+        // the remaining indexes stay valid.
+        matches.sort();
+        matches.dedup();
+        let mut deleted: Vec<Vec<Location>> = vec![];
+        for location in matches {
+            if !deleted.iter().any(|parent| location.starts_with(parent)) {
+                deleted.push(location);
+            }
+        }
+        for location in deleted.iter().rev() {
+            let (last, parent) = location.split_last().expect("root handled above");
+            match (node_mut(doc, parent), last) {
+                (Some(Json::Object(obj)), Location::Key(key)) => {
+                    obj.shift_remove(key);
+                }
+                (Some(Json::Array(array)), Location::Index(index)) => {
+                    array.remove(*index);
+                }
+                _ => unreachable!("locations come from the document"),
+            }
+        }
+        Ok(deleted.len())
+    }
+
+    /// Adds `by` to every number `path` matches. Returns the new value for
+    /// each match, or `None` where the match is not a number.
+    pub(crate) async fn json_numincrby(
+        &self,
+        key: &Entity,
+        path: &JsonPath,
+        by: &Number,
+    ) -> Result<Vec<Option<Number>>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let doc = state.json_mut(key)?.ok_or(MISSING_KEY)?;
+        let matches = path.select(doc);
+
+        // Compute every result first so an overflow leaves the document as is.
+        let results = matches
+            .iter()
+            .map(|location| match lookup(doc, location) {
+                Some(Json::Number(n)) => add(n, by).map(Some),
+                _ => Ok(None),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for (location, result) in matches.iter().zip(&results) {
+            if let (Some(node), Some(n)) = (node_mut(doc, location), result) {
+                *node = Json::Number(n.clone());
+            }
+        }
+        Ok(results)
+    }
+
+    /// Appends `values` to every array `path` matches. Returns the new length
+    /// of each match, or `None` where the match is not an array.
+    pub(crate) async fn json_arrappend(
+        &self,
+        key: &Entity,
+        path: &JsonPath,
+        values: &[Json],
+    ) -> Result<Vec<Option<usize>>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let doc = state.json_mut(key)?.ok_or(MISSING_KEY)?;
+        Ok(path
+            .select(doc)
+            .iter()
+            .map(|location| match node_mut(doc, location) {
+                Some(Json::Array(array)) => {
+                    array.extend(values.iter().cloned());
+                    Some(array.len())
+                }
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+impl State {
+    fn json(&self, key: &Entity) -> Result<Option<&Json>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::Json(doc)) => Ok(Some(doc)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn json_mut(&mut self, key: &Entity) -> Result<Option<&mut Json>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::Json(doc)) => Ok(Some(doc)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+}
+
+fn lookup<'a>(doc: &'a Json, location: &[Location]) -> Option<&'a Json> {
+    location.iter().try_fold(doc, |node, step| match step {
+        Location::Key(key) => node.get(key),
+        Location::Index(index) => node.get(*index),
+    })
+}
+
+/// Integers stay integers unless the sum overflows; anything else is
+/// computed as a float.
+fn add(n: &Number, by: &Number) -> Result<Number, CacheError> {
+    if let (Some(a), Some(b)) = (n.as_i64(), by.as_i64())
+        && let Some(sum) = a.checked_add(b)
+    {
+        return Ok(Number::from(sum));
+    }
+    let sum = n.as_f64().unwrap_or(f64::NAN) + by.as_f64().unwrap_or(f64::NAN);
+    Number::from_f64(sum).ok_or_else(|| "ERR result is not a finite number".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::key;
+    use serde_json::json;
+
+    fn path(raw: &str) -> JsonPath {
+        JsonPath::parse(raw).unwrap()
+    }
+
+    async fn get(db: &Db, name: &'static str) -> Json {
+        let values = db.json_get(&key(name), &[JsonPath::root()]).await.unwrap();
+        values.unwrap().remove(0).remove(0)
+    }
+
+    #[tokio::test]
+    async fn set_and_get() {
+        let db = Db::new();
+        let flags = JsonSetFlags::default();
+
+        assert!(
+            db.json_set(key("missing"), &path("$.a"), json!(1), flags)
+                .await
+                .is_err()
+        );
+        let doc = json!({"a": {"b": 1}, "list": [1, 2]});
+        assert!(
+            db.json_set(key("doc"), &path("$"), doc, flags)
+                .await
+                .unwrap()
+        );
+        assert!(
+            db.json_set(key("doc"), &path("$.a.c"), json!("new"), flags)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !db.json_set(key("doc"), &path("$.x.y"), json!(0), flags)
+                .await
+                .unwrap()
+        );
+        let nx = JsonSetFlags {
+            nx: true,
+            xx: false,
+        };
+        assert!(
+            !db.json_set(key("doc"), &path(".a.b"), json!(2), nx)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            json!({"a": {"b": 1, "c": "new"}, "list": [1, 2]}),
+            get(&db, "doc").await
+        );
+
+        let values = db
+            .json_get(&key("doc"), &[path("$..b"), path("$.nope")])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![vec![json!(1)], vec![]], values);
+        assert!(db.json_get(&key("none"), &[]).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn update_in_place() {
+        let db = Db::new();
+        let doc = json!({"n": 1, "f": 1.5, "s": "x", "list": [1, 2, 3], "max": i64::MAX});
+        db.json_set(key("doc"), &JsonPath::root(), doc, JsonSetFlags::default())
+            .await
+            .unwrap();
+
+        let results = db
+            .json_numincrby(&key("doc"), &path("$.*"), &Number::from(2))
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![
+                Some(Number::from(3)),
+                Some(Number::from_f64(3.5).unwrap()),
+                None,
+                None,
+                Some(Number::from_f64(i64::MAX as f64 + 2.0).unwrap()),
+            ],
+            results
+        );
+
+        let lengths = db
+            .json_arrappend(&key("doc"), &path("$..list"), &[json!(4), json!(null)])
+            .await
+            .unwrap();
+        assert_eq!(vec![Some(5)], lengths);
+
+        assert_eq!(
+            5,
+            db.json_del(&key("doc"), &path("$.list[*]")).await.unwrap()
+        );
+        assert_eq!(json!([]), get(&db, "doc").await["list"]);
+        assert_eq!(1, db.json_del(&key("doc"), &path("$")).await.unwrap());
+        assert!(
+            db.json_arrappend(&key("doc"), &path("$"), &[])
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use serde_json::Value as Json;
+
+use crate::error::CacheError;
+
+const INVALID: &str = "ERR invalid JSON path";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// `.name`, `['name']` or `["name"]`.
+    Key(String),
+    /// `[n]`; negative indexes count from the end of the array.
+    Index(i64),
+    /// `.*` or `[*]`: every member of an object or element of an array.
+    Wildcard,
+    /// `..`: the current nodes and all of their descendants, which the next
+    /// step then selects from.
+    Descend,
+}
+
+/// One step from a node to a child, as resolved against a document.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Location {
+    Key(String),
+    Index(usize),
+}
+
+/// A path into a JSON document. Paths starting with `$` are JSONPath and
+/// address every match; anything else is a legacy path such as `.a.b[0]`,
+/// which addresses a single value.
+#[derive(Debug, Clone)]
+pub(crate) struct JsonPath {
+    raw: String,
+    steps: Vec<Step>,
+    legacy: bool,
+}
+
+impl JsonPath {
+    pub(crate) fn root() -> JsonPath {
+        JsonPath {
+            raw: "$".to_string(),
+            steps: vec![],
+            legacy: false,
+        }
+    }
+
+    pub(crate) fn parse(raw: &str) -> Result<JsonPath, CacheError> {
+        let (legacy, rest) = match raw.strip_prefix('$') {
+            Some(rest) => (false, rest.to_string()),
+            None if raw == "." => (true, String::new()),
+            None if raw.starts_with(['.', '[']) => (true, raw.to_string()),
+            None => (true, format!(".{}", raw)),
+        };
+        Ok(JsonPath {
+            raw: raw.to_string(),
+            steps: parse_steps(&rest)?,
+            legacy,
+        })
+    }
+
+    pub(crate) fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    pub(crate) fn is_legacy(&self) -> bool {
+        self.legacy
+    }
+
+    pub(crate) fn is_root(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// The locations of every node the path matches in `doc`.
+    pub(crate) fn select(&self, doc: &Json) -> Vec<Vec<Location>> {
+        select(doc, &self.steps)
+    }
+
+    /// The path without its last step, and that step's key when it names an
+    /// object member, so `JSON.SET` can create the member under its parent.
+    pub(crate) fn parent(&self) -> Option<(JsonPath, &str)> {
+        let (Step::Key(name), steps) = self.steps.split_last()? else {
+            return None;
+        };
+        let parent = JsonPath {
+            raw: self.raw.clone(),
+            steps: steps.to_vec(),
+            legacy: self.legacy,
+        };
+        Some((parent, name))
+    }
+}
+
+fn parse_steps(mut rest: &str) -> Result<Vec<Step>, CacheError> {
+    let mut steps = vec![];
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("..") {
+            steps.push(Step::Descend);
+            rest = after;
+            if rest.starts_with('[') {
+                continue;
+            }
+            rest = parse_name(rest, &mut steps)?;
+        } else if let Some(after) = rest.strip_prefix('.') {
+            rest = parse_name(after, &mut steps)?;
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or(INVALID)?;
+            steps.push(parse_bracket(&after[..end])?);
+            rest = &after[end + 1..];
+        } else {
+            return Err(INVALID.into());
+        }
+    }
+    Ok(steps)
+}
+
+/// Parses a dotted member name up to the next `.` or `[`.
+fn parse_name<'a>(rest: &'a str, steps: &mut Vec<Step>) -> Result<&'a str, CacheError> {
+    let end = rest.find(['.', '[']).unwrap_or(rest.len());
+    let name = &rest[..end];
+    match name {
+        "" => return Err(INVALID.into()),
+        "*" => steps.push(Step::Wildcard),
+        name if name.contains(']') => return Err(INVALID.into()),
+        name => steps.push(Step::Key(name.to_string())),
+    }
+    Ok(&rest[end..])
+}
+
+fn parse_bracket(inner: &str) -> Result<Step, CacheError> {
+    let inner = inner.trim();
+    if inner == "*" {
+        return Ok(Step::Wildcard);
+    }
+    for quote in ['\'', '"'] {
+        if let Some(name) = inner
+            .strip_prefix(quote)
+            .and_then(|inner| inner.strip_suffix(quote))
+        {
+            return Ok(Step::Key(name.to_string()));
+        }
+    }
+    inner.parse().map(Step::Index).map_err(|_| INVALID.into())
+}
+
+fn select(doc: &Json, steps: &[Step]) -> Vec<Vec<Location>> {
+    let mut current = vec![(vec![], doc)];
+    for step in steps {
+        let mut next = vec![];
+        for (location, node) in current {
+            match step {
+                Step::Key(name) => {
+                    if let Some(child) = node.as_object().and_then(|obj| obj.get(name)) {
+                        next.push((child_of(&location, Location::Key(name.clone())), child));
+                    }
+                }
+                Step::Index(index) => {
+                    let Some(array) = node.as_array() else {
+                        continue;
+                    };
+                    let len = array.len() as i64;
+                    let index = if *index < 0 { len + index } else { *index };
+                    if (0..len).contains(&index) {
+                        let index = index as usize;
+                        next.push((child_of(&location, Location::Index(index)), &array[index]));
+                    }
+                }
+                Step::Wildcard => next.extend(children(&location, node)),
+                Step::Descend => descendants(location, node, &mut next),
+            }
+        }
+        current = next;
+    }
+    current.into_iter().map(|(location, _)| location).collect()
+}
+
+fn child_of(parent: &[Location], step: Location) -> Vec<Location> {
+    let mut location = parent.to_vec();
+    location.push(step);
+    location
+}
+
+fn children<'a>(location: &[Location], node: &'a Json) -> Vec<(Vec<Location>, &'a Json)> {
+    match node {
+        Json::Object(obj) => obj
+            .iter()
+            .map(|(key, child)| (child_of(location, Location::Key(key.clone())), child))
+            .collect(),
+        Json::Array(array) => array
+            .iter()
+            .enumerate()
+            .map(|(index, child)| (child_of(location, Location::Index(index)), child))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Collects `node` and everything below it, parents before children.
+fn descendants<'a>(
+    location: Vec<Location>,
+    node: &'a Json,
+    out: &mut Vec<(Vec<Location>, &'a Json)>,
+) {
+    let below = children(&location, node);
+    out.push((location, node));
+    for (location, child) in below {
+        descendants(location, child, out);
+    }
+}
+
+/// Follows `location` down from `doc`.
+pub(crate) fn node_mut<'a>(doc: &'a mut Json, location: &[Location]) -> Option<&'a mut Json> {
+    location.iter().try_fold(doc, |node, step| match step {
+        Location::Key(key) => node.as_object_mut()?.get_mut(key),
+        Location::Index(index) => node.as_array_mut()?.get_mut(*index),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn select(path: &str, doc: &Json) -> Vec<Json> {
+        let locations = JsonPath::parse(path).unwrap().select(doc);
+        let mut doc = doc.clone();
+        locations
+            .iter()
+            .map(|location| node_mut(&mut doc, location).unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn parse_paths() {
+        assert!(JsonPath::parse("$").unwrap().is_root());
+        assert!(JsonPath::parse(".").unwrap().is_root());
+        assert!(JsonPath::parse(".").unwrap().is_legacy());
+        assert_eq!(
+            JsonPath::parse("a.b[0]").unwrap().steps,
+            JsonPath::parse("$.a['b'][0]").unwrap().steps
+        );
+        assert_eq!(
+            vec![Step::Descend, Step::Key("x".into()), Step::Wildcard],
+            JsonPath::parse("$..x[*]").unwrap().steps
+        );
+        for invalid in ["$.", "$[", "$[x]", "$a", "$.a..", "a]"] {
+            assert!(JsonPath::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn select_matches() {
+        let doc = json!({"a": {"x": 1, "b": [{"x": 2}, {"y": 3}]}, "x": 4});
+
+        assert_eq!(vec![json!(1)], select("$.a.x", &doc));
+        assert_eq!(vec![json!({"y": 3})], select("$.a.b[-1]", &doc));
+        assert_eq!(vec![json!(4), json!(1), json!(2)], select("$..x", &doc));
+        assert_eq!(vec![json!(2)], select("$.a.b[*].x", &doc));
+        assert_eq!(vec![json!({"x": 2})], select("$..[0]", &doc));
+        assert!(select("$.a.b[5]", &doc).is_empty());
+        assert!(select("$.x.y", &doc).is_empty());
+    }
+}
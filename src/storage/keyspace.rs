@@ -0,0 +1,354 @@
+use std::{collections::HashMap, mem};
+
+use bytes::Bytes;
+use tokio::time::Instant;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, Entry, ExpireCondition, State, Ttl, entity::Entity, scan::glob_match},
+};
+
+impl Db {
+    /// Moves the deadline of `key` to `when` if the key exists and
+    /// `condition` allows. A deadline that has passed deletes the key.
+    /// Returns whether anything changed.
+    pub(crate) async fn expire(
+        &self,
+        key: &Entity,
+        when: Instant,
+        condition: Option<ExpireCondition>,
+    ) -> bool {
+        let mut state = self.shared.state.lock().await;
+        let Some(entry) = state.entities.get(key) else {
+            return false;
+        };
+        if condition.is_some_and(|condition| !condition.allows(entry.expires_at, when)) {
+            return false;
+        }
+        let notify = state.set_expiration(key, Some(when));
+
+        drop(state);
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+        true
+    }
+
+    pub(crate) async fn ttl(&self, key: &Entity) -> Ttl {
+        let state = self.shared.state.lock().await;
+        match state.entities.get(key) {
+            None => Ttl::Missing,
+            Some(entry) => entry.expires_at.map_or(Ttl::Persistent, Ttl::ExpiresAt),
+        }
+    }
+
+    /// Returns a page of keys from `cursor` on, and the cursor to resume
+    /// from. `pattern` and `type_name` filter the page after it is taken, so
+    /// a page may come back short or even empty before the scan is over.
+    pub(crate) async fn scan(
+        &self,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        type_name: Option<&str>,
+        count: usize,
+    ) -> (u64, Vec<Entity>) {
+        let state = self.shared.state.lock().await;
+        let (next, page) = state.entities.scan(cursor, count);
+        let page = page
+            .into_iter()
+            .filter(|(key, entry)| {
+                pattern.is_none_or(|pattern| key_matches(pattern, key))
+                    && type_name
+                        .is_none_or(|name| name.eq_ignore_ascii_case(entry.data.type_name()))
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        (next, page)
+    }
+
+    /// Every key matching `pattern`, in no particular order.
+    pub(crate) async fn keys(&self, pattern: &[u8]) -> Vec<Entity> {
+        let state = self.shared.state.lock().await;
+        state
+            .entities
+            .keys()
+            .filter(|key| key_matches(pattern, key))
+            .cloned()
+            .collect()
+    }
+
+    /// Drops the deadline of `key`. Returns whether it had one.
+    pub(crate) async fn persist(&self, key: &Entity) -> bool {
+        let mut state = self.shared.state.lock().await;
+        if state
+            .entities
+            .get(key)
+            .is_none_or(|entry| entry.expires_at.is_none())
+        {
+            return false;
+        }
+        state.set_expiration(key, None);
+        true
+    }
+
+    /// Moves the value at `from` to `to` along with its TTL and those of its
+    /// fields, replacing whatever `to` held unless `nx`. Returns whether it
+    /// was moved.
+    pub(crate) async fn rename(
+        &self,
+        from: &Entity,
+        to: &Entity,
+        nx: bool,
+    ) -> Result<bool, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if !state.entities.contains_key(from) {
+            return Err("ERR no such key".into());
+        }
+        if nx && state.entities.contains_key(to) {
+            return Ok(false);
+        }
+        if from == to {
+            return Ok(true);
+        }
+        let fields = state.field_expirations.key_deadlines(from);
+        let entry = state.remove(from).expect("the key exists");
+        let notify = state.place(to, entry, fields);
+
+        drop(state);
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+        Ok(true)
+    }
+
+    /// Copies the value at `from` to `to` along with its TTL and those of
+    /// its fields, replacing whatever `to` held only with `replace`. Returns
+    /// whether it was copied.
+    pub(crate) async fn copy(
+        &self,
+        from: &Entity,
+        to: &Entity,
+        replace: bool,
+    ) -> Result<bool, CacheError> {
+        if from == to {
+            return Err("ERR source and destination objects are the same".into());
+        }
+        let mut state = self.shared.state.lock().await;
+        let Some(entry) = state.entities.get(from).cloned() else {
+            return Ok(false);
+        };
+        if !replace && state.entities.contains_key(to) {
+            return Ok(false);
+        }
+        let fields = state.field_expirations.key_deadlines(from);
+        let notify = state.place(to, entry, fields);
+
+        drop(state);
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+        Ok(true)
+    }
+
+    /// The type of the value at `key`, as `TYPE` names it.
+    pub(crate) async fn type_of(&self, key: &Entity) -> Option<&'static str> {
+        let state = self.shared.state.lock().await;
+        state.entities.get(key).map(|entry| entry.data.type_name())
+    }
+
+    /// A key picked at random, from a random bucket of the key table.
+    pub(crate) async fn random_key(&self) -> Option<Entity> {
+        let state = self.shared.state.lock().await;
+        state.entities.random().map(|(key, _)| key.clone())
+    }
+
+    pub(crate) async fn dbsize(&self) -> usize {
+        let state = self.shared.state.lock().await;
+        state.entities.len()
+    }
+
+    /// Deletes every key, and the search indexes over them. With `lazy` the
+    /// values are freed on a blocking thread once the lock is released,
+    /// instead of while other connections wait for it.
+    pub(crate) async fn flush(&self, lazy: bool) {
+        let mut state = self.shared.state.lock().await;
+        let garbage = (
+            mem::take(&mut state.entities),
+            mem::take(&mut state.expirations),
+            mem::take(&mut state.indexes),
+            mem::take(&mut state.field_expirations),
+        );
+        if lazy {
+            drop(state);
+            tokio::task::spawn_blocking(move || drop(garbage));
+        } else {
+            drop(garbage);
+        }
+    }
+}
+
+impl State {
+    /// Stores `entry` at `key` for `RENAME`, `COPY` and `MOVE`, with
+    /// `fields` as the deadlines of its hash fields, and serves clients
+    /// blocked on the key. Returns whether the purge task must be woken up.
+    pub(super) fn place(
+        &mut self,
+        key: &Entity,
+        entry: Entry,
+        fields: HashMap<Bytes, Instant>,
+    ) -> bool {
+        let notify = self.insert(key.clone(), entry.data, entry.expires_at);
+        let notify = self.field_expirations.set_key(key, fields) || notify;
+        self.serve_blocked(key);
+        notify
+    }
+}
+
+fn key_matches(pattern: &[u8], key: &Entity) -> bool {
+    match key {
+        Entity::Bulk(key) => glob_match(pattern, key),
+        Entity::Simple(key) => glob_match(pattern, key.as_bytes()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::storage::{string::SetOptions, test_support::key};
+
+    #[tokio::test]
+    async fn expire_ttl_persist() {
+        let db = Db::new();
+        let value = Entity::Bulk(Bytes::from_static(b"v"));
+        let soon = Instant::now() + Duration::from_secs(10);
+        let later = soon + Duration::from_secs(10);
+
+        assert!(!db.expire(&key("k"), soon, None).await);
+        assert_eq!(Ttl::Missing, db.ttl(&key("k")).await);
+        db.set(key("k"), value.clone(), SetOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(Ttl::Persistent, db.ttl(&key("k")).await);
+
+        // A missing TTL is infinite: GT never applies to it, LT always does.
+        assert!(!db.expire(&key("k"), soon, Some(ExpireCondition::Gt)).await);
+        assert!(!db.expire(&key("k"), soon, Some(ExpireCondition::Xx)).await);
+        assert!(db.expire(&key("k"), later, Some(ExpireCondition::Lt)).await);
+        assert!(!db.expire(&key("k"), soon, Some(ExpireCondition::Nx)).await);
+        assert!(!db.expire(&key("k"), soon, Some(ExpireCondition::Gt)).await);
+        assert!(db.expire(&key("k"), soon, Some(ExpireCondition::Lt)).await);
+        assert_eq!(Ttl::ExpiresAt(soon), db.ttl(&key("k")).await);
+
+        assert!(db.persist(&key("k")).await);
+        assert!(!db.persist(&key("k")).await);
+        assert_eq!(Ttl::Persistent, db.ttl(&key("k")).await);
+
+        assert!(db.expire(&key("k"), Instant::now(), None).await);
+        assert_eq!(Ttl::Missing, db.ttl(&key("k")).await);
+    }
+
+    #[tokio::test]
+    async fn scan_and_keys() {
+        let db = Db::new();
+        for i in 0..30 {
+            let name = format!("user:{}", i);
+            db.set(
+                Entity::Bulk(Bytes::from(name)),
+                Entity::Bulk(Bytes::from_static(b"v")),
+                SetOptions::default(),
+            )
+            .await
+            .unwrap();
+        }
+        db.sadd(key("user:set"), vec![Bytes::from_static(b"m")])
+            .await
+            .unwrap();
+        db.sadd(key("other"), vec![Bytes::from_static(b"m")])
+            .await
+            .unwrap();
+
+        let mut seen = vec![];
+        let mut cursor = 0;
+        loop {
+            let (next, page) = db.scan(cursor, Some(b"user:*"), None, 4).await;
+            seen.extend(page);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        seen.sort();
+        seen.dedup();
+        assert_eq!(31, seen.len());
+
+        let (next, sets) = db.scan(0, None, Some("SET"), 100).await;
+        assert_eq!(0, next);
+        assert_eq!(2, sets.len());
+
+        let mut keys = db.keys(b"user:?").await;
+        keys.sort();
+        assert_eq!(10, keys.len());
+        assert_eq!(key("user:0"), keys[0]);
+        assert!(db.keys(b"nope*").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rename_copy_flush() {
+        let db = Db::new();
+        let field = Bytes::from_static(b"f");
+        let soon = Instant::now() + Duration::from_secs(10);
+        let later = soon + Duration::from_secs(10);
+
+        assert!(db.rename(&key("h"), &key("g"), false).await.is_err());
+        db.hset(key("h"), vec![(field.clone(), Bytes::from_static(b"v"))])
+            .await
+            .unwrap();
+        db.expire(&key("h"), later, None).await;
+        db.hexpire(&key("h"), std::slice::from_ref(&field), soon, None)
+            .await
+            .unwrap();
+        db.set(
+            key("s"),
+            Entity::Bulk(Bytes::from_static(b"v")),
+            SetOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(!db.rename(&key("h"), &key("s"), true).await.unwrap());
+        assert!(db.rename(&key("h"), &key("s"), false).await.unwrap());
+        assert_eq!(Ttl::Missing, db.ttl(&key("h")).await);
+        assert_eq!(Ttl::ExpiresAt(later), db.ttl(&key("s")).await);
+        assert_eq!(
+            vec![Ttl::ExpiresAt(soon)],
+            db.httl(&key("s"), std::slice::from_ref(&field))
+                .await
+                .unwrap()
+        );
+        assert_eq!(Some("hash"), db.type_of(&key("s")).await);
+
+        assert!(db.copy(&key("s"), &key("s"), true).await.is_err());
+        assert!(!db.copy(&key("h"), &key("c"), false).await.unwrap());
+        assert!(db.copy(&key("s"), &key("c"), false).await.unwrap());
+        assert!(!db.copy(&key("s"), &key("c"), false).await.unwrap());
+        assert_eq!(Ttl::ExpiresAt(later), db.ttl(&key("c")).await);
+        assert_eq!(
+            vec![Ttl::ExpiresAt(soon)],
+            db.httl(&key("c"), std::slice::from_ref(&field))
+                .await
+                .unwrap()
+        );
+        assert_eq!(2, db.dbsize().await);
+        assert!(db.random_key().await.is_some());
+
+        db.flush(false).await;
+        assert_eq!(0, db.dbsize().await);
+        assert_eq!(None, db.random_key().await);
+        assert_eq!(None, db.type_of(&key("s")).await);
+    }
+}
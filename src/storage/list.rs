@@ -0,0 +1,311 @@
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, Entry, State, Value, entity::Entity, range_bounds},
+};
+
+pub(crate) mod blocking;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum End {
+    Left,
+    Right,
+}
+
+impl Db {
+    pub(crate) async fn push(
+        &self,
+        key: Entity,
+        values: Vec<Bytes>,
+        end: End,
+    ) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let entry = state.entities.get_or_insert_with(key.clone(), || Entry {
+            data: Value::List(VecDeque::new()),
+            expires_at: None,
+        });
+        let Value::List(list) = &mut entry.data else {
+            return Err(CacheError::WrongType);
+        };
+        for value in values {
+            match end {
+                End::Left => list.push_front(value),
+                End::Right => list.push_back(value),
+            }
+        }
+        let len = list.len();
+        state.serve_blocked(&key);
+        Ok(len)
+    }
+
+    pub(crate) async fn pop(
+        &self,
+        key: &Entity,
+        end: End,
+        count: usize,
+    ) -> Result<Option<Vec<Bytes>>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let Some(list) = state.list_mut(key)? else {
+            return Ok(None);
+        };
+        let count = count.min(list.len());
+        let popped = match end {
+            End::Left => list.drain(..count).collect(),
+            End::Right => list.drain(list.len() - count..).rev().collect(),
+        };
+        state.remove_if_empty(key);
+        Ok(Some(popped))
+    }
+
+    pub(crate) async fn lrange(
+        &self,
+        key: &Entity,
+        start: i64,
+        stop: i64,
+    ) -> Result<Vec<Bytes>, CacheError> {
+        let state = self.shared.state.lock().await;
+        let Some(list) = state.list(key)? else {
+            return Ok(vec![]);
+        };
+        Ok(match range_bounds(start, stop, list.len()) {
+            Some((from, to)) => list.range(from..to).cloned().collect(),
+            None => vec![],
+        })
+    }
+
+    pub(crate) async fn llen(&self, key: &Entity) -> Result<usize, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state.list(key)?.map(VecDeque::len).unwrap_or(0))
+    }
+
+    pub(crate) async fn lindex(
+        &self,
+        key: &Entity,
+        index: i64,
+    ) -> Result<Option<Bytes>, CacheError> {
+        let state = self.shared.state.lock().await;
+        let Some(list) = state.list(key)? else {
+            return Ok(None);
+        };
+        Ok(list_index(index, list.len()).and_then(|index| list.get(index).cloned()))
+    }
+
+    pub(crate) async fn lset(
+        &self,
+        key: &Entity,
+        index: i64,
+        value: Bytes,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let Some(list) = state.list_mut(key)? else {
+            return Err("ERR no such key".into());
+        };
+        let index = list_index(index, list.len()).ok_or("ERR index out of range")?;
+        list[index] = value;
+        Ok(())
+    }
+
+    /// Removes up to `count` occurrences of `value`: from the head when
+    /// `count` is positive, from the tail when negative, and all of them when
+    /// zero.
+    pub(crate) async fn lrem(
+        &self,
+        key: &Entity,
+        count: i64,
+        value: &Bytes,
+    ) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let Some(list) = state.list_mut(key)? else {
+            return Ok(0);
+        };
+        let limit = if count == 0 {
+            usize::MAX
+        } else {
+            count.unsigned_abs() as usize
+        };
+        let mut removed = 0;
+        if count < 0 {
+            let mut index = list.len();
+            while index > 0 && removed < limit {
+                index -= 1;
+                if list[index] == value {
+                    list.remove(index);
+                    removed += 1;
+                }
+            }
+        } else {
+            let mut index = 0;
+            while index < list.len() && removed < limit {
+                if list[index] == value {
+                    list.remove(index);
+                    removed += 1;
+                } else {
+                    index += 1;
+                }
+            }
+        }
+        state.remove_if_empty(key);
+        Ok(removed)
+    }
+
+    pub(crate) async fn ltrim(
+        &self,
+        key: &Entity,
+        start: i64,
+        stop: i64,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let Some(list) = state.list_mut(key)? else {
+            return Ok(());
+        };
+        match range_bounds(start, stop, list.len()) {
+            Some((from, to)) => {
+                list.truncate(to);
+                list.drain(..from);
+            }
+            None => list.clear(),
+        }
+        state.remove_if_empty(key);
+        Ok(())
+    }
+}
+
+impl State {
+    fn list(&self, key: &Entity) -> Result<Option<&VecDeque<Bytes>>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::List(list)) => Ok(Some(list)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn list_mut(&mut self, key: &Entity) -> Result<Option<&mut VecDeque<Bytes>>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::List(list)) => Ok(Some(list)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+}
+
+fn list_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{string::SetOptions, test_support::key};
+
+    fn values(items: &[&'static str]) -> Vec<Bytes> {
+        items
+            .iter()
+            .map(|item| Bytes::from_static(item.as_bytes()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn push_pop_range() {
+        let db = Db::new();
+
+        assert_eq!(
+            2,
+            db.push(key("l"), values(&["b", "a"]), End::Left)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            4,
+            db.push(key("l"), values(&["c", "d"]), End::Right)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            values(&["a", "b", "c", "d"]),
+            db.lrange(&key("l"), 0, -1).await.unwrap()
+        );
+        assert_eq!(
+            values(&["c", "d"]),
+            db.lrange(&key("l"), -2, 10).await.unwrap()
+        );
+
+        assert_eq!(
+            Some(values(&["d", "c"])),
+            db.pop(&key("l"), End::Right, 2).await.unwrap()
+        );
+        assert_eq!(
+            Some(values(&["a", "b"])),
+            db.pop(&key("l"), End::Left, 5).await.unwrap()
+        );
+        assert_eq!(None, db.pop(&key("l"), End::Left, 1).await.unwrap());
+        assert_eq!(0, db.llen(&key("l")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn lrem_ltrim_lset() {
+        let db = Db::new();
+        db.push(key("l"), values(&["x", "a", "x", "b", "x"]), End::Right)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            1,
+            db.lrem(&key("l"), -1, &Bytes::from_static(b"x"))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            values(&["x", "a", "x", "b"]),
+            db.lrange(&key("l"), 0, -1).await.unwrap()
+        );
+
+        db.ltrim(&key("l"), 1, -2).await.unwrap();
+        assert_eq!(
+            values(&["a", "x"]),
+            db.lrange(&key("l"), 0, -1).await.unwrap()
+        );
+
+        db.lset(&key("l"), -1, Bytes::from_static(b"z"))
+            .await
+            .unwrap();
+        assert_eq!(
+            Some(Bytes::from_static(b"z")),
+            db.lindex(&key("l"), 1).await.unwrap()
+        );
+        assert!(db.lset(&key("l"), 2, Bytes::new()).await.is_err());
+
+        db.ltrim(&key("l"), 5, 10).await.unwrap();
+        assert!(db.get(&key("l")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn wrong_type() {
+        let db = Db::new();
+        db.set(
+            key("s"),
+            Entity::Bulk(Bytes::from_static(b"v")),
+            SetOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            db.push(key("s"), values(&["a"]), End::Left).await,
+            Err(CacheError::WrongType)
+        ));
+        assert!(matches!(
+            db.llen(&key("s")).await,
+            Err(CacheError::WrongType)
+        ));
+
+        db.push(key("l"), values(&["a"]), End::Left).await.unwrap();
+        assert!(matches!(
+            db.get(&key("l")).await,
+            Err(CacheError::WrongType)
+        ));
+    }
+}
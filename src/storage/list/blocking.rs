@@ -0,0 +1,315 @@
+use std::collections::{HashMap, VecDeque};
+
+use bytes::Bytes;
+use tokio::sync::oneshot;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, Entry, State, Value, entity::Entity, list::End},
+};
+
+/// What a blocked client does with the element it is handed.
+#[derive(Debug, Clone)]
+pub(crate) enum BlockedPop {
+    /// `BLPOP`/`BRPOP`: pop from this end.
+    Pop(End),
+    /// `BLMOVE`: pop from the first end and push onto the destination at the
+    /// second one.
+    Move(End, Entity, End),
+}
+
+/// The key an element was popped from and the element itself.
+pub(crate) type Served = Result<(Entity, Bytes), CacheError>;
+
+pub(crate) enum Blocked {
+    /// One of the keys already held an element.
+    Ready(Entity, Bytes),
+    /// The caller is parked and will be handed an element through the ticket.
+    Waiting(WaitTicket),
+}
+
+#[derive(Debug)]
+pub(crate) struct WaitTicket {
+    id: u64,
+    pub(crate) receiver: oneshot::Receiver<Served>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    keys: Vec<Entity>,
+    pop: BlockedPop,
+    sender: oneshot::Sender<Served>,
+}
+
+/// Clients parked in blocking list pops, queued per key in arrival order.
+#[derive(Debug, Default)]
+pub(crate) struct BlockedClients {
+    next_id: u64,
+    waiters: HashMap<u64, Waiter>,
+    queues: HashMap<Entity, VecDeque<u64>>,
+}
+
+impl BlockedClients {
+    fn park(&mut self, keys: &[Entity], pop: BlockedPop) -> WaitTicket {
+        let id = self.next_id;
+        self.next_id += 1;
+        for key in keys {
+            self.queues.entry(key.clone()).or_default().push_back(id);
+        }
+        let (sender, receiver) = oneshot::channel();
+        self.waiters.insert(
+            id,
+            Waiter {
+                keys: keys.to_vec(),
+                pop,
+                sender,
+            },
+        );
+        WaitTicket { id, receiver }
+    }
+
+    /// Removes a waiter from every queue it sits in.
+    fn remove(&mut self, id: u64) -> Option<Waiter> {
+        let waiter = self.waiters.remove(&id)?;
+        for key in &waiter.keys {
+            if let Some(queue) = self.queues.get_mut(key) {
+                queue.retain(|queued| *queued != id);
+                if queue.is_empty() {
+                    self.queues.remove(key);
+                }
+            }
+        }
+        Some(waiter)
+    }
+
+    /// The longest-waiting client blocked on `key`.
+    fn first(&self, key: &Entity) -> Option<u64> {
+        self.queues
+            .get(key)
+            .and_then(|queue| queue.front().copied())
+    }
+}
+
+impl Db {
+    /// Pops from the first non-empty list among `keys`, or parks the caller
+    /// behind every client already blocked on them.
+    pub(crate) async fn block_pop(
+        &self,
+        keys: &[Entity],
+        pop: BlockedPop,
+    ) -> Result<Blocked, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        for key in keys {
+            if state.list(key)?.is_none() {
+                continue;
+            }
+            let value = state.pop_blocked(key, &pop)?;
+            return Ok(Blocked::Ready(key.clone(), value));
+        }
+        Ok(Blocked::Waiting(state.blocked.park(keys, pop)))
+    }
+
+    /// Withdraws a parked client. Returns what it was served if a push got to
+    /// it first.
+    pub(crate) async fn unblock(&self, mut ticket: WaitTicket) -> Option<Served> {
+        let mut state = self.shared.state.lock().await;
+        if state.blocked.remove(ticket.id).is_some() {
+            return None;
+        }
+        ticket.receiver.try_recv().ok()
+    }
+}
+
+impl State {
+    /// Pops one element according to `pop`. The list at `key` must exist.
+    fn pop_blocked(&mut self, key: &Entity, pop: &BlockedPop) -> Result<Bytes, CacheError> {
+        let end = match pop {
+            BlockedPop::Pop(end) => *end,
+            BlockedPop::Move(from, destination, _) => {
+                // Refuse before popping so the element is not lost.
+                self.list(destination)?;
+                *from
+            }
+        };
+        let list = self.list_mut(key)?.expect("list exists");
+        let value = match end {
+            End::Left => list.pop_front(),
+            End::Right => list.pop_back(),
+        }
+        .expect("lists are never stored empty");
+        self.remove_if_empty(key);
+
+        if let BlockedPop::Move(_, destination, to) = pop {
+            let entry = self
+                .entities
+                .get_or_insert_with(destination.clone(), || Entry {
+                    data: Value::List(VecDeque::new()),
+                    expires_at: None,
+                });
+            let Value::List(list) = &mut entry.data else {
+                unreachable!("destination type checked above");
+            };
+            match to {
+                End::Left => list.push_front(value.clone()),
+                End::Right => list.push_back(value.clone()),
+            }
+            // Every nested call consumes a waiter, so chains of moves end.
+            self.serve_blocked(destination);
+        }
+        Ok(value)
+    }
+
+    /// Hands elements of the list at `key` to the clients blocked on it,
+    /// longest-waiting first, for as long as it has elements.
+    pub(in crate::storage) fn serve_blocked(&mut self, key: &Entity) {
+        while let Ok(Some(_)) = self.list(key) {
+            let Some(id) = self.blocked.first(key) else {
+                break;
+            };
+            let waiter = self.blocked.remove(id).expect("queued waiters exist");
+            if waiter.sender.is_closed() {
+                continue;
+            }
+            let served = self
+                .pop_blocked(key, &waiter.pop)
+                .map(|value| (key.clone(), value));
+            if let (Err(Ok((_, value))), BlockedPop::Pop(end)) =
+                (waiter.sender.send(served), &waiter.pop)
+            {
+                // The client went away meanwhile; put the element back.
+                self.push_back_to(key, value, *end);
+            }
+        }
+    }
+
+    /// Serves the clients blocked on every key, after the lists under the
+    /// keys were swapped out wholesale.
+    pub(in crate::storage) fn serve_all_blocked(&mut self) {
+        let keys: Vec<Entity> = self.blocked.queues.keys().cloned().collect();
+        for key in keys {
+            self.serve_blocked(&key);
+        }
+    }
+
+    fn push_back_to(&mut self, key: &Entity, value: Bytes, end: End) {
+        let entry = self.entities.get_or_insert_with(key.clone(), || Entry {
+            data: Value::List(VecDeque::new()),
+            expires_at: None,
+        });
+        if let Value::List(list) = &mut entry.data {
+            match end {
+                End::Left => list.push_front(value),
+                End::Right => list.push_back(value),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{string::SetOptions, test_support::key};
+
+    fn value(item: &'static str) -> Bytes {
+        Bytes::from_static(item.as_bytes())
+    }
+
+    fn waiting(blocked: Blocked) -> WaitTicket {
+        match blocked {
+            Blocked::Waiting(ticket) => ticket,
+            Blocked::Ready(..) => panic!("expected to block"),
+        }
+    }
+
+    #[tokio::test]
+    async fn served_in_arrival_order() {
+        let db = Db::new();
+        let first = waiting(
+            db.block_pop(&[key("a"), key("b")], BlockedPop::Pop(End::Left))
+                .await
+                .unwrap(),
+        );
+        let second = waiting(
+            db.block_pop(&[key("b")], BlockedPop::Pop(End::Left))
+                .await
+                .unwrap(),
+        );
+
+        db.push(
+            key("b"),
+            vec![value("1"), value("2"), value("3")],
+            End::Right,
+        )
+        .await
+        .unwrap();
+
+        let (served_key, served) = first.receiver.await.unwrap().unwrap();
+        assert_eq!((key("b"), value("1")), (served_key, served));
+        assert_eq!(value("2"), second.receiver.await.unwrap().unwrap().1);
+        assert_eq!(vec![value("3")], db.lrange(&key("b"), 0, -1).await.unwrap());
+
+        // The first waiter no longer sits in the queue of `a`.
+        db.push(key("a"), vec![value("x")], End::Right)
+            .await
+            .unwrap();
+        assert_eq!(1, db.llen(&key("a")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn move_wakes_destination_waiters() {
+        let db = Db::new();
+        let mover = waiting(
+            db.block_pop(
+                &[key("src")],
+                BlockedPop::Move(End::Right, key("dst"), End::Left),
+            )
+            .await
+            .unwrap(),
+        );
+        let popper = waiting(
+            db.block_pop(&[key("dst")], BlockedPop::Pop(End::Left))
+                .await
+                .unwrap(),
+        );
+
+        db.push(key("src"), vec![value("v")], End::Left)
+            .await
+            .unwrap();
+
+        assert_eq!(value("v"), mover.receiver.await.unwrap().unwrap().1);
+        let (served_key, served) = popper.receiver.await.unwrap().unwrap();
+        assert_eq!((key("dst"), value("v")), (served_key, served));
+        assert_eq!(0, db.llen(&key("src")).await.unwrap());
+        assert_eq!(0, db.llen(&key("dst")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn unblock_and_ready() {
+        let db = Db::new();
+        let ticket = waiting(
+            db.block_pop(&[key("l")], BlockedPop::Pop(End::Left))
+                .await
+                .unwrap(),
+        );
+        assert!(db.unblock(ticket).await.is_none());
+
+        db.push(key("l"), vec![value("v")], End::Left)
+            .await
+            .unwrap();
+        assert_eq!(1, db.llen(&key("l")).await.unwrap());
+
+        let blocked = db
+            .block_pop(&[key("missing"), key("l")], BlockedPop::Pop(End::Right))
+            .await
+            .unwrap();
+        assert!(matches!(blocked, Blocked::Ready(k, v) if k == key("l") && v == value("v")));
+
+        db.set(key("s"), Entity::Bulk(value("str")), SetOptions::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            db.block_pop(&[key("s")], BlockedPop::Pop(End::Left)).await,
+            Err(CacheError::WrongType)
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+pub(crate) const DEFAULT_COUNT: usize = 10;
+
+/// Redis-style glob matching: `*`, `?`, `[abc]`, `[^a-z]` and `\` escapes.
+pub(crate) fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| glob_match(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && glob_match(rest, &text[1..]),
+        Some((b'[', rest)) => {
+            let Some((&ch, text_rest)) = text.split_first() else {
+                return false;
+            };
+            match match_class(rest, ch) {
+                Some((true, rest)) => glob_match(rest, text_rest),
+                _ => false,
+            }
+        }
+        Some((b'\\', rest)) if !rest.is_empty() => {
+            text.first() == Some(&rest[0]) && glob_match(&rest[1..], &text[1..])
+        }
+        Some((&ch, rest)) => text.first() == Some(&ch) && glob_match(rest, &text[1..]),
+    }
+}
+
+/// Matches `ch` against the class body following a `[`, returning whether it
+/// matched and the pattern after the closing `]`.
+fn match_class(pattern: &[u8], ch: u8) -> Option<(bool, &[u8])> {
+    let (negate, mut pattern) = match pattern.split_first() {
+        Some((b'^', rest)) => (true, rest),
+        _ => (false, pattern),
+    };
+    let mut matched = false;
+    loop {
+        match pattern {
+            [] => return None,
+            [b']', rest @ ..] => return Some((matched != negate, rest)),
+            [b'\\', c, rest @ ..] => {
+                matched |= *c == ch;
+                pattern = rest;
+            }
+            [lo, b'-', hi, rest @ ..] if *hi != b']' => {
+                let (lo, hi) = if lo <= hi { (*lo, *hi) } else { (*hi, *lo) };
+                matched |= (lo..=hi).contains(&ch);
+                pattern = rest;
+            }
+            [c, rest @ ..] => {
+                matched |= *c == ch;
+                pattern = rest;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match(b"*", b""));
+        assert!(glob_match(b"user:*", b"user:42"));
+        assert!(glob_match(b"h?llo", b"hallo"));
+        assert!(glob_match(b"h[ae]llo", b"hello"));
+        assert!(!glob_match(b"h[^e]llo", b"hello"));
+        assert!(glob_match(b"h[a-c]llo", b"hbllo"));
+        assert!(glob_match(b"a\\*", b"a*"));
+        assert!(!glob_match(b"a\\*", b"ab"));
+        assert!(!glob_match(b"user:*", b"session:1"));
+    }
+}
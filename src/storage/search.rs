@@ -0,0 +1,607 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Bound,
+};
+
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{
+        Db, State, Value,
+        entity::Entity,
+        search::query::{Predicate, Query},
+        sorted_set::Score,
+        table::Table,
+    },
+};
+
+pub(crate) mod query;
+
+const MISSING_INDEX: &str = "ERR no such index";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FieldKind {
+    /// Comma-separated tags, matched exactly but case-insensitively.
+    Tag,
+    /// A number, matched by range.
+    Numeric,
+    /// Free text, matched by word.
+    Text,
+}
+
+impl FieldKind {
+    pub(crate) fn parse(name: &str) -> Option<FieldKind> {
+        match &name.to_uppercase()[..] {
+            "TAG" => Some(FieldKind::Tag),
+            "NUMERIC" => Some(FieldKind::Numeric),
+            "TEXT" => Some(FieldKind::Text),
+            _ => None,
+        }
+    }
+}
+
+/// `FT.CREATE` arguments: the key prefixes to watch, where an empty list
+/// watches every key, and the hash fields to index.
+#[derive(Debug, Clone)]
+pub(crate) struct IndexDefinition {
+    pub(crate) prefixes: Vec<Bytes>,
+    pub(crate) schema: Vec<(Bytes, FieldKind)>,
+}
+
+/// An `FT.SEARCH` request. Matches are ordered by `sort_by`, or by key
+/// without it, and `limit` keys are returned after skipping `offset`.
+#[derive(Debug, Clone)]
+pub(crate) struct SearchQuery {
+    pub(crate) query: Query,
+    pub(crate) sort_by: Option<(Bytes, bool)>,
+    pub(crate) offset: usize,
+    pub(crate) limit: usize,
+}
+
+/// A matching key with its hash fields and values.
+pub(crate) type Document = (Entity, Vec<(Bytes, Bytes)>);
+
+/// The hashes under some key prefixes, indexed by the schema fields. The
+/// index is kept up to date by `State::reindex` as keys change.
+#[derive(Debug, Clone)]
+pub(crate) struct SearchIndex {
+    prefixes: Vec<Bytes>,
+    fields: Vec<Field>,
+    /// The indexed value of each schema field, by document, to find the
+    /// postings to drop when the document changes.
+    docs: HashMap<Entity, Vec<Option<Bytes>>>,
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    name: Bytes,
+    postings: Postings,
+}
+
+/// The documents holding each tag, number or word of a field.
+#[derive(Debug, Clone)]
+enum Postings {
+    Tag(HashMap<String, HashSet<Entity>>),
+    Numeric(BTreeMap<Score, HashSet<Entity>>),
+    Text(HashMap<String, HashSet<Entity>>),
+}
+
+impl Postings {
+    fn new(kind: FieldKind) -> Postings {
+        match kind {
+            FieldKind::Tag => Postings::Tag(HashMap::new()),
+            FieldKind::Numeric => Postings::Numeric(BTreeMap::new()),
+            FieldKind::Text => Postings::Text(HashMap::new()),
+        }
+    }
+
+    fn kind(&self) -> FieldKind {
+        match self {
+            Postings::Tag(_) => FieldKind::Tag,
+            Postings::Numeric(_) => FieldKind::Numeric,
+            Postings::Text(_) => FieldKind::Text,
+        }
+    }
+
+    fn add(&mut self, key: &Entity, value: &[u8]) {
+        match self {
+            Postings::Tag(postings) => {
+                for tag in tags(value) {
+                    postings.entry(tag).or_default().insert(key.clone());
+                }
+            }
+            Postings::Text(postings) => {
+                for word in words(value) {
+                    postings.entry(word).or_default().insert(key.clone());
+                }
+            }
+            Postings::Numeric(postings) => {
+                if let Some(number) = number(value) {
+                    postings
+                        .entry(Score(number))
+                        .or_default()
+                        .insert(key.clone());
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &Entity, value: &[u8]) {
+        fn remove_from<K: std::hash::Hash + Eq>(
+            postings: &mut HashMap<K, HashSet<Entity>>,
+            term: K,
+            key: &Entity,
+        ) {
+            if let Some(keys) = postings.get_mut(&term) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    postings.remove(&term);
+                }
+            }
+        }
+
+        match self {
+            Postings::Tag(postings) => {
+                for tag in tags(value) {
+                    remove_from(postings, tag, key);
+                }
+            }
+            Postings::Text(postings) => {
+                for word in words(value) {
+                    remove_from(postings, word, key);
+                }
+            }
+            Postings::Numeric(postings) => {
+                let Some(number) = number(value) else {
+                    return;
+                };
+                if let Some(keys) = postings.get_mut(&Score(number)) {
+                    keys.remove(key);
+                    if keys.is_empty() {
+                        postings.remove(&Score(number));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn tags(value: &[u8]) -> HashSet<String> {
+    String::from_utf8_lossy(value)
+        .split(',')
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+fn words(value: &[u8]) -> HashSet<String> {
+    String::from_utf8_lossy(value)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Values that are not numbers are left out of numeric fields.
+fn number(value: &[u8]) -> Option<f64> {
+    str::from_utf8(value)
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|number| !number.is_nan())
+}
+
+/// Whether `(min, max)` holds anything, since `BTreeMap::range` panics on
+/// inverted ranges.
+fn is_valid_range(min: Bound<f64>, max: Bound<f64>) -> bool {
+    match (min, max) {
+        (Bound::Included(min), Bound::Included(max)) => min <= max,
+        (Bound::Included(min) | Bound::Excluded(min), Bound::Excluded(max))
+        | (Bound::Excluded(min), Bound::Included(max)) => min < max,
+        _ => true,
+    }
+}
+
+impl SearchIndex {
+    fn new(definition: IndexDefinition) -> SearchIndex {
+        SearchIndex {
+            prefixes: definition.prefixes,
+            fields: definition
+                .schema
+                .into_iter()
+                .map(|(name, kind)| Field {
+                    name,
+                    postings: Postings::new(kind),
+                })
+                .collect(),
+            docs: HashMap::new(),
+        }
+    }
+
+    fn covers(&self, key: &Entity) -> bool {
+        let key = match key {
+            Entity::Bulk(key) => &key[..],
+            Entity::Simple(key) => key.as_bytes(),
+            _ => return false,
+        };
+        self.prefixes.is_empty() || self.prefixes.iter().any(|prefix| key.starts_with(prefix))
+    }
+
+    /// Replaces the document at `key` with `hash`, or drops it for `None`.
+    fn update(&mut self, key: &Entity, hash: Option<&Table<Bytes, Bytes>>) {
+        if let Some(values) = self.docs.remove(key) {
+            for (field, value) in self.fields.iter_mut().zip(values) {
+                if let Some(value) = value {
+                    field.postings.remove(key, &value);
+                }
+            }
+        }
+        let Some(hash) = hash else {
+            return;
+        };
+        let values = self
+            .fields
+            .iter_mut()
+            .map(|field| {
+                let value = hash.get(&field.name).cloned();
+                if let Some(value) = &value {
+                    field.postings.add(key, value);
+                }
+                value
+            })
+            .collect();
+        self.docs.insert(key.clone(), values);
+    }
+
+    fn field(&self, name: &Bytes, kind: FieldKind) -> Result<&Postings, CacheError> {
+        let field = self
+            .fields
+            .iter()
+            .find(|field| field.name == *name)
+            .ok_or_else(|| format!("ERR unknown field '{}'", String::from_utf8_lossy(name)))?;
+        if field.postings.kind() != kind {
+            return Err(format!(
+                "ERR field '{}' is not a {:?} field",
+                String::from_utf8_lossy(name),
+                kind
+            )
+            .into());
+        }
+        Ok(&field.postings)
+    }
+
+    fn matching(&self, predicate: &Predicate) -> Result<HashSet<&Entity>, CacheError> {
+        let mut found = HashSet::new();
+        match predicate {
+            Predicate::All => found.extend(self.docs.keys()),
+            Predicate::Tag { field, tags } => {
+                let Postings::Tag(postings) = self.field(field, FieldKind::Tag)? else {
+                    unreachable!("checked by field()");
+                };
+                for tag in tags {
+                    found.extend(postings.get(tag).into_iter().flatten());
+                }
+            }
+            Predicate::Numeric { field, min, max } => {
+                let Postings::Numeric(postings) = self.field(field, FieldKind::Numeric)? else {
+                    unreachable!("checked by field()");
+                };
+                if is_valid_range(*min, *max) {
+                    let range = postings.range((min.map(Score), max.map(Score)));
+                    found.extend(range.flat_map(|(_, keys)| keys));
+                }
+            }
+            Predicate::Text {
+                field: Some(field),
+                term,
+            } => {
+                let Postings::Text(postings) = self.field(field, FieldKind::Text)? else {
+                    unreachable!("checked by field()");
+                };
+                found.extend(postings.get(term).into_iter().flatten());
+            }
+            Predicate::Text { field: None, term } => {
+                for field in &self.fields {
+                    if let Postings::Text(postings) = &field.postings {
+                        found.extend(postings.get(term).into_iter().flatten());
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// The keys matching `query`, in order, and how many there are before
+    /// pagination.
+    fn search(&self, query: &SearchQuery) -> Result<(usize, Vec<Entity>), CacheError> {
+        let mut matches: Option<HashSet<&Entity>> = None;
+        let mut excluded = vec![];
+        for clause in &query.query.clauses {
+            let found = self.matching(&clause.predicate)?;
+            if clause.negate {
+                excluded.push(found);
+            } else {
+                matches = Some(match matches {
+                    Some(matches) => matches.intersection(&found).copied().collect(),
+                    None => found,
+                });
+            }
+        }
+        let mut matches: Vec<&Entity> = matches
+            .unwrap_or_else(|| self.docs.keys().collect())
+            .into_iter()
+            .filter(|key| !excluded.iter().any(|excluded| excluded.contains(key)))
+            .collect();
+        matches.sort();
+
+        if let Some((name, ascending)) = &query.sort_by {
+            let (position, field) = self
+                .fields
+                .iter()
+                .enumerate()
+                .find(|(_, field)| field.name == *name)
+                .ok_or_else(|| format!("ERR unknown field '{}'", String::from_utf8_lossy(name)))?;
+            let value = |key: &Entity| self.docs[key][position].as_ref();
+            // Documents without the field go last either way.
+            matches.sort_by(|a, b| match (value(a), value(b)) {
+                (Some(a), Some(b)) => {
+                    let order = if field.postings.kind() == FieldKind::Numeric {
+                        let number = |value| number(value).map(Score);
+                        number(a).cmp(&number(b))
+                    } else {
+                        a.cmp(b)
+                    };
+                    if *ascending { order } else { order.reverse() }
+                }
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            });
+        }
+
+        let total = matches.len();
+        let page = matches
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit)
+            .cloned()
+            .collect();
+        Ok((total, page))
+    }
+}
+
+impl Db {
+    /// Creates index `name` and indexes the hashes already under its
+    /// prefixes.
+    pub(crate) async fn ft_create(
+        &self,
+        name: String,
+        definition: IndexDefinition,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if state.indexes.contains_key(&name) {
+            return Err("ERR index already exists".into());
+        }
+        let mut index = SearchIndex::new(definition);
+        for (key, entry) in state.entities.iter() {
+            if let Value::Hash(hash) = &entry.data
+                && index.covers(key)
+            {
+                index.update(key, Some(hash));
+            }
+        }
+        state.indexes.insert(name, index);
+        Ok(())
+    }
+
+    pub(crate) async fn ft_dropindex(&self, name: &str) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        state.indexes.remove(name).ok_or(MISSING_INDEX)?;
+        Ok(())
+    }
+
+    /// The number of matches and a page of them, each with its hash fields
+    /// sorted by name.
+    pub(crate) async fn ft_search(
+        &self,
+        name: &str,
+        query: &SearchQuery,
+    ) -> Result<(usize, Vec<Document>), CacheError> {
+        let state = self.shared.state.lock().await;
+        let index = state.indexes.get(name).ok_or(MISSING_INDEX)?;
+        let (total, keys) = index.search(query)?;
+        let documents = keys
+            .into_iter()
+            .map(|key| {
+                let mut fields: Vec<_> = match state.entities.get(&key).map(|entry| &entry.data) {
+                    Some(Value::Hash(hash)) => hash
+                        .iter()
+                        .map(|(field, value)| (field.clone(), value.clone()))
+                        .collect(),
+                    _ => vec![],
+                };
+                fields.sort();
+                (key, fields)
+            })
+            .collect();
+        Ok((total, documents))
+    }
+}
+
+impl State {
+    /// Brings the search indexes covering `key` up to date with its current
+    /// value. Called wherever a key is written, removed or expires.
+    pub(super) fn reindex(&mut self, key: &Entity) {
+        if self.indexes.is_empty() {
+            return;
+        }
+        let hash = match self.entities.get(key).map(|entry| &entry.data) {
+            Some(Value::Hash(hash)) => Some(hash),
+            _ => None,
+        };
+        for index in self.indexes.values_mut() {
+            if index.covers(key) {
+                index.update(key, hash);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::storage::{string::SetOptions, test_support::key};
+
+    fn bytes(value: &'static str) -> Bytes {
+        Bytes::from_static(value.as_bytes())
+    }
+
+    fn search(query: &str) -> SearchQuery {
+        SearchQuery {
+            query: Query::parse(query).unwrap(),
+            sort_by: None,
+            offset: 0,
+            limit: 10,
+        }
+    }
+
+    async fn keys(db: &Db, query: &SearchQuery) -> Vec<Entity> {
+        let (_, documents) = db.ft_search("sessions", query).await.unwrap();
+        documents.into_iter().map(|(key, _)| key).collect()
+    }
+
+    async fn session(db: &Db, id: u32, tenant: &'static str, status: &'static str, age: u32) {
+        let pairs = vec![
+            (bytes("tenant"), bytes(tenant)),
+            (bytes("status"), bytes(status)),
+            (bytes("age"), Bytes::from(age.to_string())),
+            (bytes("agent"), bytes("Mozilla Firefox")),
+        ];
+        db.hset(key(&format!("session:{}", id)), pairs)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn tracks_hash_changes() {
+        let db = Db::new();
+        session(&db, 1, "acme", "active", 30).await;
+        let definition = IndexDefinition {
+            prefixes: vec![bytes("session:")],
+            schema: vec![
+                (bytes("tenant"), FieldKind::Tag),
+                (bytes("status"), FieldKind::Tag),
+                (bytes("age"), FieldKind::Numeric),
+                (bytes("agent"), FieldKind::Text),
+            ],
+        };
+        db.ft_create("sessions".into(), definition.clone())
+            .await
+            .unwrap();
+        assert!(db.ft_create("sessions".into(), definition).await.is_err());
+
+        session(&db, 2, "acme", "active", 10).await;
+        session(&db, 3, "acme", "closed", 20).await;
+        session(&db, 4, "globex", "active", 40).await;
+        db.hset(key("other:1"), vec![(bytes("tenant"), bytes("acme"))])
+            .await
+            .unwrap();
+
+        let active = search("@tenant:{ACME} @status:{active}");
+        assert_eq!(
+            vec![key("session:1"), key("session:2")],
+            keys(&db, &active).await
+        );
+
+        db.hset(key("session:2"), vec![(bytes("status"), bytes("closed"))])
+            .await
+            .unwrap();
+        db.hincrby(key("session:1"), bytes("age"), 5).await.unwrap();
+        assert_eq!(vec![key("session:1")], keys(&db, &active).await);
+        assert_eq!(
+            vec![key("session:1")],
+            keys(&db, &search("@age:[35 35]")).await
+        );
+
+        db.hdel(&key("session:1"), &[bytes("status")])
+            .await
+            .unwrap();
+        assert!(keys(&db, &active).await.is_empty());
+        db.set(
+            key("session:1"),
+            Entity::Simple("gone".into()),
+            SetOptions::default(),
+        )
+        .await
+        .unwrap();
+        db.del(&[key("session:3")]).await;
+        assert_eq!(
+            vec![key("session:2"), key("session:4")],
+            keys(&db, &search("firefox -@tenant:{nope}")).await
+        );
+
+        db.set(
+            key("session:4"),
+            Entity::Simple("x".into()),
+            SetOptions::default(),
+        )
+        .await
+        .unwrap();
+        db.hset(key("session:5"), vec![(bytes("status"), bytes("active"))])
+            .await
+            .unwrap();
+        let mut state = db.shared.state.lock().await;
+        let expires_at = tokio::time::Instant::now() + Duration::from_millis(10);
+        state.set_expiration(&key("session:5"), Some(expires_at));
+        drop(state);
+        db.shared.background_task.notify_one();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(keys(&db, &search("@status:{active}")).await.is_empty());
+
+        db.ft_dropindex("sessions").await.unwrap();
+        assert!(db.ft_search("sessions", &active).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn sorts_and_paginates() {
+        let db = Db::new();
+        let definition = IndexDefinition {
+            prefixes: vec![],
+            schema: vec![
+                (bytes("tenant"), FieldKind::Tag),
+                (bytes("age"), FieldKind::Numeric),
+            ],
+        };
+        db.ft_create("sessions".into(), definition).await.unwrap();
+        for (id, age) in [(1, 30), (2, 5), (3, 100), (4, 20)] {
+            session(&db, id, "acme", "active", age).await;
+        }
+        db.hset(key("session:9"), vec![(bytes("tenant"), bytes("acme"))])
+            .await
+            .unwrap();
+
+        let mut query = search("@tenant:{acme} -@age:[(90 +inf]");
+        query.sort_by = Some((bytes("age"), false));
+        query.limit = 3;
+        let (total, documents) = db.ft_search("sessions", &query).await.unwrap();
+        assert_eq!(4, total);
+        let keys: Vec<_> = documents.iter().map(|(key, _)| key.clone()).collect();
+        assert_eq!(
+            vec![key("session:1"), key("session:4"), key("session:2")],
+            keys
+        );
+        assert_eq!((bytes("age"), bytes("30")), documents[0].1[0]);
+
+        query.sort_by = Some((bytes("age"), true));
+        query.offset = 2;
+        let (_, documents) = db.ft_search("sessions", &query).await.unwrap();
+        let keys: Vec<_> = documents.into_iter().map(|(key, _)| key).collect();
+        assert_eq!(vec![key("session:1"), key("session:9")], keys);
+
+        query.sort_by = Some((bytes("missing"), true));
+        assert!(db.ft_search("sessions", &query).await.is_err());
+        assert!(db.ft_search("sessions", &search("@age:{1}")).await.is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use std::{iter::Peekable, ops::Bound, str::Chars};
+
+use bytes::Bytes;
+
+use crate::error::CacheError;
+
+const INVALID: &str = "ERR syntax error in query";
+
+/// What a clause selects.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Predicate {
+    /// `*`: every document.
+    All,
+    /// `@field:{a | b}`: documents with any of the tags.
+    Tag { field: Bytes, tags: Vec<String> },
+    /// `@field:[min max]`, where `(` excludes a bound and `-inf`/`+inf`
+    /// leave it open.
+    Numeric {
+        field: Bytes,
+        min: Bound<f64>,
+        max: Bound<f64>,
+    },
+    /// `@field:word`, or a bare `word` matching any text field.
+    Text { field: Option<Bytes>, term: String },
+}
+
+/// A predicate, or with a leading `-` its complement.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Clause {
+    pub(crate) negate: bool,
+    pub(crate) predicate: Predicate,
+}
+
+/// An `FT.SEARCH` query: clauses separated by spaces, all of which must
+/// hold.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Query {
+    pub(crate) clauses: Vec<Clause>,
+}
+
+impl Query {
+    pub(crate) fn parse(query: &str) -> Result<Query, CacheError> {
+        let mut chars = query.chars().peekable();
+        let mut clauses = vec![];
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let Some(&c) = chars.peek() else {
+                break;
+            };
+            if c == '*' {
+                chars.next();
+                clauses.push(Clause {
+                    negate: false,
+                    predicate: Predicate::All,
+                });
+                continue;
+            }
+            let negate = chars.next_if_eq(&'-').is_some();
+            let predicate = if chars.next_if_eq(&'@').is_some() {
+                parse_field_predicate(&mut chars)?
+            } else {
+                Predicate::Text {
+                    field: None,
+                    term: parse_word(&mut chars)?,
+                }
+            };
+            clauses.push(Clause { negate, predicate });
+        }
+        if clauses.is_empty() {
+            return Err(INVALID.into());
+        }
+        Ok(Query { clauses })
+    }
+}
+
+/// Everything after the `@` of `@field:...`.
+fn parse_field_predicate(chars: &mut Peekable<Chars>) -> Result<Predicate, CacheError> {
+    let mut field = String::new();
+    while let Some(c) = chars.next_if(|c| *c != ':' && !c.is_whitespace()) {
+        field.push(c);
+    }
+    if field.is_empty() || chars.next_if_eq(&':').is_none() {
+        return Err(INVALID.into());
+    }
+    let field = Bytes::from(field);
+
+    if chars.next_if_eq(&'{').is_some() {
+        let tags = take_until(chars, '}')?
+            .split('|')
+            .map(|tag| tag.trim().to_lowercase())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        Ok(Predicate::Tag { field, tags })
+    } else if chars.next_if_eq(&'[').is_some() {
+        let range = take_until(chars, ']')?;
+        let mut bounds = range.split_whitespace();
+        let (Some(min), Some(max), None) = (bounds.next(), bounds.next(), bounds.next()) else {
+            return Err(INVALID.into());
+        };
+        Ok(Predicate::Numeric {
+            field,
+            min: parse_bound(min)?,
+            max: parse_bound(max)?,
+        })
+    } else {
+        Ok(Predicate::Text {
+            field: Some(field),
+            term: parse_word(chars)?,
+        })
+    }
+}
+
+/// A run of letters and digits, lowercased as text fields are indexed.
+fn parse_word(chars: &mut Peekable<Chars>) -> Result<String, CacheError> {
+    let mut word = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_alphanumeric()) {
+        word.extend(c.to_lowercase());
+    }
+    if word.is_empty() {
+        return Err(INVALID.into());
+    }
+    Ok(word)
+}
+
+/// Consumes up to and including `end`, returning what came before it.
+fn take_until(chars: &mut Peekable<Chars>, end: char) -> Result<String, CacheError> {
+    let mut taken = String::new();
+    for c in chars.by_ref() {
+        if c == end {
+            return Ok(taken);
+        }
+        taken.push(c);
+    }
+    Err(INVALID.into())
+}
+
+fn parse_bound(bound: &str) -> Result<Bound<f64>, CacheError> {
+    let (value, exclusive) = match bound.strip_prefix('(') {
+        Some(value) => (value, true),
+        None => (bound, false),
+    };
+    let value: f64 = value.parse().map_err(|_| INVALID)?;
+    Ok(match value {
+        _ if value.is_nan() => return Err(INVALID.into()),
+        _ if value.is_infinite() => Bound::Unbounded,
+        _ if exclusive => Bound::Excluded(value),
+        _ => Bound::Included(value),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &'static str) -> Bytes {
+        Bytes::from_static(name.as_bytes())
+    }
+
+    #[test]
+    fn parses_clauses() {
+        let query = Query::parse(
+            "@tenant:{Acme | globex} -@status:{closed} @age:[(18 +inf] Hello @title:World",
+        )
+        .unwrap();
+        let predicates: Vec<_> = query
+            .clauses
+            .iter()
+            .map(|clause| (clause.negate, clause.predicate.clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    false,
+                    Predicate::Tag {
+                        field: field("tenant"),
+                        tags: vec!["acme".into(), "globex".into()],
+                    }
+                ),
+                (
+                    true,
+                    Predicate::Tag {
+                        field: field("status"),
+                        tags: vec!["closed".into()],
+                    }
+                ),
+                (
+                    false,
+                    Predicate::Numeric {
+                        field: field("age"),
+                        min: Bound::Excluded(18.0),
+                        max: Bound::Unbounded,
+                    }
+                ),
+                (
+                    false,
+                    Predicate::Text {
+                        field: None,
+                        term: "hello".into(),
+                    }
+                ),
+                (
+                    false,
+                    Predicate::Text {
+                        field: Some(field("title")),
+                        term: "world".into(),
+                    }
+                ),
+            ],
+            predicates
+        );
+
+        assert_eq!(
+            Predicate::All,
+            Query::parse(" * ").unwrap().clauses[0].predicate
+        );
+        for invalid in [
+            "",
+            "@tag",
+            "@tag:{a",
+            "@n:[1]",
+            "@n:[a 2]",
+            "@n:[1 2 3]",
+            "!",
+        ] {
+            assert!(Query::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, Entry, State, Value, entity::Entity, scan::glob_match, table::TableSet},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SetOperation {
+    Inter,
+    Union,
+    Diff,
+}
+
+impl Db {
+    pub(crate) async fn sadd(&self, key: Entity, members: Vec<Bytes>) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let entry = state.entities.get_or_insert_with(key, || Entry {
+            data: Value::Set(TableSet::new()),
+            expires_at: None,
+        });
+        let Value::Set(set) = &mut entry.data else {
+            return Err(CacheError::WrongType);
+        };
+        let mut added = 0;
+        for member in members {
+            if set.insert(member) {
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    pub(crate) async fn srem(&self, key: &Entity, members: &[Bytes]) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let Some(set) = state.set_mut(key)? else {
+            return Ok(0);
+        };
+        let removed = members.iter().filter(|member| set.remove(*member)).count();
+        state.remove_if_empty(key);
+        Ok(removed)
+    }
+
+    pub(crate) async fn sismember(&self, key: &Entity, member: &Bytes) -> Result<bool, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state.set(key)?.is_some_and(|set| set.contains(member)))
+    }
+
+    pub(crate) async fn smembers(&self, key: &Entity) -> Result<Vec<Bytes>, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state
+            .set(key)?
+            .map(|set| set.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    pub(crate) async fn scard(&self, key: &Entity) -> Result<usize, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state.set(key)?.map(TableSet::len).unwrap_or(0))
+    }
+
+    pub(crate) async fn set_operation(
+        &self,
+        operation: SetOperation,
+        keys: &[Entity],
+    ) -> Result<Vec<Bytes>, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state.combine_sets(operation, keys)?.into_iter().collect())
+    }
+
+    /// Computes the set operation and stores the result in `destination`,
+    /// replacing whatever it held before. Returns the size of the result.
+    pub(crate) async fn set_operation_store(
+        &self,
+        operation: SetOperation,
+        destination: Entity,
+        keys: &[Entity],
+    ) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let result = state.combine_sets(operation, keys)?;
+        let len = result.len();
+        if result.is_empty() {
+            state.remove(&destination);
+        } else {
+            state.insert(destination, Value::Set(result), None);
+        }
+        Ok(len)
+    }
+
+    pub(crate) async fn sscan(
+        &self,
+        key: &Entity,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+    ) -> Result<(u64, Vec<Bytes>), CacheError> {
+        let state = self.shared.state.lock().await;
+        let Some(set) = state.set(key)? else {
+            return Ok((0, vec![]));
+        };
+        let (next, page) = set.scan(cursor, count);
+        let page = page
+            .into_iter()
+            .filter(|member| pattern.is_none_or(|pattern| glob_match(pattern, member)))
+            .cloned()
+            .collect();
+        Ok((next, page))
+    }
+}
+
+impl State {
+    fn set(&self, key: &Entity) -> Result<Option<&TableSet<Bytes>>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn set_mut(&mut self, key: &Entity) -> Result<Option<&mut TableSet<Bytes>>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn combine_sets(
+        &self,
+        operation: SetOperation,
+        keys: &[Entity],
+    ) -> Result<TableSet<Bytes>, CacheError> {
+        let empty = TableSet::new();
+        let sets = keys
+            .iter()
+            .map(|key| Ok(self.set(key)?.unwrap_or(&empty)))
+            .collect::<Result<Vec<_>, CacheError>>()?;
+        let Some((first, rest)) = sets.split_first() else {
+            return Ok(TableSet::new());
+        };
+        let mut result = (*first).clone();
+        for set in rest {
+            match operation {
+                SetOperation::Inter => result.retain(|member| set.contains(member)),
+                SetOperation::Union => result.extend(set.iter().cloned()),
+                SetOperation::Diff => result.retain(|member| !set.contains(member)),
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{string::SetOptions, test_support::key};
+
+    fn members(items: &[&'static str]) -> Vec<Bytes> {
+        items
+            .iter()
+            .map(|item| Bytes::from_static(item.as_bytes()))
+            .collect()
+    }
+
+    fn sorted(mut items: Vec<Bytes>) -> Vec<Bytes> {
+        items.sort();
+        items
+    }
+
+    #[tokio::test]
+    async fn membership() {
+        let db = Db::new();
+
+        assert_eq!(
+            2,
+            db.sadd(key("s"), members(&["a", "b", "a"])).await.unwrap()
+        );
+        assert!(
+            db.sismember(&key("s"), &Bytes::from_static(b"a"))
+                .await
+                .unwrap()
+        );
+        assert_eq!(2, db.scard(&key("s")).await.unwrap());
+        assert_eq!(
+            members(&["a", "b"]),
+            sorted(db.smembers(&key("s")).await.unwrap())
+        );
+
+        assert_eq!(
+            2,
+            db.srem(&key("s"), &members(&["a", "b", "c"]))
+                .await
+                .unwrap()
+        );
+        assert!(db.get(&key("s")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn algebra() {
+        let db = Db::new();
+        db.sadd(key("a"), members(&["1", "2", "3"])).await.unwrap();
+        db.sadd(key("b"), members(&["2", "3", "4"])).await.unwrap();
+        let keys = [key("a"), key("b"), key("missing")];
+
+        let inter = db
+            .set_operation(SetOperation::Inter, &keys[..2])
+            .await
+            .unwrap();
+        assert_eq!(members(&["2", "3"]), sorted(inter));
+        let union = db.set_operation(SetOperation::Union, &keys).await.unwrap();
+        assert_eq!(members(&["1", "2", "3", "4"]), sorted(union));
+        let diff = db.set_operation(SetOperation::Diff, &keys).await.unwrap();
+        assert_eq!(members(&["1"]), sorted(diff));
+
+        let stored = db
+            .set_operation_store(SetOperation::Inter, key("dst"), &keys)
+            .await
+            .unwrap();
+        assert_eq!(0, stored);
+        assert_eq!(0, db.scard(&key("dst")).await.unwrap());
+
+        db.set(
+            key("str"),
+            Entity::Bulk(Bytes::from_static(b"v")),
+            SetOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            db.set_operation(SetOperation::Union, &[key("a"), key("str")])
+                .await,
+            Err(CacheError::WrongType)
+        ));
+    }
+}
@@ -0,0 +1,463 @@
+use std::{cmp::Ordering, ops::Bound};
+
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, Entry, State, Value, entity::Entity, scan::glob_match, table::Table},
+};
+
+use ranked::RankedSet;
+
+mod ranked;
+
+/// A score with a total order, so it can key the B-tree. `NaN` never gets
+/// this far: parsing rejects it and arithmetic that yields it is an error.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Score(pub(crate) f64);
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Members ordered by `(score, member)`, with a side map for score lookups.
+/// The order is kept in a tree that counts its subtrees, so ranks cost
+/// `O(log n)`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SortedSet {
+    scores: Table<Bytes, f64>,
+    ordered: RankedSet<(Score, Bytes)>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ZAddFlags {
+    pub(crate) nx: bool,
+    pub(crate) xx: bool,
+    pub(crate) gt: bool,
+    pub(crate) lt: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ZAddOutcome {
+    Added,
+    Updated,
+    Unchanged,
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum RangeBy {
+    Rank(i64, i64),
+    Score(Bound<f64>, Bound<f64>),
+    Lex(Bound<Bytes>, Bound<Bytes>),
+}
+
+/// A `ZRANGE` query. Bounds are always stored as `(min, max)`, even for
+/// `REV` queries whose arguments arrive as `max min`.
+#[derive(Debug, Clone)]
+pub(crate) struct RangeSpec {
+    pub(crate) by: RangeBy,
+    pub(crate) rev: bool,
+    pub(crate) limit: Option<(usize, Option<usize>)>,
+}
+
+impl SortedSet {
+    pub(crate) fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub(crate) fn score(&self, member: &Bytes) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    pub(crate) fn insert(&mut self, member: Bytes, score: f64) -> bool {
+        // `-0.0 + 0.0` is `0.0`, so both zeros share one spot in the order.
+        let score = score + 0.0;
+        let prev = self.scores.insert(member.clone(), score);
+        if let Some(prev) = prev {
+            self.ordered.remove(&(Score(prev), member.clone()));
+        }
+        self.ordered.insert((Score(score), member));
+        prev.is_none()
+    }
+
+    pub(crate) fn remove(&mut self, member: &Bytes) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => self.ordered.remove(&(Score(score), member.clone())),
+            None => false,
+        }
+    }
+
+    /// Applies one `ZADD` pair under the given conditions.
+    pub(crate) fn add(&mut self, member: Bytes, score: f64, flags: ZAddFlags) -> ZAddOutcome {
+        match self.score(&member) {
+            None if flags.xx => ZAddOutcome::Skipped,
+            None => {
+                self.insert(member, score);
+                ZAddOutcome::Added
+            }
+            Some(_) if flags.nx => ZAddOutcome::Skipped,
+            Some(current) if (flags.gt && score <= current) || (flags.lt && score >= current) => {
+                ZAddOutcome::Skipped
+            }
+            Some(current) if current == score => ZAddOutcome::Unchanged,
+            Some(_) => {
+                self.insert(member, score);
+                ZAddOutcome::Updated
+            }
+        }
+    }
+
+    pub(crate) fn rank(&self, member: &Bytes) -> Option<(usize, f64)> {
+        let score = self.score(member)?;
+        let value = (Score(score), member.clone());
+        let rank = self.ordered.partition_point(|other| *other < value);
+        Some((rank, score))
+    }
+
+    pub(crate) fn range(&self, spec: &RangeSpec) -> Vec<(Bytes, f64)> {
+        let len = self.len();
+        // The matching ranks, counted from the end for `REV` queries.
+        let (from, to) = match &spec.by {
+            RangeBy::Rank(start, stop) => {
+                let Some(bounds) = super::range_bounds(*start, *stop, len) else {
+                    return vec![];
+                };
+                bounds
+            }
+            RangeBy::Score(min, max) => {
+                let from = self
+                    .ordered
+                    .partition_point(|(score, _)| !above(min, &score.0));
+                let to = self
+                    .ordered
+                    .partition_point(|(score, _)| below(max, &score.0));
+                reversed(from, to.max(from), len, spec.rev)
+            }
+            RangeBy::Lex(min, max) => {
+                let from = self
+                    .ordered
+                    .partition_point(|(_, member)| !above(min, member));
+                let to = self
+                    .ordered
+                    .partition_point(|(_, member)| below(max, member));
+                reversed(from, to.max(from), len, spec.rev)
+            }
+        };
+
+        let (offset, count) = match spec.limit {
+            Some((offset, count)) => (offset, count.unwrap_or(usize::MAX)),
+            None => (0, usize::MAX),
+        };
+        let from = from.saturating_add(offset).min(to);
+        self.ordered
+            .iter_from(from, spec.rev)
+            .take((to - from).min(count))
+            .map(|(score, member)| (member.clone(), score.0))
+            .collect()
+    }
+}
+
+/// Turns the ranks `from..to` into ranks counted from the end when `rev`.
+fn reversed(from: usize, to: usize, len: usize, rev: bool) -> (usize, usize) {
+    if rev {
+        (len - to, len - from)
+    } else {
+        (from, to)
+    }
+}
+
+fn above<T: PartialOrd>(min: &Bound<T>, value: &T) -> bool {
+    match min {
+        Bound::Included(min) => value >= min,
+        Bound::Excluded(min) => value > min,
+        Bound::Unbounded => true,
+    }
+}
+
+fn below<T: PartialOrd>(max: &Bound<T>, value: &T) -> bool {
+    match max {
+        Bound::Included(max) => value <= max,
+        Bound::Excluded(max) => value < max,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Parses a score the way Redis does, accepting `inf`/`+inf`/`-inf` but not `NaN`.
+pub(crate) fn parse_score(raw: &[u8]) -> Option<f64> {
+    str::from_utf8(raw)
+        .ok()
+        .and_then(|raw| raw.parse::<f64>().ok())
+        .filter(|score| !score.is_nan())
+}
+
+/// Parses a `ZRANGE ... BYSCORE` bound: a score, optionally prefixed with `(`
+/// to make it exclusive.
+pub(crate) fn parse_score_bound(raw: &[u8]) -> Option<Bound<f64>> {
+    match raw.split_first() {
+        Some((b'(', rest)) => parse_score(rest).map(Bound::Excluded),
+        _ => parse_score(raw).map(Bound::Included),
+    }
+}
+
+/// Parses a `ZRANGE ... BYLEX` bound: `-`, `+`, `[member` or `(member`.
+pub(crate) fn parse_lex_bound(raw: &Bytes) -> Option<Bound<Bytes>> {
+    match raw.first() {
+        Some(b'-' | b'+') if raw.len() == 1 => Some(Bound::Unbounded),
+        Some(b'[') => Some(Bound::Included(raw.slice(1..))),
+        Some(b'(') => Some(Bound::Excluded(raw.slice(1..))),
+        _ => None,
+    }
+}
+
+pub(crate) fn format_score(score: f64) -> Bytes {
+    Bytes::from(score.to_string())
+}
+
+impl Db {
+    pub(crate) async fn zadd(
+        &self,
+        key: Entity,
+        pairs: Vec<(f64, Bytes)>,
+        flags: ZAddFlags,
+    ) -> Result<Vec<ZAddOutcome>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let entry = state.entities.get_or_insert_with(key.clone(), || Entry {
+            data: Value::SortedSet(SortedSet::default()),
+            expires_at: None,
+        });
+        let Value::SortedSet(zset) = &mut entry.data else {
+            return Err(CacheError::WrongType);
+        };
+        let outcomes = pairs
+            .into_iter()
+            .map(|(score, member)| zset.add(member, score, flags))
+            .collect();
+        state.remove_if_empty(&key);
+        Ok(outcomes)
+    }
+
+    /// `ZINCRBY`, and `ZADD ... INCR` when `flags` carry conditions. Returns
+    /// `None` when a condition prevented the update.
+    pub(crate) async fn zincrby(
+        &self,
+        key: Entity,
+        increment: f64,
+        member: Bytes,
+        flags: ZAddFlags,
+    ) -> Result<Option<f64>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let entry = state.entities.get_or_insert_with(key.clone(), || Entry {
+            data: Value::SortedSet(SortedSet::default()),
+            expires_at: None,
+        });
+        let Value::SortedSet(zset) = &mut entry.data else {
+            return Err(CacheError::WrongType);
+        };
+        let score = zset.score(&member).unwrap_or(0.0) + increment;
+        if score.is_nan() {
+            state.remove_if_empty(&key);
+            return Err("ERR resulting score is not a number (NaN)".into());
+        }
+        let outcome = zset.add(member, score, flags);
+        state.remove_if_empty(&key);
+        Ok((outcome != ZAddOutcome::Skipped).then_some(score))
+    }
+
+    pub(crate) async fn zrange(
+        &self,
+        key: &Entity,
+        spec: &RangeSpec,
+    ) -> Result<Vec<(Bytes, f64)>, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state
+            .sorted_set(key)?
+            .map(|zset| zset.range(spec))
+            .unwrap_or_default())
+    }
+
+    pub(crate) async fn zrank(
+        &self,
+        key: &Entity,
+        member: &Bytes,
+    ) -> Result<Option<(usize, f64)>, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state.sorted_set(key)?.and_then(|zset| zset.rank(member)))
+    }
+
+    pub(crate) async fn zrem(&self, key: &Entity, members: &[Bytes]) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let Some(zset) = state.sorted_set_mut(key)? else {
+            return Ok(0);
+        };
+        let removed = members.iter().filter(|member| zset.remove(member)).count();
+        state.remove_if_empty(key);
+        Ok(removed)
+    }
+
+    pub(crate) async fn zscan(
+        &self,
+        key: &Entity,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+    ) -> Result<(u64, Vec<(Bytes, f64)>), CacheError> {
+        let state = self.shared.state.lock().await;
+        let Some(zset) = state.sorted_set(key)? else {
+            return Ok((0, vec![]));
+        };
+        let (next, page) = zset.scores.scan(cursor, count);
+        let page = page
+            .into_iter()
+            .filter(|(member, _)| pattern.is_none_or(|pattern| glob_match(pattern, member)))
+            .map(|(member, score)| (member.clone(), *score))
+            .collect();
+        Ok((next, page))
+    }
+}
+
+impl State {
+    pub(super) fn sorted_set(&self, key: &Entity) -> Result<Option<&SortedSet>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::SortedSet(zset)) => Ok(Some(zset)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn sorted_set_mut(&mut self, key: &Entity) -> Result<Option<&mut SortedSet>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::SortedSet(zset)) => Ok(Some(zset)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(name: &'static str) -> Bytes {
+        Bytes::from_static(name.as_bytes())
+    }
+
+    fn leaderboard() -> SortedSet {
+        let mut zset = SortedSet::default();
+        zset.insert(member("carol"), 30.0);
+        zset.insert(member("alice"), 10.0);
+        zset.insert(member("bob"), 20.0);
+        zset.insert(member("dave"), 20.0);
+        zset
+    }
+
+    fn members(items: Vec<(Bytes, f64)>) -> Vec<Bytes> {
+        items.into_iter().map(|(member, _)| member).collect()
+    }
+
+    #[test]
+    fn add_flags() {
+        let mut zset = leaderboard();
+        let gt = ZAddFlags {
+            gt: true,
+            ..Default::default()
+        };
+        let nx = ZAddFlags {
+            nx: true,
+            ..Default::default()
+        };
+        let xx = ZAddFlags {
+            xx: true,
+            ..Default::default()
+        };
+
+        assert_eq!(ZAddOutcome::Skipped, zset.add(member("alice"), 5.0, gt));
+        assert_eq!(ZAddOutcome::Updated, zset.add(member("alice"), 15.0, gt));
+        assert_eq!(ZAddOutcome::Skipped, zset.add(member("alice"), 1.0, nx));
+        assert_eq!(ZAddOutcome::Skipped, zset.add(member("erin"), 1.0, xx));
+        assert_eq!(ZAddOutcome::Unchanged, zset.add(member("bob"), 20.0, xx));
+        assert_eq!(ZAddOutcome::Added, zset.add(member("erin"), 1.0, nx));
+        assert_eq!(Some((1, 15.0)), zset.rank(&member("alice")));
+    }
+
+    #[test]
+    fn ranges() {
+        let zset = leaderboard();
+        let spec = |by, rev, limit| RangeSpec { by, rev, limit };
+
+        assert_eq!(
+            vec![
+                member("alice"),
+                member("bob"),
+                member("dave"),
+                member("carol")
+            ],
+            members(zset.range(&spec(RangeBy::Rank(0, -1), false, None)))
+        );
+        assert_eq!(
+            vec![member("carol"), member("dave")],
+            members(zset.range(&spec(RangeBy::Rank(0, 1), true, None)))
+        );
+        assert_eq!(
+            vec![member("bob"), member("dave"), member("carol")],
+            members(zset.range(&spec(
+                RangeBy::Score(Bound::Excluded(10.0), Bound::Unbounded),
+                false,
+                None
+            )))
+        );
+        assert_eq!(
+            vec![member("dave")],
+            members(zset.range(&spec(
+                RangeBy::Score(Bound::Included(20.0), Bound::Included(20.0)),
+                true,
+                Some((0, Some(1)))
+            )))
+        );
+
+        let mut names = SortedSet::default();
+        for name in ["alice", "bob", "carol", "dave"] {
+            names.insert(member(name), 0.0);
+        }
+        assert_eq!(
+            vec![member("carol"), member("bob")],
+            members(names.range(&spec(
+                RangeBy::Lex(Bound::Included(member("b")), Bound::Excluded(member("d"))),
+                true,
+                None
+            )))
+        );
+    }
+
+    #[test]
+    fn bounds() {
+        assert_eq!(Some(Bound::Excluded(1.5)), parse_score_bound(b"(1.5"));
+        assert_eq!(
+            Some(Bound::Included(f64::NEG_INFINITY)),
+            parse_score_bound(b"-inf")
+        );
+        assert_eq!(None, parse_score_bound(b"nan"));
+        assert_eq!(Some(Bound::Unbounded), parse_lex_bound(&member("+")));
+        assert_eq!(None, parse_lex_bound(&member("a")));
+    }
+}
@@ -0,0 +1,289 @@
+use std::cmp::Ordering;
+
+type Link<T> = Option<Box<Node<T>>>;
+
+/// An AVL tree whose nodes count their subtree, so that finding the rank of
+/// a value or the value at a rank takes `O(log n)`, where a `BTreeSet` has
+/// to walk every value before it.
+#[derive(Debug, Clone)]
+pub(super) struct RankedSet<T> {
+    root: Link<T>,
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    value: T,
+    left: Link<T>,
+    right: Link<T>,
+    height: u8,
+    /// Values in this subtree, this node's included.
+    size: usize,
+}
+
+impl<T> Default for RankedSet<T> {
+    fn default() -> Self {
+        RankedSet { root: None }
+    }
+}
+
+impl<T: Ord> RankedSet<T> {
+    /// Returns whether `value` is new.
+    pub(super) fn insert(&mut self, value: T) -> bool {
+        let (root, added) = insert(self.root.take(), value);
+        self.root = Some(root);
+        added
+    }
+
+    /// Returns whether `value` was present.
+    pub(super) fn remove(&mut self, value: &T) -> bool {
+        let (root, removed) = remove(self.root.take(), value);
+        self.root = root;
+        removed
+    }
+}
+
+impl<T> RankedSet<T> {
+    /// The number of leading values for which `pred` holds, given that it
+    /// holds for a prefix of the set and for nothing after: the rank of the
+    /// first value failing it.
+    pub(super) fn partition_point(&self, mut pred: impl FnMut(&T) -> bool) -> usize {
+        let mut rank = 0;
+        let mut link = &self.root;
+        while let Some(node) = link {
+            if pred(&node.value) {
+                rank += size(&node.left) + 1;
+                link = &node.right;
+            } else {
+                link = &node.left;
+            }
+        }
+        rank
+    }
+
+    /// The values from `rank` on, in order, or in reverse with `rev`, in which
+    /// case `rank` counts from the last value.
+    pub(super) fn iter_from(&self, rank: usize, rev: bool) -> Iter<'_, T> {
+        let mut iter = Iter { stack: vec![], rev };
+        let mut rank = rank;
+        let mut link = &self.root;
+        while let Some(node) = link {
+            let before = size(node.near(rev));
+            match rank.cmp(&before) {
+                Ordering::Less => {
+                    iter.stack.push(node);
+                    link = node.near(rev);
+                }
+                Ordering::Equal => {
+                    iter.stack.push(node);
+                    break;
+                }
+                Ordering::Greater => {
+                    rank -= before + 1;
+                    link = node.far(rev);
+                }
+            }
+        }
+        iter
+    }
+}
+
+/// An in-order walk. The stack holds the nodes whose value is still to come,
+/// along with their far subtree.
+pub(super) struct Iter<'a, T> {
+    stack: Vec<&'a Node<T>>,
+    rev: bool,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.stack.pop()?;
+        let mut link = node.far(self.rev);
+        while let Some(next) = link {
+            self.stack.push(next);
+            link = next.near(self.rev);
+        }
+        Some(&node.value)
+    }
+}
+
+impl<T> Node<T> {
+    fn leaf(value: T) -> Box<Node<T>> {
+        Box::new(Node {
+            value,
+            left: None,
+            right: None,
+            height: 1,
+            size: 1,
+        })
+    }
+
+    /// The subtree walked first: the left one, or the right one in reverse.
+    fn near(&self, rev: bool) -> &Link<T> {
+        if rev { &self.right } else { &self.left }
+    }
+
+    fn far(&self, rev: bool) -> &Link<T> {
+        if rev { &self.left } else { &self.right }
+    }
+
+    fn update(&mut self) {
+        self.height = 1 + height(&self.left).max(height(&self.right));
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+fn height<T>(link: &Link<T>) -> u8 {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+fn insert<T: Ord>(link: Link<T>, value: T) -> (Box<Node<T>>, bool) {
+    let Some(mut node) = link else {
+        return (Node::leaf(value), true);
+    };
+    let added = match value.cmp(&node.value) {
+        Ordering::Less => {
+            let (left, added) = insert(node.left.take(), value);
+            node.left = Some(left);
+            added
+        }
+        Ordering::Greater => {
+            let (right, added) = insert(node.right.take(), value);
+            node.right = Some(right);
+            added
+        }
+        Ordering::Equal => return (node, false),
+    };
+    (rebalance(node), added)
+}
+
+fn remove<T: Ord>(link: Link<T>, value: &T) -> (Link<T>, bool) {
+    let Some(mut node) = link else {
+        return (None, false);
+    };
+    let removed = match value.cmp(&node.value) {
+        Ordering::Less => {
+            let (left, removed) = remove(node.left.take(), value);
+            node.left = left;
+            removed
+        }
+        Ordering::Greater => {
+            let (right, removed) = remove(node.right.take(), value);
+            node.right = right;
+            removed
+        }
+        Ordering::Equal => {
+            let replacement = match (node.left.take(), node.right.take()) {
+                (None, child) | (child, None) => child,
+                (left, Some(right)) => {
+                    let (right, mut successor) = remove_first(right);
+                    successor.left = left;
+                    successor.right = right;
+                    Some(rebalance(successor))
+                }
+            };
+            return (replacement, true);
+        }
+    };
+    (Some(rebalance(node)), removed)
+}
+
+/// Detaches the smallest node of a subtree. Returns the rest and that node.
+fn remove_first<T>(mut node: Box<Node<T>>) -> (Link<T>, Box<Node<T>>) {
+    match node.left.take() {
+        None => (node.right.take(), node),
+        Some(left) => {
+            let (left, first) = remove_first(left);
+            node.left = left;
+            (Some(rebalance(node)), first)
+        }
+    }
+}
+
+/// Restores the AVL balance of a node whose subtrees differ in height by at
+/// most two, and refreshes its height and size.
+fn rebalance<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    node.update();
+    let left = height(&node.left);
+    let right = height(&node.right);
+    if left > right + 1 {
+        let child = node.left.as_ref().expect("taller side");
+        if height(&child.right) > height(&child.left) {
+            let rotated = rotate_left(node.left.take().expect("taller side"));
+            node.left = Some(rotated);
+        }
+        rotate_right(node)
+    } else if right > left + 1 {
+        let child = node.right.as_ref().expect("taller side");
+        if height(&child.left) > height(&child.right) {
+            let rotated = rotate_right(node.right.take().expect("taller side"));
+            node.right = Some(rotated);
+        }
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
+fn rotate_left<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut pivot = node.right.take().expect("a right child");
+    node.right = pivot.left.take();
+    node.update();
+    pivot.left = Some(node);
+    pivot.update();
+    pivot
+}
+
+fn rotate_right<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut pivot = node.left.take().expect("a left child");
+    node.left = pivot.right.take();
+    node.update();
+    pivot.right = Some(node);
+    pivot.update();
+    pivot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_match_a_sorted_vec() {
+        let mut set = RankedSet::default();
+        let mut expected = vec![];
+        // A fixed shuffle of 0..500, then every third value removed.
+        for i in 0..500u32 {
+            let value = (i * 263) % 500;
+            assert!(set.insert(value));
+            expected.push(value);
+        }
+        assert!(!set.insert(7));
+        for value in (0..500).step_by(3) {
+            assert!(set.remove(&value));
+            expected.retain(|v| *v != value);
+        }
+        assert!(!set.remove(&0));
+        expected.sort_unstable();
+
+        assert_eq!(expected.len(), size(&set.root));
+        assert!(height(&set.root) <= 12);
+        for (rank, value) in expected.iter().enumerate() {
+            assert_eq!(rank, set.partition_point(|v| v < value));
+            assert_eq!(Some(value), set.iter_from(rank, false).next());
+        }
+        let all: Vec<_> = set.iter_from(0, false).copied().collect();
+        assert_eq!(expected, all);
+        let tail: Vec<_> = set.iter_from(2, true).take(3).copied().collect();
+        let len = expected.len();
+        assert_eq!(
+            vec![expected[len - 3], expected[len - 4], expected[len - 5]],
+            tail
+        );
+        assert_eq!(None, set.iter_from(len, false).next());
+    }
+}
@@ -0,0 +1,367 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    ops::Bound,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, Entry, State, Value, entity::Entity, stream::group::ConsumerGroup},
+};
+
+pub(crate) mod group;
+
+/// A `<milliseconds>-<sequence>` stream entry ID.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct StreamId {
+    pub(crate) ms: u64,
+    pub(crate) seq: u64,
+}
+
+impl StreamId {
+    pub(crate) const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+
+    /// Parses `ms-seq`, or a bare `ms` with `default_seq` as its sequence.
+    pub(crate) fn parse(raw: &[u8], default_seq: u64) -> Option<StreamId> {
+        let raw = str::from_utf8(raw).ok()?;
+        match raw.split_once('-') {
+            Some((ms, seq)) => Some(StreamId {
+                ms: ms.parse().ok()?,
+                seq: seq.parse().ok()?,
+            }),
+            None => Some(StreamId {
+                ms: raw.parse().ok()?,
+                seq: default_seq,
+            }),
+        }
+    }
+
+    fn next(self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId { ms: self.ms, seq }),
+            None => self.ms.checked_add(1).map(|ms| StreamId { ms, seq: 0 }),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+pub(crate) type StreamFields = Vec<(Bytes, Bytes)>;
+
+/// The ID argument of `XADD`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum XAddId {
+    /// `*`: both parts generated.
+    Auto,
+    /// `ms-*`: the sequence is generated.
+    AutoSeq(u64),
+    Explicit(StreamId),
+}
+
+impl XAddId {
+    pub(crate) fn parse(raw: &[u8]) -> Option<XAddId> {
+        if raw == b"*" {
+            return Some(XAddId::Auto);
+        }
+        if let Some(ms) = raw.strip_suffix(b"-*") {
+            return str::from_utf8(ms).ok()?.parse().ok().map(XAddId::AutoSeq);
+        }
+        StreamId::parse(raw, 0).map(XAddId::Explicit)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum TrimStrategy {
+    MaxLen(usize),
+    MinId(StreamId),
+}
+
+/// `MAXLEN`/`MINID` trimming. Approximate (`~`) trimming is honoured exactly,
+/// which Redis permits; `limit` caps how many entries one call may evict.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Trim {
+    pub(crate) strategy: TrimStrategy,
+    pub(crate) limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Stream {
+    entries: BTreeMap<StreamId, StreamFields>,
+    last_id: StreamId,
+    groups: HashMap<Bytes, ConsumerGroup>,
+}
+
+impl Stream {
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn add(&mut self, id: XAddId, fields: StreamFields) -> Result<StreamId, CacheError> {
+        let id = match id {
+            XAddId::Auto => {
+                let now = unix_millis();
+                if now > self.last_id.ms {
+                    StreamId { ms: now, seq: 0 }
+                } else {
+                    self.last_id
+                        .next()
+                        .ok_or("ERR The stream has exhausted the last possible ID, unable to add more items")?
+                }
+            }
+            XAddId::AutoSeq(ms) if ms == self.last_id.ms => self
+                .last_id
+                .next()
+                .filter(|id| id.ms == ms)
+                .ok_or(TOO_SMALL)?,
+            XAddId::AutoSeq(ms) => StreamId {
+                ms,
+                seq: (ms == 0) as u64,
+            },
+            XAddId::Explicit(id) => id,
+        };
+        if id == StreamId::MIN {
+            return Err("ERR The ID specified in XADD must be greater than 0-0".into());
+        }
+        if id <= self.last_id {
+            return Err(TOO_SMALL.into());
+        }
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        Ok(id)
+    }
+
+    fn trim(&mut self, trim: Trim) -> usize {
+        let limit = trim.limit.unwrap_or(usize::MAX);
+        let mut removed = 0;
+        while removed < limit {
+            let len = self.entries.len();
+            let Some(entry) = self.entries.first_entry() else {
+                break;
+            };
+            let evict = match trim.strategy {
+                TrimStrategy::MaxLen(max_len) => len > max_len,
+                TrimStrategy::MinId(min_id) => *entry.key() < min_id,
+            };
+            if !evict {
+                break;
+            }
+            entry.remove();
+            removed += 1;
+        }
+        removed
+    }
+
+    pub(crate) fn range(
+        &self,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+    ) -> impl DoubleEndedIterator<Item = (&StreamId, &StreamFields)> {
+        let empty = match (start, end) {
+            (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e)) => {
+                s > e
+                    || (s == e
+                        && (matches!(start, Bound::Excluded(_))
+                            || matches!(end, Bound::Excluded(_))))
+            }
+            _ => false,
+        };
+        // An empty `BTreeMap::range` panics on inverted bounds, so short-circuit.
+        let bounds = if empty {
+            (
+                Bound::Excluded(StreamId::MIN),
+                Bound::Included(StreamId::MIN),
+            )
+        } else {
+            (start, end)
+        };
+        self.entries.range(bounds)
+    }
+}
+
+pub(crate) fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+const TOO_SMALL: &str =
+    "ERR The ID specified in XADD is equal or smaller than the target stream top item";
+
+impl Db {
+    /// Appends an entry, creating the stream unless `no_mkstream` is set.
+    /// Returns `None` when the stream is missing and may not be created.
+    pub(crate) async fn xadd(
+        &self,
+        key: Entity,
+        id: XAddId,
+        fields: StreamFields,
+        no_mkstream: bool,
+        trim: Option<Trim>,
+    ) -> Result<Option<StreamId>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let stream = match state.stream_mut(&key)? {
+            Some(stream) => stream,
+            None if no_mkstream => return Ok(None),
+            None => {
+                // Validate against a fresh stream first so a rejected ID
+                // does not leave an empty stream behind.
+                let mut stream = Stream::default();
+                let id = stream.add(id, fields)?;
+                if let Some(trim) = trim {
+                    stream.trim(trim);
+                }
+                let entry = Entry {
+                    data: Value::Stream(stream),
+                    expires_at: None,
+                };
+                state.entities.insert(key, entry);
+                drop(state);
+                self.shared.stream_written.notify_waiters();
+                return Ok(Some(id));
+            }
+        };
+        let id = stream.add(id, fields)?;
+        if let Some(trim) = trim {
+            stream.trim(trim);
+        }
+        drop(state);
+        self.shared.stream_written.notify_waiters();
+        Ok(Some(id))
+    }
+
+    pub(crate) async fn xrange(
+        &self,
+        key: &Entity,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: Option<usize>,
+        rev: bool,
+    ) -> Result<Vec<(StreamId, StreamFields)>, CacheError> {
+        let state = self.shared.state.lock().await;
+        let Some(stream) = state.stream(key)? else {
+            return Ok(vec![]);
+        };
+        let count = count.unwrap_or(usize::MAX);
+        let entries = stream.range(start, end);
+        let entries: Box<dyn Iterator<Item = _>> = if rev {
+            Box::new(entries.rev())
+        } else {
+            Box::new(entries)
+        };
+        Ok(entries
+            .take(count)
+            .map(|(id, fields)| (*id, fields.clone()))
+            .collect())
+    }
+
+    pub(crate) async fn xlen(&self, key: &Entity) -> Result<usize, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state.stream(key)?.map(Stream::len).unwrap_or(0))
+    }
+
+    pub(crate) async fn xtrim(&self, key: &Entity, trim: Trim) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        Ok(state
+            .stream_mut(key)?
+            .map(|stream| stream.trim(trim))
+            .unwrap_or(0))
+    }
+}
+
+impl State {
+    fn stream(&self, key: &Entity) -> Result<Option<&Stream>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn stream_mut(&mut self, key: &Entity) -> Result<Option<&mut Stream>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    fn fields() -> StreamFields {
+        vec![(Bytes::from_static(b"k"), Bytes::from_static(b"v"))]
+    }
+
+    #[test]
+    fn ids() {
+        assert_eq!(Some(id(5, 0)), StreamId::parse(b"5", 0));
+        assert_eq!(Some(id(5, 3)), StreamId::parse(b"5-3", 0));
+        assert_eq!(None, StreamId::parse(b"5-x", 0));
+        assert!(matches!(XAddId::parse(b"*"), Some(XAddId::Auto)));
+        assert!(matches!(XAddId::parse(b"7-*"), Some(XAddId::AutoSeq(7))));
+        assert_eq!("5-3", id(5, 3).to_string());
+    }
+
+    #[test]
+    fn add_is_monotonic() {
+        let mut stream = Stream::default();
+
+        assert!(stream.add(XAddId::Explicit(id(0, 0)), fields()).is_err());
+        assert_eq!(id(0, 1), stream.add(XAddId::AutoSeq(0), fields()).unwrap());
+        assert_eq!(id(5, 0), stream.add(XAddId::AutoSeq(5), fields()).unwrap());
+        assert_eq!(id(5, 1), stream.add(XAddId::AutoSeq(5), fields()).unwrap());
+        assert!(stream.add(XAddId::Explicit(id(5, 1)), fields()).is_err());
+        assert!(stream.add(XAddId::AutoSeq(4), fields()).is_err());
+
+        let auto = stream.add(XAddId::Auto, fields()).unwrap();
+        assert!(auto > id(5, 1));
+    }
+
+    #[test]
+    fn trim_and_range() {
+        let mut stream = Stream::default();
+        for ms in 1..=10 {
+            stream.add(XAddId::Explicit(id(ms, 0)), fields()).unwrap();
+        }
+
+        let removed = stream.trim(Trim {
+            strategy: TrimStrategy::MaxLen(8),
+            limit: None,
+        });
+        assert_eq!(2, removed);
+
+        let removed = stream.trim(Trim {
+            strategy: TrimStrategy::MinId(id(8, 0)),
+            limit: Some(3),
+        });
+        assert_eq!(3, removed);
+        assert_eq!(5, stream.len());
+
+        let ids: Vec<_> = stream
+            .range(Bound::Excluded(id(6, 0)), Bound::Included(id(9, 0)))
+            .rev()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(vec![id(9, 0), id(8, 0), id(7, 0)], ids);
+        assert_eq!(
+            0,
+            stream
+                .range(Bound::Included(id(9, 0)), Bound::Excluded(id(9, 0)))
+                .count()
+        );
+    }
+}
@@ -0,0 +1,736 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    ops::Bound,
+};
+
+use bytes::Bytes;
+use tokio::sync::futures::Notified;
+
+use crate::{
+    error::CacheError,
+    storage::{
+        Db, Entry, State, Value,
+        entity::Entity,
+        stream::{Stream, StreamFields, StreamId, unix_millis},
+    },
+};
+
+/// Delivery state of one consumer group: how far it has read and which
+/// delivered entries are still waiting for an `XACK` (the pending entries
+/// list, or PEL).
+#[derive(Debug, Clone)]
+pub(crate) struct ConsumerGroup {
+    last_delivered: StreamId,
+    pending: BTreeMap<StreamId, PendingEntry>,
+    consumers: HashSet<Bytes>,
+}
+
+#[derive(Debug, Clone)]
+struct PendingEntry {
+    consumer: Bytes,
+    delivered_at: u64,
+    delivery_count: u64,
+}
+
+/// Where a group starts reading: an explicit ID, or `$` for the stream's
+/// current last entry.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum GroupStart {
+    Id(StreamId),
+    Last,
+}
+
+/// The per-stream ID argument of `XREADGROUP`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReadId {
+    /// `>`: entries never delivered to any consumer of the group.
+    New,
+    /// The consumer's own pending entries after this ID.
+    Pending(StreamId),
+}
+
+/// A pending entry as returned by the extended form of `XPENDING`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PendingDetail {
+    pub(crate) id: StreamId,
+    pub(crate) consumer: Bytes,
+    pub(crate) idle: u64,
+    pub(crate) delivery_count: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PendingSummary {
+    pub(crate) count: usize,
+    pub(crate) bounds: Option<(StreamId, StreamId)>,
+    pub(crate) consumers: Vec<(Bytes, usize)>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PendingQuery {
+    pub(crate) min_idle: u64,
+    pub(crate) start: Bound<StreamId>,
+    pub(crate) end: Bound<StreamId>,
+    pub(crate) count: usize,
+    pub(crate) consumer: Option<Bytes>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ClaimOptions {
+    pub(crate) idle: Option<u64>,
+    pub(crate) time: Option<u64>,
+    pub(crate) retry_count: Option<u64>,
+    pub(crate) force: bool,
+    pub(crate) just_id: bool,
+    pub(crate) last_id: Option<StreamId>,
+}
+
+pub(crate) type ReadResult = Vec<(Entity, Vec<(StreamId, Option<StreamFields>)>)>;
+
+impl ConsumerGroup {
+    fn new(last_delivered: StreamId) -> ConsumerGroup {
+        ConsumerGroup {
+            last_delivered,
+            pending: BTreeMap::new(),
+            consumers: HashSet::new(),
+        }
+    }
+
+    /// Registers `consumer`, returning whether it is new to the group.
+    fn touch(&mut self, consumer: &Bytes) -> bool {
+        self.consumers.insert(consumer.clone())
+    }
+
+    /// Hands `id` to `consumer`, as a fresh delivery or a claim.
+    fn assign(&mut self, id: StreamId, consumer: &Bytes, delivered_at: u64, delivery_count: u64) {
+        self.pending.insert(
+            id,
+            PendingEntry {
+                consumer: consumer.clone(),
+                delivered_at,
+                delivery_count,
+            },
+        );
+    }
+}
+
+impl Stream {
+    fn start_id(&self, start: GroupStart) -> StreamId {
+        match start {
+            GroupStart::Id(id) => id,
+            GroupStart::Last => self.last_id,
+        }
+    }
+
+    /// Reads for `consumer` of `group`, updating the PEL. Returns `None` when
+    /// a `>` read found nothing new, so a blocking caller knows to wait.
+    fn read_group(
+        &mut self,
+        group: &Bytes,
+        consumer: &Bytes,
+        id: ReadId,
+        count: usize,
+        no_ack: bool,
+    ) -> Option<Vec<(StreamId, Option<StreamFields>)>> {
+        let Stream {
+            entries, groups, ..
+        } = self;
+        let group = groups.get_mut(group)?;
+        let now = unix_millis();
+        group.touch(consumer);
+
+        match id {
+            ReadId::New => {
+                let read: Vec<_> = entries
+                    .range((Bound::Excluded(group.last_delivered), Bound::Unbounded))
+                    .take(count)
+                    .map(|(id, fields)| (*id, Some(fields.clone())))
+                    .collect();
+                let (last, _) = read.last()?;
+                group.last_delivered = *last;
+                if !no_ack {
+                    for (id, _) in &read {
+                        group.assign(*id, consumer, now, 1);
+                    }
+                }
+                Some(read)
+            }
+            // Like Redis, reading history counts as another delivery of each
+            // entry that still exists.
+            ReadId::Pending(after) => Some(
+                group
+                    .pending
+                    .range_mut((Bound::Excluded(after), Bound::Unbounded))
+                    .filter(|(_, pending)| pending.consumer == consumer)
+                    .take(count)
+                    .map(|(id, pending)| {
+                        let fields = entries.get(id).cloned();
+                        if fields.is_some() {
+                            pending.delivered_at = now;
+                            pending.delivery_count += 1;
+                        }
+                        (*id, fields)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn no_group(key: &Entity, group: &Bytes) -> CacheError {
+    format!(
+        "NOGROUP No such key '{}' or consumer group '{}'",
+        key,
+        String::from_utf8_lossy(group)
+    )
+    .into()
+}
+
+impl Db {
+    /// Resolves once an `XADD` has happened since the future was enabled.
+    pub(crate) fn stream_written(&self) -> Notified<'_> {
+        self.shared.stream_written.notified()
+    }
+
+    pub(crate) async fn xgroup_create(
+        &self,
+        key: Entity,
+        group: Bytes,
+        start: GroupStart,
+        mkstream: bool,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let stream = match state.stream_mut(&key)? {
+            Some(stream) => stream,
+            None if mkstream => {
+                let entry = state.entities.get_or_insert_with(key, || Entry {
+                    data: Value::Stream(Stream::default()),
+                    expires_at: None,
+                });
+                let Value::Stream(stream) = &mut entry.data else {
+                    unreachable!("the key was just checked to be absent");
+                };
+                stream
+            }
+            None => {
+                return Err("ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.".into());
+            }
+        };
+        if stream.groups.contains_key(&group) {
+            return Err("BUSYGROUP Consumer Group name already exists".into());
+        }
+        let start = stream.start_id(start);
+        stream.groups.insert(group, ConsumerGroup::new(start));
+        Ok(())
+    }
+
+    pub(crate) async fn xgroup_destroy(
+        &self,
+        key: &Entity,
+        group: &Bytes,
+    ) -> Result<bool, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let stream = state.existing_stream_mut(key)?;
+        Ok(stream.groups.remove(group).is_some())
+    }
+
+    pub(crate) async fn xgroup_create_consumer(
+        &self,
+        key: &Entity,
+        group: &Bytes,
+        consumer: &Bytes,
+    ) -> Result<bool, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let group = state.group_mut(key, group)?;
+        Ok(group.touch(consumer))
+    }
+
+    /// Removes a consumer together with its pending entries, returning how
+    /// many entries were still pending.
+    pub(crate) async fn xgroup_del_consumer(
+        &self,
+        key: &Entity,
+        group: &Bytes,
+        consumer: &Bytes,
+    ) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let group = state.group_mut(key, group)?;
+        if !group.consumers.remove(consumer) {
+            return Ok(0);
+        }
+        let before = group.pending.len();
+        group
+            .pending
+            .retain(|_, pending| pending.consumer != consumer);
+        Ok(before - group.pending.len())
+    }
+
+    pub(crate) async fn xgroup_setid(
+        &self,
+        key: &Entity,
+        group: &Bytes,
+        start: GroupStart,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let stream = state.existing_stream_mut(key)?;
+        let start = stream.start_id(start);
+        let group = stream
+            .groups
+            .get_mut(group)
+            .ok_or_else(|| no_group(key, group))?;
+        group.last_delivered = start;
+        Ok(())
+    }
+
+    /// Serves an `XREADGROUP` across several streams under one lock. Streams
+    /// read with `>` that had nothing new are left out of the result.
+    pub(crate) async fn xreadgroup(
+        &self,
+        group: &Bytes,
+        consumer: &Bytes,
+        streams: &[(Entity, ReadId)],
+        count: Option<usize>,
+        no_ack: bool,
+    ) -> Result<ReadResult, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        for (key, _) in streams {
+            state.group_mut(key, group)?;
+        }
+
+        let count = count.unwrap_or(usize::MAX);
+        let mut result = vec![];
+        for (key, id) in streams {
+            let stream = state.existing_stream_mut(key)?;
+            if let Some(entries) = stream.read_group(group, consumer, *id, count, no_ack) {
+                result.push((key.clone(), entries));
+            }
+        }
+        Ok(result)
+    }
+
+    pub(crate) async fn xack(
+        &self,
+        key: &Entity,
+        group: &Bytes,
+        ids: &[StreamId],
+    ) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let group = match state.stream_mut(key)? {
+            Some(stream) => match stream.groups.get_mut(group) {
+                Some(group) => group,
+                None => return Ok(0),
+            },
+            None => return Ok(0),
+        };
+        Ok(ids
+            .iter()
+            .filter(|id| group.pending.remove(id).is_some())
+            .count())
+    }
+
+    pub(crate) async fn xpending_summary(
+        &self,
+        key: &Entity,
+        group: &Bytes,
+    ) -> Result<PendingSummary, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let group = state.group_mut(key, group)?;
+
+        let mut per_consumer: BTreeMap<Bytes, usize> = BTreeMap::new();
+        for pending in group.pending.values() {
+            *per_consumer.entry(pending.consumer.clone()).or_default() += 1;
+        }
+        let bounds = group
+            .pending
+            .first_key_value()
+            .zip(group.pending.last_key_value())
+            .map(|((first, _), (last, _))| (*first, *last));
+        Ok(PendingSummary {
+            count: group.pending.len(),
+            bounds,
+            consumers: per_consumer.into_iter().collect(),
+        })
+    }
+
+    pub(crate) async fn xpending(
+        &self,
+        key: &Entity,
+        group: &Bytes,
+        query: &PendingQuery,
+    ) -> Result<Vec<PendingDetail>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let group = state.group_mut(key, group)?;
+        if range_is_empty(query.start, query.end) {
+            return Ok(vec![]);
+        }
+        let now = unix_millis();
+        Ok(group
+            .pending
+            .range((query.start, query.end))
+            .map(|(id, pending)| PendingDetail {
+                id: *id,
+                consumer: pending.consumer.clone(),
+                idle: now.saturating_sub(pending.delivered_at),
+                delivery_count: pending.delivery_count,
+            })
+            .filter(|detail| detail.idle >= query.min_idle)
+            .filter(|detail| {
+                query
+                    .consumer
+                    .as_ref()
+                    .is_none_or(|consumer| *consumer == detail.consumer)
+            })
+            .take(query.count)
+            .collect())
+    }
+
+    /// Transfers ownership of pending entries idle for at least `min_idle`
+    /// milliseconds to `consumer`. Entries deleted from the stream are dropped
+    /// from the PEL instead of being claimed.
+    pub(crate) async fn xclaim(
+        &self,
+        key: &Entity,
+        group: &Bytes,
+        consumer: &Bytes,
+        min_idle: u64,
+        ids: &[StreamId],
+        options: ClaimOptions,
+    ) -> Result<Vec<(StreamId, StreamFields)>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let stream = state.existing_stream_mut(key)?;
+        let Stream {
+            entries, groups, ..
+        } = stream;
+        let group = groups.get_mut(group).ok_or_else(|| no_group(key, group))?;
+
+        let now = unix_millis();
+        let delivered_at = match (options.time, options.idle) {
+            (Some(time), _) => time,
+            (None, Some(idle)) => now.saturating_sub(idle),
+            (None, None) => now,
+        };
+        group.touch(consumer);
+        if let Some(last_id) = options.last_id
+            && last_id > group.last_delivered
+        {
+            group.last_delivered = last_id;
+        }
+
+        let mut claimed = vec![];
+        for id in ids {
+            let Some(fields) = entries.get(id) else {
+                group.pending.remove(id);
+                continue;
+            };
+            let delivery_count = match group.pending.get(id) {
+                Some(pending) if now.saturating_sub(pending.delivered_at) < min_idle => continue,
+                Some(pending) => pending.delivery_count,
+                None if options.force => 0,
+                None => continue,
+            };
+            let delivery_count = match options.retry_count {
+                Some(retry_count) => retry_count,
+                None if options.just_id => delivery_count,
+                None => delivery_count + 1,
+            };
+            group.assign(*id, consumer, delivered_at, delivery_count);
+            claimed.push((*id, fields.clone()));
+        }
+        Ok(claimed)
+    }
+
+    /// Scans the PEL from `start`, claiming up to `count` entries idle for at
+    /// least `min_idle` milliseconds. Returns the cursor to continue from
+    /// (`0-0` when the scan is complete), the claimed entries and the IDs of
+    /// pending entries that no longer exist in the stream.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn xautoclaim(
+        &self,
+        key: &Entity,
+        group: &Bytes,
+        consumer: &Bytes,
+        min_idle: u64,
+        start: StreamId,
+        count: usize,
+        just_id: bool,
+    ) -> Result<(StreamId, Vec<(StreamId, StreamFields)>, Vec<StreamId>), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let stream = state.existing_stream_mut(key)?;
+        let Stream {
+            entries, groups, ..
+        } = stream;
+        let group = groups.get_mut(group).ok_or_else(|| no_group(key, group))?;
+
+        let now = unix_millis();
+        group.touch(consumer);
+
+        // Like Redis, bound the work of one call by scanning at most ten
+        // times `count` pending entries.
+        let mut attempts = count.saturating_mul(10);
+        let mut claimed = vec![];
+        let mut deleted = vec![];
+        let mut cursor = StreamId::MIN;
+        let mut next = group.pending.range(start..).next().map(|(id, _)| *id);
+        while let Some(id) = next {
+            if claimed.len() == count || attempts == 0 {
+                cursor = id;
+                break;
+            }
+            attempts -= 1;
+            next = group
+                .pending
+                .range((Bound::Excluded(id), Bound::Unbounded))
+                .next()
+                .map(|(id, _)| *id);
+            let Some(fields) = entries.get(&id) else {
+                group.pending.remove(&id);
+                deleted.push(id);
+                continue;
+            };
+            let pending = &group.pending[&id];
+            if now.saturating_sub(pending.delivered_at) < min_idle {
+                continue;
+            }
+            let delivery_count = pending.delivery_count + (!just_id) as u64;
+            group.assign(id, consumer, now, delivery_count);
+            claimed.push((id, fields.clone()));
+        }
+        Ok((cursor, claimed, deleted))
+    }
+}
+
+fn range_is_empty(start: Bound<StreamId>, end: Bound<StreamId>) -> bool {
+    match (start, end) {
+        (Bound::Included(s), Bound::Included(e)) => s > e,
+        (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e)) => {
+            s >= e
+        }
+        _ => false,
+    }
+}
+
+impl State {
+    /// Like `stream_mut`, but a missing key is an error rather than `None`.
+    fn existing_stream_mut(&mut self, key: &Entity) -> Result<&mut Stream, CacheError> {
+        self.stream_mut(key)?
+            .ok_or_else(|| format!("ERR no such key '{}'", key).into())
+    }
+
+    fn group_mut(&mut self, key: &Entity, group: &Bytes) -> Result<&mut ConsumerGroup, CacheError> {
+        self.stream_mut(key)?
+            .and_then(|stream| stream.groups.get_mut(group))
+            .ok_or_else(|| no_group(key, group))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{stream::XAddId, test_support::key};
+
+    fn name(name: &'static str) -> Bytes {
+        Bytes::from_static(name.as_bytes())
+    }
+
+    fn id(ms: u64) -> StreamId {
+        StreamId { ms, seq: 0 }
+    }
+
+    async fn stream_with_group(db: &Db, entries: u64) {
+        db.xgroup_create(
+            key("events"),
+            name("workers"),
+            GroupStart::Id(StreamId::MIN),
+            true,
+        )
+        .await
+        .unwrap();
+        for ms in 1..=entries {
+            let fields = vec![(name("n"), Bytes::from(ms.to_string()))];
+            db.xadd(key("events"), XAddId::Explicit(id(ms)), fields, false, None)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn read_ack_pending() {
+        let db = Db::new();
+        stream_with_group(&db, 3).await;
+        let streams = [(key("events"), ReadId::New)];
+
+        let read = db
+            .xreadgroup(&name("workers"), &name("alice"), &streams, Some(2), false)
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![id(1), id(2)],
+            read[0].1.iter().map(|(id, _)| *id).collect::<Vec<_>>()
+        );
+
+        let read = db
+            .xreadgroup(&name("workers"), &name("bob"), &streams, None, false)
+            .await
+            .unwrap();
+        assert_eq!(1, read[0].1.len());
+        let read = db
+            .xreadgroup(&name("workers"), &name("bob"), &streams, None, false)
+            .await
+            .unwrap();
+        assert!(read.is_empty());
+
+        let history = [(key("events"), ReadId::Pending(StreamId::MIN))];
+        let read = db
+            .xreadgroup(&name("workers"), &name("alice"), &history, None, false)
+            .await
+            .unwrap();
+        assert_eq!(2, read[0].1.len());
+        let query = PendingQuery {
+            min_idle: 0,
+            start: Bound::Unbounded,
+            end: Bound::Unbounded,
+            count: 10,
+            consumer: Some(name("alice")),
+        };
+        let pending = db
+            .xpending(&key("events"), &name("workers"), &query)
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![2, 2],
+            pending
+                .iter()
+                .map(|detail| detail.delivery_count)
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            1,
+            db.xack(&key("events"), &name("workers"), &[id(1), id(9)])
+                .await
+                .unwrap()
+        );
+        let summary = db
+            .xpending_summary(&key("events"), &name("workers"))
+            .await
+            .unwrap();
+        assert_eq!(2, summary.count);
+        assert_eq!(Some((id(2), id(3))), summary.bounds);
+        assert_eq!(
+            vec![(name("alice"), 1), (name("bob"), 1)],
+            summary.consumers
+        );
+    }
+
+    #[tokio::test]
+    async fn claim_from_crashed_consumer() {
+        let db = Db::new();
+        stream_with_group(&db, 3).await;
+        db.xreadgroup(
+            &name("workers"),
+            &name("alice"),
+            &[(key("events"), ReadId::New)],
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let claimed = db
+            .xclaim(
+                &key("events"),
+                &name("workers"),
+                &name("bob"),
+                60_000,
+                &[id(1)],
+                ClaimOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert!(claimed.is_empty(), "entry is not idle long enough yet");
+
+        let options = ClaimOptions {
+            idle: Some(0),
+            ..Default::default()
+        };
+        let claimed = db
+            .xclaim(
+                &key("events"),
+                &name("workers"),
+                &name("bob"),
+                0,
+                &[id(1)],
+                options,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![id(1)],
+            claimed.iter().map(|(id, _)| *id).collect::<Vec<_>>()
+        );
+
+        let query = PendingQuery {
+            min_idle: 0,
+            start: Bound::Unbounded,
+            end: Bound::Unbounded,
+            count: 10,
+            consumer: Some(name("bob")),
+        };
+        let pending = db
+            .xpending(&key("events"), &name("workers"), &query)
+            .await
+            .unwrap();
+        assert_eq!(1, pending.len());
+        assert_eq!(2, pending[0].delivery_count);
+
+        db.xtrim(
+            &key("events"),
+            crate::storage::stream::Trim {
+                strategy: crate::storage::stream::TrimStrategy::MinId(id(3)),
+                limit: None,
+            },
+        )
+        .await
+        .unwrap();
+        let (cursor, claimed, deleted) = db
+            .xautoclaim(
+                &key("events"),
+                &name("workers"),
+                &name("carol"),
+                0,
+                StreamId::MIN,
+                10,
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(StreamId::MIN, cursor);
+        assert_eq!(
+            vec![id(3)],
+            claimed.iter().map(|(id, _)| *id).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![id(1), id(2)], deleted);
+    }
+
+    #[tokio::test]
+    async fn missing_group() {
+        let db = Db::new();
+        let err = db
+            .xreadgroup(
+                &name("g"),
+                &name("c"),
+                &[(key("events"), ReadId::New)],
+                None,
+                false,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("NOGROUP"));
+
+        stream_with_group(&db, 0).await;
+        let err = db
+            .xgroup_create(key("events"), name("workers"), GroupStart::Last, false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("BUSYGROUP"));
+    }
+}
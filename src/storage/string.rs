@@ -0,0 +1,487 @@
+use std::mem;
+
+use bytes::{Bytes, BytesMut};
+use tokio::time::Instant;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, Entry, State, Value, entity::Entity, range_bounds, sorted_set::format_score},
+};
+
+const NOT_AN_INTEGER: &str = "ERR value is not an integer or out of range";
+
+/// Redis caps strings at 512 MB.
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+const TOO_LONG: &str = "ERR string exceeds maximum allowed size (proto-max-bulk-len)";
+
+/// How `GETEX` changes the expiration of the key it reads, or which one
+/// `SET` gives the key it writes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Expiry {
+    Keep,
+    Persist,
+    At(Instant),
+}
+
+/// The `SET` conditions: `NX` only creates the key, `XX` only replaces it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SetCondition {
+    Nx,
+    Xx,
+}
+
+/// `SET` modifiers. The default writes unconditionally and drops any TTL.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SetOptions {
+    pub(crate) condition: Option<SetCondition>,
+    /// `KEEPTTL` is `Expiry::Keep`.
+    pub(crate) expiry: Expiry,
+    /// `GET`: return the previous string.
+    pub(crate) get: bool,
+}
+
+impl Default for SetOptions {
+    fn default() -> Self {
+        SetOptions {
+            condition: None,
+            expiry: Expiry::Persist,
+            get: false,
+        }
+    }
+}
+
+impl Db {
+    /// Adds `increment` to the integer stored at `key`, treating a missing key
+    /// as `0`. The key keeps its time to live.
+    pub(crate) async fn incr_by(&self, key: Entity, increment: i64) -> Result<i64, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let current = match state.string(&key)? {
+            Some(value) => str::from_utf8(&value)
+                .ok()
+                .and_then(|value| value.parse::<i64>().ok())
+                .ok_or(NOT_AN_INTEGER)?,
+            None => 0,
+        };
+        let value = current
+            .checked_add(increment)
+            .ok_or("ERR increment or decrement would overflow")?;
+        state.put_string(key, Entity::Bulk(Bytes::from(value.to_string())));
+        Ok(value)
+    }
+
+    /// Float counterpart of [`Db::incr_by`]; returns the new value as stored.
+    pub(crate) async fn incr_by_float(
+        &self,
+        key: Entity,
+        increment: f64,
+    ) -> Result<Bytes, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let current = match state.string(&key)? {
+            Some(value) => str::from_utf8(&value)
+                .ok()
+                .and_then(|value| value.parse::<f64>().ok())
+                .filter(|value| value.is_finite())
+                .ok_or("ERR value is not a valid float")?,
+            None => 0.0,
+        };
+        let value = current + increment;
+        if !value.is_finite() {
+            return Err("ERR increment would produce NaN or Infinity".into());
+        }
+        let value = format_score(value);
+        state.put_string(key, Entity::Bulk(value.clone()));
+        Ok(value)
+    }
+
+    /// Appends to the string at `key`, creating it if needed, and returns the
+    /// new length.
+    pub(crate) async fn append(&self, key: Entity, value: Bytes) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let len = state
+            .string(&key)?
+            .map(|current| current.len())
+            .unwrap_or(0);
+        if len.saturating_add(value.len()) > MAX_STRING_LEN {
+            return Err(TOO_LONG.into());
+        }
+        state.edit_string(key, |buf| {
+            buf.extend_from_slice(&value);
+            buf.len()
+        })
+    }
+
+    pub(crate) async fn strlen(&self, key: &Entity) -> Result<usize, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state.string(key)?.map(|value| value.len()).unwrap_or(0))
+    }
+
+    /// The bytes between the inclusive offsets `start` and `end`, where
+    /// negative offsets count from the end.
+    pub(crate) async fn getrange(
+        &self,
+        key: &Entity,
+        start: i64,
+        end: i64,
+    ) -> Result<Bytes, CacheError> {
+        let state = self.shared.state.lock().await;
+        let value = state.string(key)?.unwrap_or_default();
+        Ok(match range_bounds(start, end, value.len()) {
+            Some((from, to)) => value.slice(from..to),
+            None => Bytes::new(),
+        })
+    }
+
+    /// Overwrites the string at `key` from `offset` on, zero-padding it first
+    /// if it is shorter than `offset`. Returns the new length.
+    pub(crate) async fn setrange(
+        &self,
+        key: Entity,
+        offset: usize,
+        value: Bytes,
+    ) -> Result<usize, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if value.is_empty() {
+            // Nothing to write: neither create the key nor pad it.
+            return Ok(state
+                .string(&key)?
+                .map(|current| current.len())
+                .unwrap_or(0));
+        }
+        let end = offset
+            .checked_add(value.len())
+            .filter(|end| *end <= MAX_STRING_LEN)
+            .ok_or(TOO_LONG)?;
+        state.edit_string(key, |buf| {
+            if buf.len() < end {
+                buf.resize(end, 0);
+            }
+            buf[offset..end].copy_from_slice(&value);
+            buf.len()
+        })
+    }
+
+    pub(crate) async fn getdel(&self, key: &Entity) -> Result<Option<Entity>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if state.string(key)?.is_none() {
+            return Ok(None);
+        }
+        Ok(state.remove(key).and_then(|entry| match entry.data {
+            Value::String(value) => Some(value),
+            _ => None,
+        }))
+    }
+
+    /// Reads the string at `key` and applies `expiry` to it.
+    pub(crate) async fn getex(
+        &self,
+        key: &Entity,
+        expiry: Expiry,
+    ) -> Result<Option<Entity>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let value = match state.entities.get(key).map(|entry| &entry.data) {
+            None => return Ok(None),
+            Some(Value::String(value)) => value.clone(),
+            Some(_) => return Err(CacheError::WrongType),
+        };
+        let notify = match expiry {
+            Expiry::Keep => false,
+            Expiry::Persist => state.set_expiration(key, None),
+            Expiry::At(when) => state.set_expiration(key, Some(when)),
+        };
+        drop(state);
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+        Ok(Some(value))
+    }
+
+    /// Replaces the string at `key`, dropping its TTL like `SET` does, and
+    /// returns the previous value.
+    pub(crate) async fn getset(
+        &self,
+        key: Entity,
+        value: Entity,
+    ) -> Result<Option<Entity>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let previous = match state.entities.get(&key).map(|entry| &entry.data) {
+            None => None,
+            Some(Value::String(previous)) => Some(previous.clone()),
+            Some(_) => return Err(CacheError::WrongType),
+        };
+        state.insert(key, Value::String(value), None);
+        Ok(previous)
+    }
+}
+
+impl State {
+    /// The raw bytes of the string at `key`, whichever frame type it was
+    /// written with.
+    pub(super) fn string(&self, key: &Entity) -> Result<Option<Bytes>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::String(value)) => Ok(Some(string_bytes(value))),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    /// Runs `edit` on the string at `key`, creating it empty if needed and
+    /// keeping its expiration. The bytes are only copied when something
+    /// else, such as a reply being written, still shares them.
+    pub(super) fn edit_string<R>(
+        &mut self,
+        key: Entity,
+        edit: impl FnOnce(&mut BytesMut) -> R,
+    ) -> Result<R, CacheError> {
+        let mut buf = match self.entities.get_mut(&key).map(|entry| &mut entry.data) {
+            None => BytesMut::new(),
+            Some(Value::String(Entity::Bulk(value))) => mem::take(value)
+                .try_into_mut()
+                .unwrap_or_else(BytesMut::from),
+            Some(Value::String(value)) => BytesMut::from(string_bytes(value)),
+            Some(_) => return Err(CacheError::WrongType),
+        };
+        let result = edit(&mut buf);
+        self.put_string(key, Entity::Bulk(buf.freeze()));
+        Ok(result)
+    }
+
+    /// Replaces the string at `key` in place, keeping its expiration.
+    pub(super) fn put_string(&mut self, key: Entity, value: Entity) {
+        match self.entities.get_mut(&key) {
+            Some(entry) => entry.data = Value::String(value),
+            None => {
+                self.entities.insert(
+                    key,
+                    Entry {
+                        data: Value::String(value),
+                        expires_at: None,
+                    },
+                );
+            }
+        }
+    }
+}
+
+pub(crate) fn string_bytes(value: &Entity) -> Bytes {
+    match value {
+        Entity::Simple(value) => Bytes::from(value.clone()),
+        Entity::Bulk(value) => value.clone(),
+        Entity::Integer(value) => Bytes::from(value.to_string()),
+        other => Bytes::from(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::storage::{list::End, test_support::key};
+
+    #[tokio::test]
+    async fn incr_by() {
+        let db = Db::new();
+
+        assert_eq!(1, db.incr_by(key("n"), 1).await.unwrap());
+        assert_eq!(-4, db.incr_by(key("n"), -5).await.unwrap());
+        assert!(db.incr_by(key("n"), i64::MIN).await.is_err());
+
+        db.set(key("n"), Entity::Integer(41), SetOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(42, db.incr_by(key("n"), 1).await.unwrap());
+
+        db.set(
+            key("s"),
+            Entity::Bulk(Bytes::from_static(b"abc")),
+            SetOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(db.incr_by(key("s"), 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn incr_by_float() {
+        let db = Db::new();
+        db.set(
+            key("f"),
+            Entity::Bulk(Bytes::from_static(b"10.5")),
+            SetOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(&b"10.6"[..], db.incr_by_float(key("f"), 0.1).await.unwrap());
+        assert_eq!(&b"3"[..], db.incr_by_float(key("f"), -7.6).await.unwrap());
+        assert!(db.incr_by_float(key("f"), f64::INFINITY).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn append_and_ranges() {
+        let db = Db::new();
+
+        assert_eq!(5, db.append(key("s"), Bytes::from("Hello")).await.unwrap());
+        assert_eq!(
+            11,
+            db.append(key("s"), Bytes::from(" World")).await.unwrap()
+        );
+        assert_eq!(&b"World"[..], db.getrange(&key("s"), -5, -1).await.unwrap());
+        assert_eq!(&b""[..], db.getrange(&key("s"), 20, 30).await.unwrap());
+
+        assert_eq!(
+            11,
+            db.setrange(key("s"), 6, Bytes::from("Redis"))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            Some(Entity::Bulk(Bytes::from("Hello Redis"))),
+            db.get(&key("s")).await.unwrap()
+        );
+
+        assert_eq!(0, db.setrange(key("new"), 3, Bytes::new()).await.unwrap());
+        assert_eq!(
+            5,
+            db.setrange(key("new"), 3, Bytes::from("ab")).await.unwrap()
+        );
+        assert_eq!(
+            Some(Entity::Bulk(Bytes::from_static(b"\0\0\0ab"))),
+            db.get(&key("new")).await.unwrap()
+        );
+        assert_eq!(5, db.strlen(&key("new")).await.unwrap());
+
+        // A value already handed out is not changed by later edits.
+        let shared = db.get(&key("new")).await.unwrap();
+        assert_eq!(6, db.append(key("new"), Bytes::from("c")).await.unwrap());
+        assert_eq!(Some(Entity::Bulk(Bytes::from_static(b"\0\0\0ab"))), shared);
+        assert!(
+            db.setrange(key("new"), MAX_STRING_LEN, Bytes::from("x"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn get_and_modify() {
+        let db = Db::new();
+        let value = Entity::Bulk(Bytes::from("v"));
+        let options = SetOptions {
+            expiry: Expiry::At(Instant::now() + Duration::from_secs(60)),
+            ..SetOptions::default()
+        };
+        db.set(key("k"), value.clone(), options).await.unwrap();
+
+        let past = Instant::now() - Duration::from_millis(1);
+        assert_eq!(
+            Some(value.clone()),
+            db.getex(&key("k"), Expiry::At(past)).await.unwrap()
+        );
+        assert_eq!(None, db.get(&key("k")).await.unwrap());
+
+        assert_eq!(None, db.getset(key("k"), value.clone()).await.unwrap());
+        assert_eq!(Some(value.clone()), db.getdel(&key("k")).await.unwrap());
+        assert_eq!(None, db.getdel(&key("k")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn set_options() {
+        let db = Db::new();
+        let value = |v: &'static str| Entity::Bulk(Bytes::from(v));
+        let nx = SetOptions {
+            condition: Some(SetCondition::Nx),
+            ..SetOptions::default()
+        };
+        let xx_get = SetOptions {
+            condition: Some(SetCondition::Xx),
+            get: true,
+            ..SetOptions::default()
+        };
+
+        assert_eq!(
+            (false, None),
+            db.set(key("k"), value("a"), xx_get).await.unwrap()
+        );
+        assert_eq!(
+            (true, None),
+            db.set(key("k"), value("a"), nx).await.unwrap()
+        );
+        assert_eq!(
+            (false, None),
+            db.set(key("k"), value("b"), nx).await.unwrap()
+        );
+        assert_eq!(
+            (true, Some(value("a"))),
+            db.set(key("k"), value("b"), xx_get).await.unwrap()
+        );
+
+        // KEEPTTL keeps the deadline that a plain SET would drop.
+        let when = Instant::now() + Duration::from_secs(60);
+        let ttl = SetOptions {
+            expiry: Expiry::At(when),
+            ..SetOptions::default()
+        };
+        let keep = SetOptions {
+            expiry: Expiry::Keep,
+            ..SetOptions::default()
+        };
+        db.set(key("k"), value("c"), ttl).await.unwrap();
+        db.set(key("k"), value("d"), keep).await.unwrap();
+        let expires_at = |db: &Db| {
+            let db = db.clone();
+            async move { db.shared.state.lock().await.entities[&key("k")].expires_at }
+        };
+        assert_eq!(Some(when), expires_at(&db).await);
+        db.set(key("k"), value("e"), SetOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(None, expires_at(&db).await);
+
+        let past = SetOptions {
+            expiry: Expiry::At(Instant::now()),
+            get: true,
+            ..SetOptions::default()
+        };
+        assert_eq!(
+            (true, Some(value("e"))),
+            db.set(key("k"), value("f"), past).await.unwrap()
+        );
+        assert_eq!(None, db.get(&key("k")).await.unwrap());
+
+        db.push(key("l"), vec![Bytes::from("x")], End::Left)
+            .await
+            .unwrap();
+        assert!(db.set(key("l"), value("g"), xx_get).await.is_err());
+        assert!(db.set(key("l"), value("g"), keep).await.unwrap().0);
+    }
+
+    #[tokio::test]
+    async fn multi_key() {
+        let db = Db::new();
+        let value = |v: &'static str| Entity::Bulk(Bytes::from(v));
+
+        assert!(
+            db.mset(vec![(key("a"), value("1")), (key("b"), value("2"))], false)
+                .await
+        );
+        assert!(
+            !db.mset(vec![(key("c"), value("3")), (key("b"), value("4"))], true)
+                .await
+        );
+        db.push(key("l"), vec![Bytes::from("x")], End::Left)
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![Some(value("1")), Some(value("2")), None, None],
+            db.mget(&[key("a"), key("b"), key("c"), key("l")]).await
+        );
+
+        assert_eq!(
+            3,
+            db.exists(&[key("a"), key("a"), key("l"), key("c")]).await
+        );
+        assert_eq!(2, db.del(&[key("a"), key("a"), key("l"), key("c")]).await);
+        assert_eq!(1, db.exists(&[key("a"), key("b"), key("l")]).await);
+    }
+}
@@ -0,0 +1,424 @@
+use std::{
+    borrow::Borrow,
+    fmt,
+    hash::{BuildHasher, Hash, Hasher, RandomState},
+    ops::Index,
+};
+
+/// The smallest number of buckets once the table holds anything.
+const MIN_BUCKETS: usize = 4;
+
+/// A hash map with chained buckets, for the collections `SCAN` and its
+/// siblings walk and `RANDOMKEY` samples: unlike `HashMap`, its buckets can be
+/// visited by index.
+///
+/// Scans follow Redis' `dictScan`. The cursor is a bucket index counted in
+/// reverse bit order, so a bucket that is split or merged by a resize between
+/// two calls keeps its place in the walk: anything present for the whole scan
+/// is returned at least once, and each call costs `O(count)`.
+#[derive(Clone)]
+pub(crate) struct Table<K, V> {
+    /// Empty, or a power of two in length.
+    buckets: Vec<Vec<(K, V)>>,
+    len: usize,
+    hasher: RandomState,
+}
+
+impl<K, V> Default for Table<K, V> {
+    fn default() -> Self {
+        Table {
+            buckets: vec![],
+            len: 0,
+            hasher: RandomState::new(),
+        }
+    }
+}
+
+impl<K, V> Table<K, V> {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.buckets
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key, value))
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Returns the entries of the buckets from `cursor` on, stopping once
+    /// there are at least `count` of them, and the cursor to resume from
+    /// (`0` once the scan is complete). A sparse table may return fewer:
+    /// at most `10 * count` empty buckets are visited per call, as in Redis.
+    pub(crate) fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<(&K, &V)>) {
+        let mut page = vec![];
+        if self.buckets.is_empty() {
+            return (0, page);
+        }
+        let count = count.max(1);
+        let mask = self.buckets.len() as u64 - 1;
+        let mut cursor = cursor;
+        let mut empty_visits = count.saturating_mul(10);
+        loop {
+            let bucket = &self.buckets[(cursor & mask) as usize];
+            if bucket.is_empty() {
+                empty_visits -= 1;
+            }
+            page.extend(bucket.iter().map(|(key, value)| (key, value)));
+            cursor = next_cursor(cursor, mask);
+            if cursor == 0 || page.len() >= count || empty_visits == 0 {
+                return (cursor, page);
+            }
+        }
+    }
+
+    /// An entry picked at random: a random non-empty bucket, then a random
+    /// entry in it. Buckets hold about one entry each, so this is close to
+    /// uniform.
+    pub(crate) fn random(&self) -> Option<(&K, &V)> {
+        if self.is_empty() {
+            return None;
+        }
+        let mask = self.buckets.len() as u64 - 1;
+        let mut rng = RandomState::new().build_hasher().finish();
+        loop {
+            // splitmix64
+            rng = rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut bits = rng;
+            bits = (bits ^ (bits >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            bits = (bits ^ (bits >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            bits ^= bits >> 31;
+            let bucket = &self.buckets[(bits & mask) as usize];
+            if !bucket.is_empty() {
+                let (key, value) = &bucket[(bits >> 32) as usize % bucket.len()];
+                return Some((key, value));
+            }
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Table<K, V> {
+    fn bucket_of<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        self.hasher.hash_one(key) as usize & (self.buckets.len() - 1)
+    }
+
+    /// The bucket and position of `key`, if present.
+    fn find<Q>(&self, key: &Q) -> Option<(usize, usize)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let bucket = self.bucket_of(key);
+        let position = self.buckets[bucket]
+            .iter()
+            .position(|(k, _)| k.borrow() == key)?;
+        Some((bucket, position))
+    }
+
+    pub(crate) fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (bucket, position) = self.find(key)?;
+        Some(&self.buckets[bucket][position].1)
+    }
+
+    pub(crate) fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (bucket, position) = self.find(key)?;
+        Some(&mut self.buckets[bucket][position].1)
+    }
+
+    pub(crate) fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Returns the value `key` had before, if any.
+    pub(crate) fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some((bucket, position)) = self.find(&key) {
+            return Some(std::mem::replace(
+                &mut self.buckets[bucket][position].1,
+                value,
+            ));
+        }
+        self.push(key, value);
+        None
+    }
+
+    /// The value at `key`, inserting `default()` first if there is none.
+    pub(crate) fn get_or_insert_with(&mut self, key: K, default: impl FnOnce() -> V) -> &mut V {
+        let (bucket, position) = match self.find(&key) {
+            Some(found) => found,
+            None => self.push(key, default()),
+        };
+        &mut self.buckets[bucket][position].1
+    }
+
+    pub(crate) fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (bucket, position) = self.find(key)?;
+        let (_, value) = self.buckets[bucket].swap_remove(position);
+        self.len -= 1;
+        self.shrink();
+        Some(value)
+    }
+
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&K, &mut V) -> bool) {
+        for bucket in &mut self.buckets {
+            bucket.retain_mut(|(key, value)| keep(key, value));
+        }
+        self.len = self.buckets.iter().map(Vec::len).sum();
+        self.shrink();
+    }
+
+    /// Appends a key known to be absent, growing the table to keep about one
+    /// entry per bucket. Returns where the entry ended up.
+    fn push(&mut self, key: K, value: V) -> (usize, usize) {
+        if self.len >= self.buckets.len() {
+            self.resize((self.buckets.len() * 2).max(MIN_BUCKETS));
+        }
+        let bucket = self.bucket_of(&key);
+        self.buckets[bucket].push((key, value));
+        self.len += 1;
+        (bucket, self.buckets[bucket].len() - 1)
+    }
+
+    /// Halves the table, or more, once it is mostly empty buckets, which
+    /// scans and `random` would otherwise have to skip.
+    fn shrink(&mut self) {
+        if self.buckets.len() > MIN_BUCKETS && self.len * 8 < self.buckets.len() {
+            self.resize((self.len * 2).next_power_of_two().max(MIN_BUCKETS));
+        }
+    }
+
+    fn resize(&mut self, buckets: usize) {
+        let old = std::mem::replace(&mut self.buckets, (0..buckets).map(|_| vec![]).collect());
+        for (key, value) in old.into_iter().flatten() {
+            let bucket = self.bucket_of(&key);
+            self.buckets[bucket].push((key, value));
+        }
+    }
+}
+
+/// Advances a scan cursor by one bucket. The bits under `mask` are counted
+/// from the top down, so the buckets a resize splits or merges stay next to
+/// each other in the walk.
+fn next_cursor(cursor: u64, mask: u64) -> u64 {
+    (cursor | !mask)
+        .reverse_bits()
+        .wrapping_add(1)
+        .reverse_bits()
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Table<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq + Borrow<Q>, Q: Hash + Eq + ?Sized, V> Index<&Q> for Table<K, V> {
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key in table")
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, V)> for Table<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut table = Table::new();
+        table.extend(iter);
+        table
+    }
+}
+
+impl<K: Hash + Eq, V> Extend<(K, V)> for Table<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V> IntoIterator for Table<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::iter::Flatten<std::vec::IntoIter<Vec<(K, V)>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.buckets.into_iter().flatten()
+    }
+}
+
+/// A set on top of `Table`, for `SSCAN`.
+#[derive(Clone, Default)]
+pub(crate) struct TableSet<K>(Table<K, ()>);
+
+impl<K> TableSet<K> {
+    pub(crate) fn new() -> Self {
+        TableSet(Table::new())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &K> {
+        self.0.keys()
+    }
+
+    /// As `Table::scan`.
+    pub(crate) fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<&K>) {
+        let (next, page) = self.0.scan(cursor, count);
+        (next, page.into_iter().map(|(member, _)| member).collect())
+    }
+}
+
+impl<K: Hash + Eq> TableSet<K> {
+    pub(crate) fn contains<Q>(&self, member: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.0.contains_key(member)
+    }
+
+    /// Returns whether `member` is new.
+    pub(crate) fn insert(&mut self, member: K) -> bool {
+        self.0.insert(member, ()).is_none()
+    }
+
+    /// Returns whether `member` was present.
+    pub(crate) fn remove<Q>(&mut self, member: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.0.remove(member).is_some()
+    }
+
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&K) -> bool) {
+        self.0.retain(|member, _| keep(member));
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for TableSet<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq> FromIterator<K> for TableSet<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        TableSet(iter.into_iter().map(|member| (member, ())).collect())
+    }
+}
+
+impl<K: Hash + Eq> Extend<K> for TableSet<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        self.0.extend(iter.into_iter().map(|member| (member, ())));
+    }
+}
+
+impl<K> IntoIterator for TableSet<K> {
+    type Item = K;
+    type IntoIter = std::iter::Map<<Table<K, ()> as IntoIterator>::IntoIter, fn((K, ())) -> K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter().map(|(member, _)| member)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_visits_everything_while_resizing() {
+        let mut table: Table<u32, u32> = (0..50).map(|i| (i, i)).collect();
+        let mut seen = vec![];
+        let mut cursor = 0;
+        loop {
+            let (next, page) = table.scan(cursor, 7);
+            seen.extend(page.into_iter().map(|(key, _)| *key));
+            // Grow past several doublings, then shrink back.
+            let len = table.len() as u32;
+            if len < 400 {
+                table.extend((len..len + 60).map(|i| (i, i)));
+            } else {
+                table.retain(|key, _| *key < 50);
+            }
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        for i in 0..50 {
+            assert!(seen.contains(&i));
+        }
+    }
+
+    #[test]
+    fn random_reaches_every_entry() {
+        let table: Table<u32, ()> = (0..8).map(|i| (i, ())).collect();
+        let mut seen = [false; 8];
+        for _ in 0..1000 {
+            let (key, _) = table.random().unwrap();
+            seen[*key as usize] = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+    }
+
+    #[test]
+    fn operations() {
+        let mut table = Table::new();
+        assert_eq!(None, table.random());
+        assert_eq!((0, vec![]), table.scan(0, 10));
+        for i in 0..100 {
+            assert_eq!(None, table.insert(i, i));
+        }
+        assert_eq!(Some(7), table.insert(7, 70));
+        assert_eq!(Some(&70), table.get(&7));
+        *table.get_or_insert_with(200, || 0) += 1;
+        *table.get_or_insert_with(200, || 0) += 1;
+        assert_eq!(Some(&2), table.get(&200));
+        assert_eq!(101, table.len());
+
+        for i in 0..100 {
+            assert_eq!(Some(if i == 7 { 70 } else { i }), table.remove(&i));
+        }
+        assert_eq!(None, table.remove(&7));
+        assert_eq!(Some((&200, &2)), table.random());
+        // Shrunk from 128 buckets as the table emptied.
+        assert_eq!(8, table.buckets.len());
+    }
+}
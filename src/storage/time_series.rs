@@ -0,0 +1,516 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, State, Value, entity::Entity, stream::unix_millis},
+};
+
+const MISSING_KEY: &str = "ERR TSDB: the key does not exist";
+
+/// How samples falling in one bucket are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Aggregator {
+    Avg,
+    Min,
+    Max,
+    Sum,
+    Count,
+}
+
+impl Aggregator {
+    pub(crate) fn parse(name: &str) -> Option<Aggregator> {
+        match &name.to_lowercase()[..] {
+            "avg" => Some(Aggregator::Avg),
+            "min" => Some(Aggregator::Min),
+            "max" => Some(Aggregator::Max),
+            "sum" => Some(Aggregator::Sum),
+            "count" => Some(Aggregator::Count),
+            _ => None,
+        }
+    }
+}
+
+/// Samples grouped into buckets of `bucket` milliseconds, aligned to the
+/// Unix epoch.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Aggregation {
+    pub(crate) aggregator: Aggregator,
+    pub(crate) bucket: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Accumulator {
+    sum: f64,
+    min: f64,
+    max: f64,
+    count: u64,
+}
+
+impl Accumulator {
+    fn new(value: f64) -> Accumulator {
+        Accumulator {
+            sum: value,
+            min: value,
+            max: value,
+            count: 1,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.count += 1;
+    }
+
+    fn value(&self, aggregator: Aggregator) -> f64 {
+        match aggregator {
+            Aggregator::Avg => self.sum / self.count as f64,
+            Aggregator::Min => self.min,
+            Aggregator::Max => self.max,
+            Aggregator::Sum => self.sum,
+            Aggregator::Count => self.count as f64,
+        }
+    }
+}
+
+/// `TS.CREATE` settings, also applied when `TS.ADD` creates a series.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SeriesOptions {
+    /// Samples older than this many milliseconds before the newest one are
+    /// dropped.
+    pub(crate) retention: Option<u64>,
+    pub(crate) labels: Vec<(String, String)>,
+}
+
+/// A `TS.RANGE`/`TS.MRANGE` query over `from..=to`.
+#[derive(Debug, Clone)]
+pub(crate) struct RangeQuery {
+    pub(crate) from: u64,
+    pub(crate) to: u64,
+    pub(crate) aggregation: Option<Aggregation>,
+    pub(crate) count: Option<usize>,
+}
+
+/// A `TS.MRANGE` filter: `label=value` or `label!=value`. An empty value
+/// matches series without the label, so `label!=` selects those having it.
+#[derive(Debug, Clone)]
+pub(crate) struct LabelFilter {
+    pub(crate) label: String,
+    pub(crate) value: String,
+    pub(crate) negate: bool,
+}
+
+impl LabelFilter {
+    fn matches(&self, labels: &[(String, String)]) -> bool {
+        let actual = labels
+            .iter()
+            .find(|(label, _)| *label == self.label)
+            .map(|(_, value)| value.as_str())
+            .unwrap_or("");
+        (actual == self.value) != self.negate
+    }
+}
+
+/// A compaction rule: samples added to the source are aggregated per bucket
+/// and each finished bucket is written to `destination`.
+#[derive(Debug, Clone)]
+struct Rule {
+    destination: Entity,
+    aggregation: Aggregation,
+    /// The bucket being filled: its start and the samples seen so far.
+    current: Option<(u64, Accumulator)>,
+}
+
+impl Rule {
+    /// Feeds one sample. Returns the previous bucket once a sample from a
+    /// later bucket arrives. Samples for buckets already written are ignored.
+    fn feed(&mut self, timestamp: u64, value: f64) -> Option<(u64, f64)> {
+        let start = timestamp - timestamp % self.aggregation.bucket;
+        match &mut self.current {
+            Some((current, acc)) if *current == start => {
+                acc.add(value);
+                None
+            }
+            Some((current, _)) if start < *current => None,
+            current => {
+                let done = current
+                    .take()
+                    .map(|(start, acc)| (start, acc.value(self.aggregation.aggregator)));
+                *current = Some((start, Accumulator::new(value)));
+                done
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TimeSeries {
+    samples: BTreeMap<u64, f64>,
+    options: SeriesOptions,
+    rules: Vec<Rule>,
+    /// The series this one is compacted from, if any.
+    source: Option<Entity>,
+}
+
+impl TimeSeries {
+    fn new(options: SeriesOptions) -> TimeSeries {
+        TimeSeries {
+            samples: BTreeMap::new(),
+            options,
+            rules: vec![],
+            source: None,
+        }
+    }
+
+    /// The oldest timestamp the retention still allows, measured back from
+    /// the newest sample so that historical data can be loaded.
+    fn cutoff(&self) -> u64 {
+        match (self.options.retention, self.samples.last_key_value()) {
+            (Some(retention), Some((newest, _))) => newest.saturating_sub(retention),
+            _ => 0,
+        }
+    }
+
+    /// Checks that a sample at `timestamp` is within the retention window
+    /// and not already present.
+    fn accepts(&self, timestamp: u64) -> Result<(), CacheError> {
+        if timestamp < self.cutoff() {
+            return Err("ERR TSDB: Timestamp is older than retention".into());
+        }
+        if self.samples.contains_key(&timestamp) {
+            return Err("ERR TSDB: duplicate sample, updates are not supported".into());
+        }
+        Ok(())
+    }
+
+    /// Drops the samples that fell out of the retention window.
+    fn trim(&mut self) {
+        let cutoff = self.cutoff();
+        while self
+            .samples
+            .first_key_value()
+            .is_some_and(|(oldest, _)| *oldest < cutoff)
+        {
+            self.samples.pop_first();
+        }
+    }
+
+    fn range(&self, query: &RangeQuery) -> Vec<(u64, f64)> {
+        if query.from > query.to {
+            return vec![];
+        }
+        let samples = self.samples.range(query.from..=query.to);
+        let limit = query.count.unwrap_or(usize::MAX);
+        let Some(aggregation) = query.aggregation else {
+            return samples
+                .map(|(ts, value)| (*ts, *value))
+                .take(limit)
+                .collect();
+        };
+
+        let mut buckets: Vec<(u64, Accumulator)> = vec![];
+        for (ts, value) in samples {
+            let start = ts - ts % aggregation.bucket;
+            if let Some((current, acc)) = buckets.last_mut()
+                && *current == start
+            {
+                acc.add(*value);
+            } else if buckets.len() < limit {
+                buckets.push((start, Accumulator::new(*value)));
+            } else {
+                break;
+            }
+        }
+        buckets
+            .into_iter()
+            .map(|(start, acc)| (start, acc.value(aggregation.aggregator)))
+            .collect()
+    }
+}
+
+impl Db {
+    pub(crate) async fn ts_create(
+        &self,
+        key: Entity,
+        options: SeriesOptions,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if state.entities.contains_key(&key) {
+            return Err("ERR TSDB: key already exists".into());
+        }
+        state.insert(key, Value::TimeSeries(TimeSeries::new(options)), None);
+        Ok(())
+    }
+
+    /// Appends a sample, creating the series with `options` if needed, and
+    /// feeds it to the compaction rules. Returns the sample's timestamp.
+    pub(crate) async fn ts_add(
+        &self,
+        key: Entity,
+        timestamp: Option<u64>,
+        value: f64,
+        options: SeriesOptions,
+    ) -> Result<u64, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let timestamp = timestamp.unwrap_or_else(unix_millis);
+        let series = match state.time_series_mut(&key)? {
+            Some(series) => series,
+            None => {
+                state.insert(
+                    key.clone(),
+                    Value::TimeSeries(TimeSeries::new(options)),
+                    None,
+                );
+                state.time_series_mut(&key)?.expect("series exists")
+            }
+        };
+        series.accepts(timestamp)?;
+
+        let compacted: Vec<_> = series
+            .rules
+            .iter_mut()
+            .filter_map(|rule| {
+                let (start, value) = rule.feed(timestamp, value)?;
+                Some((rule.destination.clone(), start, value))
+            })
+            .collect();
+        state.put_sample(&key, timestamp, value);
+        for (destination, start, value) in compacted {
+            state.put_sample(&destination, start, value);
+        }
+        Ok(timestamp)
+    }
+
+    pub(crate) async fn ts_range(
+        &self,
+        key: &Entity,
+        query: &RangeQuery,
+    ) -> Result<Vec<(u64, f64)>, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state.time_series(key)?.ok_or(MISSING_KEY)?.range(query))
+    }
+
+    /// Queries every series whose labels match all `filters`, ordered by key.
+    /// Returns each key with its labels and samples.
+    pub(crate) async fn ts_mrange(
+        &self,
+        filters: &[LabelFilter],
+        query: &RangeQuery,
+    ) -> Vec<(Entity, Vec<(String, String)>, Vec<(u64, f64)>)> {
+        let state = self.shared.state.lock().await;
+        let mut series: Vec<_> = state
+            .entities
+            .iter()
+            .filter_map(|(key, entry)| match &entry.data {
+                Value::TimeSeries(series)
+                    if filters
+                        .iter()
+                        .all(|filter| filter.matches(&series.options.labels)) =>
+                {
+                    Some((key, series))
+                }
+                _ => None,
+            })
+            .collect();
+        series.sort_by(|a, b| a.0.cmp(b.0));
+        series
+            .into_iter()
+            .map(|(key, series)| {
+                (
+                    key.clone(),
+                    series.options.labels.clone(),
+                    series.range(query),
+                )
+            })
+            .collect()
+    }
+
+    /// Compacts `source` into `destination`. Both must exist, and rules do
+    /// not chain: a destination neither has rules nor feeds other series.
+    pub(crate) async fn ts_createrule(
+        &self,
+        source: &Entity,
+        destination: &Entity,
+        aggregation: Aggregation,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if source == destination {
+            return Err("ERR TSDB: the source key and destination key should be different".into());
+        }
+        let target = state.time_series(destination)?.ok_or(MISSING_KEY)?;
+        if target.source.is_some() || !target.rules.is_empty() {
+            return Err("ERR TSDB: the destination key already has a src rule".into());
+        }
+        let series = state.time_series(source)?.ok_or(MISSING_KEY)?;
+        if series.source.is_some() {
+            return Err("ERR TSDB: the source key is itself a compaction destination".into());
+        }
+
+        let series = state.time_series_mut(source)?.expect("checked above");
+        series.rules.push(Rule {
+            destination: destination.clone(),
+            aggregation,
+            current: None,
+        });
+        let target = state.time_series_mut(destination)?.expect("checked above");
+        target.source = Some(source.clone());
+        Ok(())
+    }
+}
+
+impl State {
+    fn time_series(&self, key: &Entity) -> Result<Option<&TimeSeries>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::TimeSeries(series)) => Ok(Some(series)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn time_series_mut(&mut self, key: &Entity) -> Result<Option<&mut TimeSeries>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::TimeSeries(series)) => Ok(Some(series)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    /// Stores a sample in the series at `key`, if it still is one and the
+    /// sample is within its retention, and drops the samples a new newest
+    /// one pushes out of the window.
+    fn put_sample(&mut self, key: &Entity, timestamp: u64, value: f64) {
+        let Ok(Some(series)) = self.time_series_mut(key) else {
+            return;
+        };
+        if timestamp < series.cutoff() {
+            return;
+        }
+        series.samples.insert(timestamp, value);
+        series.trim();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::key;
+
+    fn query(from: u64, to: u64, aggregation: Option<(Aggregator, u64)>) -> RangeQuery {
+        RangeQuery {
+            from,
+            to,
+            aggregation: aggregation.map(|(aggregator, bucket)| Aggregation { aggregator, bucket }),
+            count: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn add_range_aggregate() {
+        let db = Db::new();
+        let options = SeriesOptions::default();
+        for (ts, value) in [(10, 1.0), (15, 3.0), (20, 5.0), (31, 7.0)] {
+            db.ts_add(key("lat"), Some(ts), value, options.clone())
+                .await
+                .unwrap();
+        }
+        assert!(db.ts_add(key("lat"), Some(15), 0.0, options).await.is_err());
+
+        let samples = db
+            .ts_range(&key("lat"), &query(11, 30, None))
+            .await
+            .unwrap();
+        assert_eq!(vec![(15, 3.0), (20, 5.0)], samples);
+
+        let buckets = db
+            .ts_range(
+                &key("lat"),
+                &query(0, u64::MAX, Some((Aggregator::Avg, 10))),
+            )
+            .await
+            .unwrap();
+        assert_eq!(vec![(10, 2.0), (20, 5.0), (30, 7.0)], buckets);
+        let buckets = db
+            .ts_range(&key("lat"), &query(0, 19, Some((Aggregator::Count, 100))))
+            .await
+            .unwrap();
+        assert_eq!(vec![(0, 2.0)], buckets);
+        assert!(db.ts_range(&key("nope"), &query(0, 1, None)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn compaction_and_mrange() {
+        let db = Db::new();
+        let labels = |endpoint: &str| SeriesOptions {
+            retention: None,
+            labels: vec![("endpoint".into(), endpoint.into())],
+        };
+        db.ts_create(key("raw"), labels("/a")).await.unwrap();
+        db.ts_create(key("max"), labels("/a")).await.unwrap();
+        db.ts_create(key("other"), labels("/b")).await.unwrap();
+        let aggregation = Aggregation {
+            aggregator: Aggregator::Max,
+            bucket: 10,
+        };
+        db.ts_createrule(&key("raw"), &key("max"), aggregation)
+            .await
+            .unwrap();
+        assert!(
+            db.ts_createrule(&key("max"), &key("other"), aggregation)
+                .await
+                .is_err()
+        );
+
+        for (ts, value) in [(1, 4.0), (5, 9.0), (12, 2.0), (25, 1.0)] {
+            db.ts_add(key("raw"), Some(ts), value, SeriesOptions::default())
+                .await
+                .unwrap();
+        }
+        // The bucket at 20 is still open.
+        let compacted = db
+            .ts_range(&key("max"), &query(0, 100, None))
+            .await
+            .unwrap();
+        assert_eq!(vec![(0, 9.0), (10, 2.0)], compacted);
+
+        let filters = [LabelFilter {
+            label: "endpoint".into(),
+            value: "/a".into(),
+            negate: false,
+        }];
+        let series = db.ts_mrange(&filters, &query(0, 100, None)).await;
+        let keys: Vec<_> = series.iter().map(|(key, _, _)| key.clone()).collect();
+        assert_eq!(vec![key("max"), key("raw")], keys);
+    }
+
+    #[tokio::test]
+    async fn retention_from_newest_sample() {
+        let db = Db::new();
+        let options = SeriesOptions {
+            retention: Some(1000),
+            labels: vec![],
+        };
+        // Historical samples are kept however old they are.
+        db.ts_add(key("ts"), Some(5000), 1.0, options.clone())
+            .await
+            .unwrap();
+        db.ts_add(key("ts"), Some(5800), 2.0, options.clone())
+            .await
+            .unwrap();
+        assert!(
+            db.ts_add(key("ts"), Some(3000), 0.0, options.clone())
+                .await
+                .is_err()
+        );
+
+        // A new newest sample pushes the first one out of the window.
+        db.ts_add(key("ts"), Some(6500), 3.0, options)
+            .await
+            .unwrap();
+        let samples = db
+            .ts_range(&key("ts"), &query(0, u64::MAX, None))
+            .await
+            .unwrap();
+        assert_eq!(vec![(5800, 2.0), (6500, 3.0)], samples);
+    }
+}
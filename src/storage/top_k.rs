@@ -0,0 +1,241 @@
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, State, Value, bloom::hash_item, entity::Entity},
+};
+
+const MISSING_KEY: &str = "ERR TopK: key does not exist";
+/// Distinguishes the fingerprint hash from the per-row hashes.
+const FINGERPRINT_SEED: u64 = u64::MAX;
+/// The most cells one sketch may have, 512 MiB worth.
+const MAX_CELLS: u64 = 1 << 25;
+/// The most items one sketch may track.
+const MAX_K: usize = 1 << 16;
+
+/// `TOPK.RESERVE` sizing, with RedisBloom's defaults.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TopKParams {
+    pub(crate) width: u64,
+    pub(crate) depth: u64,
+    pub(crate) decay: f64,
+}
+
+impl Default for TopKParams {
+    fn default() -> Self {
+        TopKParams {
+            width: 8,
+            depth: 7,
+            decay: 0.9,
+        }
+    }
+}
+
+/// Tracks the `k` most frequent items with HeavyKeeper: a count-min-like
+/// grid whose cells belong to one fingerprint at a time. Colliding items
+/// decay a cell's count with probability `decay^count`, so heavy hitters
+/// keep their cells while rare items wear each other out.
+#[derive(Debug, Clone)]
+pub(crate) struct TopK {
+    k: usize,
+    params: TopKParams,
+    cells: Box<[Cell]>,
+    /// The current top items and their estimated counts, unordered.
+    top: Vec<(Bytes, u64)>,
+    /// State of the generator behind the decay coin flips.
+    rng: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Cell {
+    fingerprint: u64,
+    count: u64,
+}
+
+impl TopK {
+    /// Fails if `k` is over `MAX_K` or the grid takes more than `MAX_CELLS`.
+    fn new(k: usize, params: TopKParams) -> Result<TopK, CacheError> {
+        if k > MAX_K {
+            return Err("ERR TopK: k is too large".into());
+        }
+        let cells = params
+            .width
+            .checked_mul(params.depth)
+            .filter(|cells| *cells <= MAX_CELLS)
+            .ok_or("ERR TopK: sketch is too large")?;
+        Ok(TopK {
+            k,
+            params,
+            cells: vec![Cell::default(); cells as usize].into_boxed_slice(),
+            top: Vec::with_capacity(k),
+            rng: 0x9e37_79b9_7f4a_7c15,
+        })
+    }
+
+    /// A uniform float in `[0, 1)`, from xorshift64*.
+    fn random(&mut self) -> f64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let bits = self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11;
+        bits as f64 / (1u64 << 53) as f64
+    }
+
+    /// Counts one occurrence of `item`. Returns the item it pushed out of the
+    /// top list, if any.
+    fn add(&mut self, item: &Bytes) -> Option<Bytes> {
+        let fingerprint = hash_item(item, FINGERPRINT_SEED);
+        let mut estimate = 0;
+        for row in 0..self.params.depth {
+            let index =
+                (row * self.params.width + hash_item(item, row) % self.params.width) as usize;
+            let cell = self.cells[index];
+            let cell = if cell.count == 0 || cell.fingerprint == fingerprint {
+                Cell {
+                    fingerprint,
+                    count: cell.count + 1,
+                }
+            } else if self.random() < self.params.decay.powf(cell.count as f64) {
+                match cell.count - 1 {
+                    0 => Cell {
+                        fingerprint,
+                        count: 1,
+                    },
+                    count => Cell { count, ..cell },
+                }
+            } else {
+                cell
+            };
+            self.cells[index] = cell;
+            if cell.fingerprint == fingerprint {
+                estimate = estimate.max(cell.count);
+            }
+        }
+
+        if let Some(entry) = self.top.iter_mut().find(|(member, _)| member == item) {
+            entry.1 = estimate;
+            return None;
+        }
+        if self.top.len() < self.k {
+            self.top.push((item.clone(), estimate));
+            return None;
+        }
+        let (min, _) = self
+            .top
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, count))| *count)?;
+        if estimate <= self.top[min].1 {
+            return None;
+        }
+        Some(std::mem::replace(&mut self.top[min], (item.clone(), estimate)).0)
+    }
+
+    /// The top items, most frequent first.
+    fn list(&self) -> Vec<(Bytes, u64)> {
+        let mut top = self.top.clone();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top
+    }
+}
+
+impl Db {
+    pub(crate) async fn topk_reserve(
+        &self,
+        key: Entity,
+        k: usize,
+        params: TopKParams,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if state.entities.contains_key(&key) {
+            return Err("ERR TopK: key already exists".into());
+        }
+        let top_k = TopK::new(k, params)?;
+        state.insert(key, Value::TopK(top_k), None);
+        Ok(())
+    }
+
+    /// Counts each of `items`, returning for each the item it expelled from
+    /// the top list, if any.
+    pub(crate) async fn topk_add(
+        &self,
+        key: &Entity,
+        items: &[Bytes],
+    ) -> Result<Vec<Option<Bytes>>, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let top_k = state.top_k_mut(key)?.ok_or(MISSING_KEY)?;
+        Ok(items.iter().map(|item| top_k.add(item)).collect())
+    }
+
+    /// The top items with their estimated counts, most frequent first.
+    pub(crate) async fn topk_list(&self, key: &Entity) -> Result<Vec<(Bytes, u64)>, CacheError> {
+        let state = self.shared.state.lock().await;
+        Ok(state.top_k(key)?.ok_or(MISSING_KEY)?.list())
+    }
+}
+
+impl State {
+    fn top_k(&self, key: &Entity) -> Result<Option<&TopK>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::TopK(top_k)) => Ok(Some(top_k)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn top_k_mut(&mut self, key: &Entity) -> Result<Option<&mut TopK>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::TopK(top_k)) => Ok(Some(top_k)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::key;
+
+    fn item(i: u64) -> Bytes {
+        Bytes::from(format!("item{}", i))
+    }
+
+    #[tokio::test]
+    async fn finds_heavy_hitters() {
+        let db = Db::new();
+        assert!(db.topk_list(&key("top")).await.is_err());
+        let params = TopKParams {
+            width: 50,
+            ..TopKParams::default()
+        };
+        db.topk_reserve(key("top"), 3, params).await.unwrap();
+        assert!(db.topk_reserve(key("top"), 3, params).await.is_err());
+        let huge = TopKParams {
+            width: 1 << 30,
+            depth: 1 << 29,
+            ..params
+        };
+        assert!(db.topk_reserve(key("big"), 3, huge).await.is_err());
+        assert!(
+            db.topk_reserve(key("big"), MAX_K + 1, params)
+                .await
+                .is_err()
+        );
+        assert_eq!(1, db.dbsize().await);
+
+        // Items 0, 1 and 2 are heavy; the rest show up once or twice each.
+        let mut stream = vec![];
+        for round in 0..200 {
+            stream.extend([item(0), item(1), item(1), item(2), item(2), item(2)]);
+            stream.push(item(100 + round % 150));
+        }
+        let expelled = db.topk_add(&key("top"), &stream).await.unwrap();
+        assert!(expelled.iter().flatten().all(|item| item.len() > 5));
+
+        let top = db.topk_list(&key("top")).await.unwrap();
+        let names: Vec<_> = top.iter().map(|(item, _)| item.clone()).collect();
+        assert_eq!(vec![item(2), item(1), item(0)], names);
+        assert!(top[0].1 >= 550 && top[0].1 <= 600);
+    }
+}
@@ -0,0 +1,619 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use bytes::Bytes;
+
+use crate::{
+    error::CacheError,
+    storage::{Db, State, Value, entity::Entity},
+};
+
+const MISSING_KEY: &str = "ERR no such vector index";
+/// Levels are drawn from a geometric distribution; this only guards against
+/// a freak draw.
+const MAX_LEVEL: usize = 16;
+
+/// How distances between vectors are measured. Smaller is always closer:
+/// cosine and inner product report `1 - similarity`, L2 the Euclidean
+/// distance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Metric {
+    Cosine,
+    L2,
+    InnerProduct,
+}
+
+impl Metric {
+    pub(crate) fn parse(name: &str) -> Option<Metric> {
+        match &name.to_uppercase()[..] {
+            "COSINE" => Some(Metric::Cosine),
+            "L2" => Some(Metric::L2),
+            "IP" => Some(Metric::InnerProduct),
+            _ => None,
+        }
+    }
+
+    fn distance(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            // Cosine vectors are normalized on the way in.
+            Metric::Cosine | Metric::InnerProduct => 1.0 - dot(a, b),
+            Metric::L2 => a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f32>()
+                .sqrt(),
+        }
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// HNSW tuning, with RediSearch's defaults: `m` links per node (twice that
+/// on the bottom layer), and the candidate list sizes used while inserting
+/// and, unless a query asks for more, while searching.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HnswParams {
+    pub(crate) m: usize,
+    pub(crate) ef_construction: usize,
+    pub(crate) ef_runtime: usize,
+}
+
+impl Default for HnswParams {
+    fn default() -> Self {
+        HnswParams {
+            m: 16,
+            ef_construction: 200,
+            ef_runtime: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Algorithm {
+    /// Exact search, comparing the query with every vector.
+    Flat,
+    /// Approximate search over a hierarchical navigable small world graph.
+    Hnsw(HnswParams),
+}
+
+/// `VINDEX.CREATE` arguments.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VectorParams {
+    pub(crate) dim: usize,
+    pub(crate) metric: Metric,
+    pub(crate) algorithm: Algorithm,
+}
+
+/// Embeddings of cached items, by item key, searchable by similarity.
+#[derive(Debug, Clone)]
+pub(crate) struct VectorIndex {
+    dim: usize,
+    metric: Metric,
+    store: Store,
+}
+
+#[derive(Debug, Clone)]
+enum Store {
+    Flat(HashMap<Bytes, Box<[f32]>>),
+    Hnsw(Hnsw),
+}
+
+impl VectorIndex {
+    fn new(params: VectorParams) -> VectorIndex {
+        let store = match params.algorithm {
+            Algorithm::Flat => Store::Flat(HashMap::new()),
+            Algorithm::Hnsw(params) => Store::Hnsw(Hnsw::new(params)),
+        };
+        VectorIndex {
+            dim: params.dim,
+            metric: params.metric,
+            store,
+        }
+    }
+
+    /// Checks the dimension of `vector` and normalizes it for the cosine
+    /// metric, which then reduces to a dot product.
+    fn prepare(&self, mut vector: Vec<f32>) -> Result<Box<[f32]>, CacheError> {
+        if vector.len() != self.dim {
+            return Err(format!(
+                "ERR vector has {} dimensions, the index expects {}",
+                vector.len(),
+                self.dim
+            )
+            .into());
+        }
+        if !vector.iter().all(|x| x.is_finite()) {
+            return Err("ERR vector components must be finite".into());
+        }
+        if self.metric == Metric::Cosine {
+            let norm = dot(&vector, &vector).sqrt();
+            if norm == 0.0 {
+                return Err("ERR zero vectors have no cosine similarity".into());
+            }
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        Ok(vector.into_boxed_slice())
+    }
+
+    /// Returns whether `member` is new; an existing member's vector is
+    /// replaced.
+    fn add(&mut self, member: Bytes, vector: Vec<f32>) -> Result<bool, CacheError> {
+        let vector = self.prepare(vector)?;
+        Ok(match &mut self.store {
+            Store::Flat(vectors) => vectors.insert(member, vector).is_none(),
+            Store::Hnsw(hnsw) => {
+                let existed = hnsw.remove(&member, self.metric);
+                hnsw.insert(member, vector, self.metric);
+                !existed
+            }
+        })
+    }
+
+    fn remove(&mut self, member: &[u8]) -> bool {
+        match &mut self.store {
+            Store::Flat(vectors) => vectors.remove(member).is_some(),
+            Store::Hnsw(hnsw) => hnsw.remove(member, self.metric),
+        }
+    }
+
+    /// The `k` members closest to `query`, closest first.
+    fn knn(
+        &self,
+        query: Vec<f32>,
+        k: usize,
+        ef: Option<usize>,
+    ) -> Result<Vec<(Bytes, f32)>, CacheError> {
+        let query = self.prepare(query)?;
+        Ok(match &self.store {
+            Store::Flat(vectors) => {
+                let mut found: Vec<_> = vectors
+                    .iter()
+                    .map(|(member, vector)| (member.clone(), self.metric.distance(&query, vector)))
+                    .collect();
+                found.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+                found.truncate(k);
+                found
+            }
+            Store::Hnsw(hnsw) => hnsw.search(&query, k, ef, self.metric),
+        })
+    }
+}
+
+/// A hierarchical navigable small world graph. Each node sits on layers
+/// `0..=level`, with levels drawn so that every layer holds a fraction of
+/// the one below; searches descend greedily from the sparse top layer and
+/// widen into a best-first search on the bottom one.
+#[derive(Debug, Clone)]
+struct Hnsw {
+    params: HnswParams,
+    nodes: Vec<Node>,
+    /// The live node of each member.
+    members: HashMap<Bytes, usize>,
+    /// A node on the top layer, where searches start.
+    entry: Option<usize>,
+    /// Removed nodes stay in the graph, since others link through them,
+    /// until they make up half of it and the graph is rebuilt.
+    deleted: usize,
+    /// State of the generator behind the level draws.
+    rng: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    member: Bytes,
+    vector: Box<[f32]>,
+    /// The neighbours on each of the node's layers, bottom first.
+    layers: Vec<Vec<usize>>,
+    deleted: bool,
+}
+
+/// A node and its distance to the vector being searched for, ordered by
+/// distance.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    distance: f32,
+    node: usize,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then_with(|| self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl Hnsw {
+    fn new(params: HnswParams) -> Hnsw {
+        Hnsw {
+            params,
+            nodes: vec![],
+            members: HashMap::new(),
+            entry: None,
+            deleted: 0,
+            rng: 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+
+    /// A level with `P(level >= l) = m^-l`, from xorshift64*.
+    fn random_level(&mut self) -> usize {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let bits = self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11;
+        // In (0, 1], so the logarithm is finite.
+        let uniform = 1.0 - bits as f64 / (1u64 << 53) as f64;
+        let level = -uniform.ln() / (self.params.m as f64).ln();
+        (level as usize).min(MAX_LEVEL)
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.params.m * 2
+        } else {
+            self.params.m
+        }
+    }
+
+    fn candidate(&self, query: &[f32], node: usize, metric: Metric) -> Candidate {
+        Candidate {
+            distance: metric.distance(query, &self.nodes[node].vector),
+            node,
+        }
+    }
+
+    /// Best-first search of one layer from `entry`, keeping the `ef` closest
+    /// nodes seen. Returns them closest first.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry: &[Candidate],
+        ef: usize,
+        layer: usize,
+        metric: Metric,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry.iter().map(|candidate| candidate.node).collect();
+        let mut candidates: BinaryHeap<_> = entry.iter().copied().map(Reverse).collect();
+        // A max-heap, so the farthest of the nodes found is on top.
+        let mut found: BinaryHeap<_> = entry.iter().copied().collect();
+        while let Some(Reverse(closest)) = candidates.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|far| closest > *far) {
+                break;
+            }
+            for &neighbour in &self.nodes[closest.node].layers[layer] {
+                if !visited.insert(neighbour) {
+                    continue;
+                }
+                let candidate = self.candidate(query, neighbour, metric);
+                if found.len() < ef || found.peek().is_some_and(|far| candidate < *far) {
+                    candidates.push(Reverse(candidate));
+                    found.push(candidate);
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+        found.into_sorted_vec()
+    }
+
+    /// Descends greedily from the entry point to `layer`.
+    fn descend(&self, query: &[f32], layer: usize, metric: Metric) -> Vec<Candidate> {
+        let Some(entry) = self.entry else {
+            return vec![];
+        };
+        let mut nearest = vec![self.candidate(query, entry, metric)];
+        for upper in (layer + 1..self.nodes[entry].layers.len()).rev() {
+            nearest = self.search_layer(query, &nearest, 1, upper, metric);
+        }
+        nearest
+    }
+
+    fn insert(&mut self, member: Bytes, vector: Box<[f32]>, metric: Metric) {
+        let level = self.random_level();
+        let node = self.nodes.len();
+        let mut nearest = self.descend(&vector, level, metric);
+        let top = self.entry.map(|entry| self.nodes[entry].layers.len() - 1);
+        self.nodes.push(Node {
+            member: member.clone(),
+            vector,
+            layers: vec![vec![]; level + 1],
+            deleted: false,
+        });
+        self.members.insert(member, node);
+        let Some(top) = top else {
+            self.entry = Some(node);
+            return;
+        };
+
+        let query = self.nodes[node].vector.clone();
+        for layer in (0..=level.min(top)).rev() {
+            nearest =
+                self.search_layer(&query, &nearest, self.params.ef_construction, layer, metric);
+            let neighbours: Vec<_> = nearest
+                .iter()
+                .take(self.params.m)
+                .map(|candidate| candidate.node)
+                .collect();
+            for &neighbour in &neighbours {
+                self.nodes[neighbour].layers[layer].push(node);
+                self.prune(neighbour, layer, metric);
+            }
+            self.nodes[node].layers[layer] = neighbours;
+        }
+        if level > top {
+            self.entry = Some(node);
+        }
+    }
+
+    /// Drops the farthest links of `node` on `layer` beyond the maximum.
+    fn prune(&mut self, node: usize, layer: usize, metric: Metric) {
+        let max = self.max_links(layer);
+        if self.nodes[node].layers[layer].len() <= max {
+            return;
+        }
+        let vector = &self.nodes[node].vector;
+        let mut links: Vec<_> = self.nodes[node].layers[layer]
+            .iter()
+            .map(|&link| self.candidate(vector, link, metric))
+            .collect();
+        links.sort();
+        links.truncate(max);
+        self.nodes[node].layers[layer] = links.into_iter().map(|link| link.node).collect();
+    }
+
+    fn remove(&mut self, member: &[u8], metric: Metric) -> bool {
+        let Some(node) = self.members.remove(member) else {
+            return false;
+        };
+        self.nodes[node].deleted = true;
+        self.deleted += 1;
+        if self.deleted * 2 > self.nodes.len() {
+            self.rebuild(metric);
+        }
+        true
+    }
+
+    fn rebuild(&mut self, metric: Metric) {
+        let nodes = std::mem::take(&mut self.nodes);
+        self.members.clear();
+        self.entry = None;
+        self.deleted = 0;
+        for node in nodes.into_iter().filter(|node| !node.deleted) {
+            self.insert(node.member, node.vector, metric);
+        }
+    }
+
+    fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: Option<usize>,
+        metric: Metric,
+    ) -> Vec<(Bytes, f32)> {
+        let entry = self.descend(query, 0, metric);
+        if entry.is_empty() {
+            return vec![];
+        }
+        // Removed nodes take up room among the candidates, so widen the
+        // search by their share of the graph.
+        let ef = ef.unwrap_or(self.params.ef_runtime).max(k);
+        let ef = ef * self.nodes.len() / self.members.len().max(1);
+        self.search_layer(query, &entry, ef, 0, metric)
+            .into_iter()
+            .filter(|candidate| !self.nodes[candidate.node].deleted)
+            .take(k)
+            .map(|candidate| {
+                let node = &self.nodes[candidate.node];
+                (node.member.clone(), candidate.distance)
+            })
+            .collect()
+    }
+}
+
+impl Db {
+    pub(crate) async fn vindex_create(
+        &self,
+        key: Entity,
+        params: VectorParams,
+    ) -> Result<(), CacheError> {
+        let mut state = self.shared.state.lock().await;
+        if state.entities.contains_key(&key) {
+            return Err("ERR index already exists".into());
+        }
+        state.insert(key, Value::Vector(VectorIndex::new(params)), None);
+        Ok(())
+    }
+
+    /// Sets the vector of `member`. Returns whether the member is new.
+    pub(crate) async fn vindex_add(
+        &self,
+        key: &Entity,
+        member: Bytes,
+        vector: Vec<f32>,
+    ) -> Result<bool, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        let index = state.vector_mut(key)?.ok_or(MISSING_KEY)?;
+        index.add(member, vector)
+    }
+
+    pub(crate) async fn vindex_del(
+        &self,
+        key: &Entity,
+        member: &Bytes,
+    ) -> Result<bool, CacheError> {
+        let mut state = self.shared.state.lock().await;
+        Ok(state
+            .vector_mut(key)?
+            .is_some_and(|index| index.remove(member)))
+    }
+
+    /// The `k` members nearest to `query` with their distances, nearest
+    /// first. `ef` overrides the HNSW search width; flat indexes are exact.
+    pub(crate) async fn vindex_knn(
+        &self,
+        key: &Entity,
+        query: Vec<f32>,
+        k: usize,
+        ef: Option<usize>,
+    ) -> Result<Vec<(Bytes, f32)>, CacheError> {
+        let state = self.shared.state.lock().await;
+        state.vector(key)?.ok_or(MISSING_KEY)?.knn(query, k, ef)
+    }
+}
+
+impl State {
+    fn vector(&self, key: &Entity) -> Result<Option<&VectorIndex>, CacheError> {
+        match self.entities.get(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(Value::Vector(index)) => Ok(Some(index)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+
+    fn vector_mut(&mut self, key: &Entity) -> Result<Option<&mut VectorIndex>, CacheError> {
+        match self.entities.get_mut(key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Value::Vector(index)) => Ok(Some(index)),
+            Some(_) => Err(CacheError::WrongType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::key;
+
+    fn member(i: usize) -> Bytes {
+        Bytes::from(format!("item:{}", i))
+    }
+
+    fn params(metric: Metric, algorithm: Algorithm) -> VectorParams {
+        VectorParams {
+            dim: 2,
+            metric,
+            algorithm,
+        }
+    }
+
+    /// Deterministic pseudo-random vectors in `[-1, 1)`.
+    fn vectors(count: usize, dim: usize) -> Vec<Vec<f32>> {
+        let mut state = 1u64;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (state >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+        };
+        (0..count)
+            .map(|_| (0..dim).map(|_| next()).collect())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn metrics() {
+        let db = Db::new();
+        for (name, metric) in [
+            ("cos", Metric::Cosine),
+            ("l2", Metric::L2),
+            ("ip", Metric::InnerProduct),
+        ] {
+            db.vindex_create(key(name), params(metric, Algorithm::Flat))
+                .await
+                .unwrap();
+            for (i, vector) in [[1.0, 0.0], [0.0, 2.0], [3.0, 3.0]].iter().enumerate() {
+                db.vindex_add(&key(name), member(i), vector.to_vec())
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let query = vec![1.0, 0.1];
+        let found = db.vindex_knn(&key("cos"), query.clone(), 2, None).await;
+        let names: Vec<_> = found.unwrap().into_iter().map(|(m, _)| m).collect();
+        assert_eq!(vec![member(0), member(2)], names);
+
+        let found = db.vindex_knn(&key("l2"), query.clone(), 3, None).await;
+        let found = found.unwrap();
+        assert_eq!(member(0), found[0].0);
+        assert!((found[0].1 - 0.1).abs() < 1e-6);
+        assert_eq!(member(1), found[1].0);
+
+        // 1 - dot product: the long vector wins.
+        let found = db.vindex_knn(&key("ip"), query, 1, None).await.unwrap();
+        assert_eq!(member(2), found[0].0);
+        assert!((found[0].1 + 2.3).abs() < 1e-6);
+
+        assert!(db.vindex_knn(&key("l2"), vec![1.0], 1, None).await.is_err());
+        assert!(
+            db.vindex_add(&key("cos"), member(9), vec![0.0, 0.0])
+                .await
+                .is_err()
+        );
+        assert!(db.vindex_del(&key("l2"), &member(0)).await.unwrap());
+        let found = db.vindex_knn(&key("l2"), vec![1.0, 0.1], 1, None).await;
+        assert_eq!(member(1), found.unwrap()[0].0);
+    }
+
+    #[tokio::test]
+    async fn hnsw_recall_matches_flat() {
+        let db = Db::new();
+        let dim = 16;
+        for (name, algorithm) in [
+            ("flat", Algorithm::Flat),
+            ("hnsw", Algorithm::Hnsw(HnswParams::default())),
+        ] {
+            let params = VectorParams {
+                dim,
+                metric: Metric::L2,
+                algorithm,
+            };
+            db.vindex_create(key(name), params).await.unwrap();
+            for (i, vector) in vectors(1000, dim).into_iter().enumerate() {
+                db.vindex_add(&key(name), member(i), vector).await.unwrap();
+            }
+            // Re-adding replaces, and removals leave tombstones behind.
+            for (i, vector) in vectors(1150, dim).into_iter().enumerate().skip(750) {
+                let added = db.vindex_add(&key(name), member(i % 1000), vector).await;
+                assert!(!added.unwrap());
+            }
+            for i in 0..250 {
+                assert!(db.vindex_del(&key(name), &member(i)).await.unwrap());
+            }
+        }
+
+        let removed: HashSet<_> = (0..250).map(member).collect();
+        let mut hits = 0;
+        for query in vectors(1200, dim).into_iter().skip(1150) {
+            let exact = db.vindex_knn(&key("flat"), query.clone(), 10, None).await;
+            let approximate = db.vindex_knn(&key("hnsw"), query, 10, Some(50)).await;
+            let approximate = approximate.unwrap();
+            assert_eq!(10, approximate.len());
+            assert!(approximate.iter().all(|(m, _)| !removed.contains(m)));
+            hits += exact
+                .unwrap()
+                .iter()
+                .filter(|found| approximate.contains(found))
+                .count();
+        }
+        assert!(hits >= 450, "recall {} / 500", hits);
+    }
+}